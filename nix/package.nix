@@ -24,7 +24,11 @@ rustPlatform.buildRustPackage rec {
 
   src = lib.cleanSource ../.;
 
-  cargoHash = "sha256-8p7aLdyjboD7W0OIFp9ssQHEH0IfVT2jFf9WipZ686E=";
+  # Vendor straight from the lock file so it can't drift from a hash
+  cargoLock = {
+    lockFile = ../Cargo.lock;
+    allowBuiltinFetchGit = true;
+  };
 
   nativeBuildInputs = [
     pkg-config
@@ -0,0 +1,304 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Radio alarms that start a station at set times, stored in `alarms.toml`
+//! next to `channels.toml`.
+
+use crate::channels;
+use chrono::{Datelike, NaiveDateTime, NaiveTime, TimeDelta, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Days of the week in display order.
+pub const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// Alarms missed by more than this, e.g. while suspended, are skipped.
+const MISSED_GRACE: TimeDelta = TimeDelta::minutes(10);
+
+/// Sound played when the station of an alarm can't be reached.
+const FALLBACK_SOUND: &[u8] = include_bytes!("../resources/alarm.wav");
+
+fn default_ramp_minutes() -> u32 {
+    2
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Alarm {
+    /// Id of the channel to play.
+    pub channel_id: String,
+    /// Local time to start at, written as "HH:MM".
+    #[serde(with = "hour_minute")]
+    pub time: NaiveTime,
+    /// Days to repeat on. Alarms without days go off once and are then disabled.
+    #[serde(default)]
+    pub weekdays: Vec<Weekday>,
+    /// Minutes to raise the volume from silence to the configured volume.
+    #[serde(default = "default_ramp_minutes")]
+    pub ramp_minutes: u32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+impl Alarm {
+    /// Whether the alarm repeats on some weekdays.
+    pub fn repeats(&self) -> bool {
+        !self.weekdays.is_empty()
+    }
+
+    /// The first time strictly after `after` the alarm goes off, ignoring `enabled`.
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        (0..=7)
+            .filter_map(|days| after.date().checked_add_days(chrono::Days::new(days)))
+            .filter(|date| !self.repeats() || self.weekdays.contains(&date.weekday()))
+            .map(|date| date.and_time(self.time))
+            .find(|time| *time > after)
+    }
+
+    pub fn ramp(&self) -> Duration {
+        Duration::from_secs(u64::from(self.ramp_minutes) * 60)
+    }
+
+    /// The repeat days in words, e.g. "Weekdays" or "Mon, Wed".
+    pub fn days_label(&self) -> String {
+        let has = |day: &Weekday| self.weekdays.contains(day);
+        let weekend = [Weekday::Sat, Weekday::Sun];
+        if !self.repeats() {
+            "Once".to_string()
+        } else if WEEKDAYS.iter().all(has) {
+            "Every day".to_string()
+        } else if WEEKDAYS[..5].iter().all(has) && !weekend.iter().any(has) {
+            "Weekdays".to_string()
+        } else if weekend.iter().all(has) && !WEEKDAYS[..5].iter().any(has) {
+            "Weekends".to_string()
+        } else {
+            WEEKDAYS.iter()
+                .filter(|day| has(day))
+                .map(|day| day.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    }
+}
+
+/// Serializes times as "HH:MM".
+pub(crate) mod hour_minute {
+    use chrono::NaiveTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&time.format("%H:%M"))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveTime, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.trim().parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct AlarmList {
+    #[serde(default)]
+    pub alarms: Vec<Alarm>,
+}
+
+#[derive(Error, Debug)]
+pub enum AlarmError {
+    #[error("Failed to access alarms file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse TOML: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("Failed to serialize TOML: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+/// Returns the full path to the alarms.toml file
+fn alarms_file_path() -> PathBuf {
+    channels::config_dir().join("alarms.toml")
+}
+
+/// Load alarms from the config file, or none if it doesn't exist yet.
+pub fn load_alarms() -> Result<AlarmList, AlarmError> {
+    let path = alarms_file_path();
+    if !path.exists() {
+        return Ok(AlarmList::default());
+    }
+    let content = fs::read_to_string(&path)?;
+    Ok(toml::from_str(&content)?)
+}
+
+/// Save alarms to the config file
+pub fn save_alarms(list: &AlarmList) -> Result<(), AlarmError> {
+    fs::create_dir_all(channels::config_dir())?;
+    let content = toml::to_string_pretty(list)?;
+    fs::write(alarms_file_path(), content)?;
+    Ok(())
+}
+
+/// URI of the bundled fallback sound, written to the cache directory on first use.
+pub fn fallback_sound_uri() -> Result<String, AlarmError> {
+    let dir = dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("cosmic-ext-applet-radio");
+    let path = dir.join("alarm.wav");
+    if fs::read(&path).ok().as_deref() != Some(FALLBACK_SOUND) {
+        fs::create_dir_all(&dir)?;
+        fs::write(&path, FALLBACK_SOUND)?;
+    }
+    url::Url::from_file_path(&path)
+        .map(String::from)
+        .map_err(|_| std::io::Error::other("Invalid fallback sound path").into())
+}
+
+/// Decides which alarms go off, given the local time on every check.
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    last_check: Option<NaiveDateTime>,
+}
+
+impl Scheduler {
+    /// Indices of the enabled alarms that went off since the previous check.
+    ///
+    /// The first check only records the time, so alarms are never raised
+    /// for times before the applet started.
+    pub fn due(&mut self, alarms: &[Alarm], now: NaiveDateTime) -> Vec<usize> {
+        let Some(last) = self.last_check.replace(now) else {
+            return Vec::new();
+        };
+        // The clock went backwards, e.g. a time zone change
+        if now <= last {
+            return Vec::new();
+        }
+
+        let since = last.max(now - MISSED_GRACE);
+        alarms.iter()
+            .enumerate()
+            .filter(|(_, alarm)| alarm.enabled)
+            .filter(|(_, alarm)| alarm.next_after(since).is_some_and(|time| time <= now))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    /// A fake clock, advanced by hand.
+    struct FakeClock(NaiveDateTime);
+
+    impl FakeClock {
+        /// Starts on Monday 2024-01-01 at the given time.
+        fn monday_at(hour: u32, minute: u32) -> Self {
+            Self(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(hour, minute, 0).unwrap())
+        }
+
+        fn advance(&mut self, delta: TimeDelta) -> NaiveDateTime {
+            self.0 += delta;
+            self.0
+        }
+    }
+
+    fn alarm(time: &str, weekdays: &[Weekday]) -> Alarm {
+        Alarm {
+            channel_id: "fip-radio".to_string(),
+            time: time.parse().unwrap(),
+            weekdays: weekdays.to_vec(),
+            ramp_minutes: 2,
+            enabled: true,
+        }
+    }
+
+    /// Run the scheduler in 15 second steps for `duration`, returning when each alarm went off.
+    fn run(
+        scheduler: &mut Scheduler,
+        clock: &mut FakeClock,
+        alarms: &[Alarm],
+        duration: TimeDelta,
+    ) -> Vec<(usize, NaiveDateTime)> {
+        let end = clock.0 + duration;
+        let mut fired = Vec::new();
+        scheduler.due(alarms, clock.0);
+        while clock.0 < end {
+            let now = clock.advance(TimeDelta::seconds(15));
+            fired.extend(scheduler.due(alarms, now).into_iter().map(|idx| (idx, now)));
+        }
+        fired
+    }
+
+    #[test]
+    fn test_weekday_alarm_fires_once_per_matching_day() {
+        let alarms = vec![alarm("07:30", &WEEKDAYS[..5])];
+        let mut scheduler = Scheduler::default();
+        let mut clock = FakeClock::monday_at(6, 0);
+
+        let fired = run(&mut scheduler, &mut clock, &alarms, TimeDelta::days(7));
+        let days: Vec<Weekday> = fired.iter().map(|(_, time)| time.weekday()).collect();
+        assert_eq!(days, WEEKDAYS[..5].to_vec());
+        for (idx, time) in fired {
+            assert_eq!(idx, 0);
+            assert_eq!(time.time(), "07:30:00".parse::<NaiveTime>().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_scheduler_skips_disabled_past_and_long_missed_alarms() {
+        let mut disabled = alarm("07:00", &[]);
+        disabled.enabled = false;
+        let alarms = vec![alarm("06:00", &[]), disabled, alarm("08:00", &[])];
+        let mut scheduler = Scheduler::default();
+        let mut clock = FakeClock::monday_at(6, 30);
+
+        // 06:00 already passed at startup, 07:00 is disabled
+        let fired = run(&mut scheduler, &mut clock, &alarms, TimeDelta::minutes(90));
+        assert_eq!(fired.iter().map(|(idx, _)| *idx).collect::<Vec<_>>(), vec![2]);
+
+        // Waking from suspend shortly after an alarm still raises it
+        let alarms = vec![alarm("09:00", &[])];
+        clock.0 = clock.0.date().and_hms_opt(8, 55, 0).unwrap();
+        scheduler.due(&alarms, clock.0);
+        assert_eq!(scheduler.due(&alarms, clock.advance(TimeDelta::minutes(12))), vec![0]);
+
+        // Much later it doesn't
+        clock.0 = clock.0.date().and_hms_opt(8, 55, 0).unwrap() + TimeDelta::days(1);
+        scheduler.due(&alarms, clock.0);
+        assert!(scheduler.due(&alarms, clock.advance(TimeDelta::hours(2))).is_empty());
+    }
+
+    #[test]
+    fn test_alarm_serialization() {
+        let list = AlarmList {
+            alarms: vec![alarm("07:05", &[Weekday::Mon, Weekday::Fri])],
+        };
+        let toml_str = toml::to_string_pretty(&list).unwrap();
+        assert!(toml_str.contains("time = \"07:05\""));
+        assert!(toml_str.contains("weekdays = [\n    \"Mon\",\n    \"Fri\",\n]"));
+        assert_eq!(toml::from_str::<AlarmList>(&toml_str).unwrap(), list);
+
+        // Optional fields fall back to their defaults
+        let loaded: AlarmList = toml::from_str(
+            "[[alarms]]\nchannel_id = \"fip-radio\"\ntime = \"6:45\"\n",
+        )
+        .unwrap();
+        assert_eq!(loaded.alarms[0].time, "06:45:00".parse::<NaiveTime>().unwrap());
+        assert_eq!(loaded.alarms[0].ramp_minutes, 2);
+        assert!(loaded.alarms[0].enabled);
+        assert_eq!(loaded.alarms[0].days_label(), "Once");
+        assert_eq!(alarm("07:00", &WEEKDAYS[..5]).days_label(), "Weekdays");
+        assert_eq!(alarm("07:00", &[Weekday::Mon, Weekday::Wed]).days_label(), "Mon, Wed");
+    }
+}
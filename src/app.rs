@@ -1,16 +1,34 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::channels::{self, Channel, ChannelList};
+use crate::alarms::{self, Alarm, AlarmList};
+use crate::channels::{self, Channel, ChannelError, ChannelList, StreamEndpoint};
 use crate::config::Config;
-use crate::player::Player;
-use cosmic::cosmic_config::{self, CosmicConfigEntry};
-use cosmic::iced::{window::Id, Limits, Subscription, Task};
+use crate::history::{self, ExportFormat, HistoryEntry, StationTrack};
+use crate::liked::{self, LikedList, LikedTrack};
+use crate::logos::{self, LogoSource};
+use crate::metadata::NowPlaying;
+use crate::mpris;
+use crate::notifications::{self, Notification};
+use crate::player::{self, Player, Reconnector, StreamTap};
+use crate::playlist;
+use crate::radio_browser::{self, SearchQuery, Station};
+use crate::recorder::{self, RecordingOptions};
+use crate::recording_schedule::{self, Schedule, ScheduledRecording};
+use crate::scrobbler::{self, Listen, ScrobbleSettings, Scrobbler, Service, SubmitFailure};
+use crate::shortcuts::{self, ShortcutAction};
+use crate::sleep_timer::{self, SleepTimer};
+use crate::station_lists::{self, ImportMode, ListFormat};
+use cosmic::cosmic_config::{self, ConfigSet, CosmicConfigEntry};
+use cosmic::iced::{mouse::ScrollDelta, window::Id, Limits, Subscription, Task};
 use cosmic::iced_winit::commands::popup::{destroy_popup, get_popup};
 use cosmic::prelude::*;
 use cosmic::widget;
 use futures_util::{SinkExt, StreamExt};
 use gstreamer::{MessageView, State};
 use gstreamer::prelude::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 /// The application model stores app-specific state used to describe its interface and
 /// drive its logic.
@@ -21,14 +39,133 @@ pub struct AppModel {
     popup: Option<Id>,
     /// Configuration data that persists between application runs.
     config: Config,
+    /// Handle used to write configuration changes.
+    config_handler: Option<cosmic_config::Config>,
+    /// Leftover touchpad scroll distance not yet applied to the volume.
+    scroll_accumulator: f32,
     /// The audio player.
     player: Option<Player>,
     /// Current playback state.
     play_state: State,
-    /// List of radio channels.
+    /// List of radio channels, ungrouped ones first and then group by group.
     channels: Vec<Channel>,
+    /// Names of the channel groups, in order.
+    groups: Vec<String>,
+    /// Filter for the channel list, showing every channel when empty.
+    station_query: String,
+    /// Index of the channel highlighted for keyboard control.
+    selected_station: Option<usize>,
     /// Index of the currently playing channel (None if stopped).
     current_channel_idx: Option<usize>,
+    /// Index of the most recently played channel, used by middle-click.
+    last_channel_idx: Option<usize>,
+    /// Track and stream information for the current channel.
+    now_playing: NowPlaying,
+    /// Sender for publishing state to the MPRIS service once it is running.
+    mpris: Option<tokio::sync::mpsc::UnboundedSender<mpris::State>>,
+    /// Sender for track change notifications while they are enabled.
+    notifier: Option<tokio::sync::mpsc::UnboundedSender<Notification>>,
+    /// When playback was paused, while paused.
+    paused_at: Option<Instant>,
+    /// How far playback is behind the live stream, as last read from the
+    /// timeshift buffer.
+    timeshift_delay: Duration,
+    /// Incremented for every channel started, to drop stale `StreamResolved` messages.
+    play_request: u64,
+    /// Reconnect attempts for the current channel.
+    reconnector: Reconnector,
+    /// Shown while reconnecting or after giving up.
+    reconnect_status: Option<ReconnectStatus>,
+    /// The recording in progress, if any.
+    recording: Option<Recording>,
+    /// Incremented for every recording started, to tell their subscriptions apart.
+    recording_id: u64,
+    /// Why the last recording stopped, if it failed.
+    recording_error: Option<String>,
+    /// Whether we're currently editing the recording settings.
+    editing_recording_settings: bool,
+    /// Recording settings form directory input.
+    recording_dir_input: String,
+    /// Recording settings form split-per-track toggle.
+    split_recordings_input: bool,
+    /// Whether we're currently editing the keyboard shortcuts.
+    editing_shortcuts: bool,
+    /// Shortcuts form inputs, one per `ShortcutAction::ALL`.
+    shortcut_inputs: Vec<String>,
+    /// Why the shortcuts couldn't be saved.
+    shortcuts_error: Option<String>,
+    /// Active sleep timer, if any.
+    sleep_timer: Option<SleepTimer>,
+    /// Custom sleep timer minutes input, while it is shown.
+    sleep_timer_custom: Option<String>,
+    /// Labels of the sleep timer dropdown, see `sleep_timer_options`.
+    sleep_timer_options: Vec<String>,
+    /// Radio alarms.
+    alarms: Vec<Alarm>,
+    /// Decides when alarms go off.
+    alarm_scheduler: alarms::Scheduler,
+    /// The alarm that started the current playback, until playback stops.
+    ringing_alarm: Option<RingingAlarm>,
+    /// Whether we're currently showing the alarm list.
+    showing_alarms: bool,
+    /// Alarm being added or edited.
+    alarm_form: Option<AlarmForm>,
+    /// Programmes to record.
+    schedule: Vec<ScheduledRecording>,
+    /// Recordings started by the schedule, one per channel.
+    scheduled_captures: Vec<ScheduledCapture>,
+    /// Shown when a scheduled recording had to be skipped.
+    schedule_notice: Option<String>,
+    /// Whether we're currently showing the recording schedule.
+    showing_schedule: bool,
+    /// Scheduled recording being added or edited.
+    schedule_form: Option<ScheduleForm>,
+    /// Played tracks, oldest first.
+    history: Vec<HistoryEntry>,
+    /// Whether we're currently showing the recently played tracks.
+    showing_history: bool,
+    /// Search text for the recently played tracks.
+    history_query: String,
+    /// Result of the last history export.
+    history_notice: Option<String>,
+    /// Tracks the user liked, oldest first.
+    liked: Vec<LikedTrack>,
+    /// Whether we're currently showing the liked tracks.
+    showing_liked: bool,
+    /// Shown when liking or unliking a track could not be saved.
+    liked_error: Option<String>,
+    /// Whether we're currently showing station list import and export.
+    showing_station_lists: bool,
+    /// Path of the station list to import.
+    import_path: String,
+    /// Whether imported stations are merged into or replace the list.
+    import_mode: ImportMode,
+    /// Result of the last import or export.
+    station_list_notice: Option<String>,
+    /// Scrobbling settings and the track being timed, once loaded.
+    scrobbler: Option<Scrobbler>,
+    /// Listens waiting to be submitted, oldest first.
+    scrobble_queue: Vec<Listen>,
+    /// Whether listens from the queue are being submitted.
+    scrobble_submitting: bool,
+    /// Why the last submission failed.
+    scrobble_error: Option<String>,
+    /// Scrobbling settings being edited.
+    scrobble_form: Option<ScrobbleForm>,
+    /// Whether we're currently searching the station directory.
+    browsing: bool,
+    /// Station directory search filters.
+    browse_query: SearchQuery,
+    /// Directory server input, empty for the public directory.
+    browse_server_input: String,
+    /// Stations found by the last search.
+    browse_results: Vec<Station>,
+    /// Id of the latest search, older results are ignored.
+    browse_request: u64,
+    /// Whether a search is running.
+    browse_searching: bool,
+    /// Why the last search failed.
+    browse_error: Option<String>,
     /// Error message to display (if any).
     error_message: Option<String>,
     /// Whether we're currently in "add station" mode.
@@ -37,6 +174,8 @@ pub struct AppModel {
     new_station_name: String,
     /// New station URL input.
     new_station_url: String,
+    /// New station optional details.
+    new_station_details: StationDetailsForm,
     /// Validation error for new station form.
     new_station_error: Option<String>,
     /// Index of station being edited (None if not editing).
@@ -45,10 +184,20 @@ pub struct AppModel {
     edit_station_name: String,
     /// Edit form station URL input.
     edit_station_url: String,
+    /// Edit form fallback stream URL inputs, in order.
+    edit_station_fallbacks: Vec<String>,
+    /// Edit form optional details.
+    edit_station_details: StationDetailsForm,
     /// Validation error for edit form.
     edit_station_error: Option<String>,
     /// Index of station pending deletion (for confirmation).
     deleting_station_idx: Option<usize>,
+    /// Index of the station being dragged to a new position.
+    dragging_station_idx: Option<usize>,
+    /// Where the dragged station would be dropped.
+    drop_target: Option<DropTarget>,
+    /// Station logos by `LogoSource::key`.
+    logos: HashMap<String, LogoState>,
 }
 
 impl Default for AppModel {
@@ -65,24 +214,354 @@ impl Default for AppModel {
             core: Default::default(),
             popup: Default::default(),
             config: Default::default(),
+            config_handler: None,
+            scroll_accumulator: 0.0,
             player,
             play_state: State::Null,
             channels: Vec::new(),
+            groups: Vec::new(),
+            station_query: String::new(),
+            selected_station: None,
             current_channel_idx: None,
+            last_channel_idx: None,
+            now_playing: NowPlaying::default(),
+            mpris: None,
+            notifier: None,
+            paused_at: None,
+            timeshift_delay: Duration::ZERO,
+            play_request: 0,
+            reconnector: Reconnector::default(),
+            reconnect_status: None,
+            recording: None,
+            recording_id: 0,
+            recording_error: None,
+            editing_recording_settings: false,
+            recording_dir_input: String::new(),
+            split_recordings_input: false,
+            editing_shortcuts: false,
+            shortcut_inputs: Vec::new(),
+            shortcuts_error: None,
+            sleep_timer: None,
+            sleep_timer_custom: None,
+            sleep_timer_options: sleep_timer_options(),
+            alarms: Vec::new(),
+            alarm_scheduler: alarms::Scheduler::default(),
+            ringing_alarm: None,
+            showing_alarms: false,
+            alarm_form: None,
+            schedule: Vec::new(),
+            scheduled_captures: Vec::new(),
+            schedule_notice: None,
+            showing_schedule: false,
+            schedule_form: None,
+            history: Vec::new(),
+            showing_history: false,
+            history_query: String::new(),
+            history_notice: None,
+            liked: Vec::new(),
+            showing_liked: false,
+            liked_error: None,
+            showing_station_lists: false,
+            import_path: String::new(),
+            import_mode: ImportMode::Merge,
+            station_list_notice: None,
+            scrobbler: None,
+            scrobble_queue: Vec::new(),
+            scrobble_submitting: false,
+            scrobble_error: None,
+            scrobble_form: None,
+            browsing: false,
+            browse_query: SearchQuery::default(),
+            browse_server_input: String::new(),
+            browse_results: Vec::new(),
+            browse_request: 0,
+            browse_searching: false,
+            browse_error: None,
             error_message: None,
             adding_station: false,
             new_station_name: String::new(),
             new_station_url: String::new(),
+            new_station_details: StationDetailsForm::default(),
             new_station_error: None,
             editing_station_idx: None,
             edit_station_name: String::new(),
             edit_station_url: String::new(),
+            edit_station_fallbacks: Vec::new(),
+            edit_station_details: StationDetailsForm::default(),
             edit_station_error: None,
             deleting_station_idx: None,
+            dragging_station_idx: None,
+            drop_target: None,
+            logos: HashMap::new(),
         }
     }
 }
 
+/// Where a dragged station can be dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DropTarget {
+    /// Take the place of the station at this index.
+    Station(usize),
+    /// Go to the end of this group, `None` for the ungrouped stations.
+    Group(Option<String>),
+}
+
+/// Keys acting on the highlighted station of the list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StationKey {
+    /// Enter plays or stops it
+    Play,
+    Delete,
+    Edit,
+    Favourite,
+    /// Alt+Up moves it up within its section
+    MoveUp,
+    MoveDown,
+}
+
+/// Progress of reconnecting to a dropped stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReconnectStatus {
+    /// Waiting for or running the given attempt.
+    Retrying { attempt: u32 },
+    /// All attempts failed for the channel at this index.
+    GaveUp { channel_idx: usize },
+    /// The stream of the channel at this index failed while paused.
+    LostWhilePaused { channel_idx: usize },
+}
+
+/// An alarm that went off.
+#[derive(Debug, Clone, Copy)]
+struct RingingAlarm {
+    started: Instant,
+    /// How long the volume takes to reach the configured volume, zero once it has.
+    ramp: Duration,
+    /// Whether the fallback sound plays because the station failed.
+    fallback: bool,
+}
+
+impl RingingAlarm {
+    /// Factor to scale the volume by at `now`, rising from 0.0 to 1.0 over the ramp.
+    fn volume_factor(&self, now: Instant) -> f64 {
+        if self.ramp.is_zero() {
+            return 1.0;
+        }
+        (now.duration_since(self.started).as_secs_f64() / self.ramp.as_secs_f64()).min(1.0)
+    }
+}
+
+/// Inputs of the add/edit alarm form.
+#[derive(Debug, Clone, Default)]
+struct AlarmForm {
+    /// Index of the alarm being edited, `None` when adding.
+    idx: Option<usize>,
+    time: String,
+    /// Index of the selected channel.
+    channel: Option<usize>,
+    /// Channel names for the station dropdown.
+    station_names: Vec<String>,
+    /// Selected days, in `alarms::WEEKDAYS` order.
+    weekdays: [bool; 7],
+    ramp_minutes: String,
+    error: Option<String>,
+}
+
+/// Inputs of the add/edit scheduled recording form.
+#[derive(Debug, Clone, Default)]
+struct ScheduleForm {
+    /// Index of the scheduled recording being edited, `None` when adding.
+    idx: Option<usize>,
+    title: String,
+    /// Index of the selected channel.
+    channel: Option<usize>,
+    /// Channel names for the station dropdown.
+    station_names: Vec<String>,
+    /// Date of a one-off recording, empty for weekly ones.
+    date: String,
+    /// Selected days, in `alarms::WEEKDAYS` order.
+    weekdays: [bool; 7],
+    start: String,
+    end: String,
+    error: Option<String>,
+}
+
+/// A station logo, once requested.
+#[derive(Debug, Clone)]
+enum LogoState {
+    Loading,
+    Ready(widget::icon::Handle),
+    /// Not available, the initials badge is shown instead.
+    Failed,
+}
+
+/// Optional station details, shared by the add and edit forms.
+#[derive(Debug, Clone, Default)]
+struct StationDetailsForm {
+    homepage: String,
+    favicon: String,
+    /// Comma separated.
+    tags: String,
+    country: String,
+    language: String,
+    /// Codec of the main stream.
+    codec: String,
+    /// Bitrate of the main stream in kbps.
+    bitrate: String,
+    notes: String,
+    /// Name of the group, empty for none.
+    group: String,
+}
+
+/// A field of the station details form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StationDetail {
+    Homepage,
+    Favicon,
+    Tags,
+    Country,
+    Language,
+    Codec,
+    Bitrate,
+    Notes,
+    Group,
+}
+
+impl StationDetailsForm {
+    fn from_channel(channel: &Channel) -> Self {
+        let stream = channel.streams.first();
+        Self {
+            homepage: channel.homepage.clone().unwrap_or_default(),
+            favicon: channel.favicon.clone().unwrap_or_default(),
+            tags: channel.tags.join(", "),
+            country: channel.country.clone().unwrap_or_default(),
+            language: channel.language.clone().unwrap_or_default(),
+            codec: stream.and_then(|s| s.codec.clone()).unwrap_or_default(),
+            bitrate: stream.and_then(|s| s.bitrate).map(|b| b.to_string()).unwrap_or_default(),
+            notes: channel.notes.clone().unwrap_or_default(),
+            group: channel.group.clone().unwrap_or_default(),
+        }
+    }
+
+    fn set(&mut self, field: StationDetail, value: String) {
+        let input = match field {
+            StationDetail::Homepage => &mut self.homepage,
+            StationDetail::Favicon => &mut self.favicon,
+            StationDetail::Tags => &mut self.tags,
+            StationDetail::Country => &mut self.country,
+            StationDetail::Language => &mut self.language,
+            StationDetail::Codec => &mut self.codec,
+            StationDetail::Bitrate => &mut self.bitrate,
+            StationDetail::Notes => &mut self.notes,
+            StationDetail::Group => &mut self.group,
+        };
+        *input = value;
+    }
+
+    /// Check the inputs, returning a message for the first invalid one.
+    fn validate(&self) -> Result<(), String> {
+        let homepage = self.homepage.trim();
+        if !homepage.is_empty() && !homepage.starts_with("http://") && !homepage.starts_with("https://") {
+            return Err("Homepage must start with http:// or https://".to_string());
+        }
+        let logo = self.favicon.trim();
+        if !logo.is_empty() && !logos::is_valid_logo(logo) {
+            return Err("Logo must be a URL or an absolute file path".to_string());
+        }
+        let bitrate = self.bitrate.trim();
+        if !bitrate.is_empty() && !bitrate.parse::<u32>().is_ok_and(|b| b > 0) {
+            return Err("Bitrate must be a whole number of kbps".to_string());
+        }
+        Ok(())
+    }
+
+    /// The group the station should be in.
+    fn group(&self) -> Option<String> {
+        let group = self.group.trim();
+        (!group.is_empty()).then(|| group.to_string())
+    }
+
+    /// Copy the validated details to `channel`, except for the group.
+    fn apply(&self, channel: &mut Channel) {
+        let optional = |value: &str| {
+            let value = value.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        channel.homepage = optional(&self.homepage);
+        channel.favicon = optional(&self.favicon);
+        channel.tags = channels::parse_tags(&self.tags);
+        channel.country = optional(&self.country);
+        channel.language = optional(&self.language);
+        channel.notes = optional(&self.notes);
+        if let Some(stream) = channel.streams.first_mut() {
+            stream.codec = optional(&self.codec);
+            stream.bitrate = self.bitrate.trim().parse().ok();
+        }
+    }
+}
+
+/// Inputs of the scrobbling settings form.
+#[derive(Debug, Clone, Default)]
+struct ScrobbleForm {
+    enabled: bool,
+    /// Index in `Service::ALL`.
+    service: usize,
+    service_names: Vec<String>,
+    /// Empty for the default of the service.
+    endpoint: String,
+    token: String,
+    api_key: String,
+    api_secret: String,
+    /// Last.fm session key from an earlier sign-in.
+    session_key: String,
+    username: String,
+    /// Only used to sign in to Last.fm, never saved.
+    password: String,
+    min_play_seconds: String,
+    filters: Vec<FilterInput>,
+    /// "All stations" followed by the channel names.
+    station_options: Vec<String>,
+    /// Whether we're waiting for a Last.fm session key.
+    signing_in: bool,
+    error: Option<String>,
+}
+
+/// A scrobble filter being edited.
+#[derive(Debug, Clone, Default)]
+struct FilterInput {
+    /// `None` for all stations.
+    station_id: Option<String>,
+    pattern: String,
+}
+
+/// A channel being recorded because of the schedule.
+#[derive(Debug, Clone)]
+struct ScheduledCapture {
+    channel_id: String,
+    /// Title of the programme.
+    title: String,
+    /// When the programme ends.
+    end: chrono::NaiveDateTime,
+    /// The running recording, `None` while resolving, retrying or skipped.
+    recording: Option<Recording>,
+    /// Whether the stream URL is being resolved.
+    resolving: bool,
+    /// Failed attempts to record the current programme.
+    failures: u32,
+}
+
+/// A recording of the current stream.
+#[derive(Debug, Clone)]
+struct Recording {
+    id: u64,
+    uri: String,
+    /// The player's stream, `None` for scheduled recordings, which
+    /// connect to `uri` themselves.
+    tap: Option<StreamTap>,
+    options: RecordingOptions,
+    /// The file currently being written.
+    file: Option<PathBuf>,
+}
+
 /// Messages emitted by the application and its widgets.
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -90,6 +569,7 @@ pub enum Message {
     PopupClosed(Id),
     SubscriptionChannel,
     UpdateConfig(Config),
+    /// Stop if playing, otherwise play the last played channel
     TogglePlayback,
     PlayerStateChanged(State),
     MetadataUpdated(gstreamer::TagList),
@@ -97,8 +577,22 @@ pub enum Message {
     PlayChannel(usize),
     /// Stop playback and clear current channel
     StopPlayback,
+    /// Pause playback while the stream keeps buffering
+    PausePlayback,
+    /// Resume from where playback was paused
+    ResumePlayback,
+    /// Drop the buffered audio and continue with the live stream
+    JumpToLive,
+    /// Read how far playback is behind the live stream
+    TimeshiftTick,
     /// Channels loaded from file
-    ChannelsLoaded(Vec<Channel>),
+    ChannelsLoaded(ChannelList),
+    /// Station filter text changed
+    StationQueryChanged(String),
+    /// Highlight the next (`true`) or previous station in the list
+    SelectStation(bool),
+    /// A key for the highlighted station was pressed
+    StationKey(StationKey),
     /// Error loading channels
     ChannelError(String),
     /// Toggle add station form visibility
@@ -107,6 +601,8 @@ pub enum Message {
     NewStationNameChanged(String),
     /// New station URL changed
     NewStationUrlChanged(String),
+    /// New station optional detail changed
+    NewStationDetailChanged(StationDetail, String),
     /// Save the new station
     SaveNewStation,
     /// Cancel adding station
@@ -117,16 +613,443 @@ pub enum Message {
     EditStationNameChanged(String),
     /// Edit form station URL changed
     EditStationUrlChanged(String),
+    /// Add an empty fallback URL to the edit form
+    AddEditStationFallback,
+    /// Edit form fallback URL at this position changed
+    EditStationFallbackChanged(usize, String),
+    /// Remove the fallback URL at this position from the edit form
+    RemoveEditStationFallback(usize),
+    /// Edit form optional detail changed
+    EditStationDetailChanged(StationDetail, String),
     /// Save edited station
     SaveEditStation,
     /// Cancel editing station
     CancelEditStation,
+    /// Mark or unmark a station as a favourite
+    ToggleFavourite(usize),
+    /// Open the homepage of a station in the browser
+    OpenHomepage(usize),
+    /// Move a station one place up within its section
+    MoveStationUp(usize),
+    /// Move a station one place down within its section
+    MoveStationDown(usize),
+    /// Start dragging a station by its handle
+    StartDragStation(usize),
+    /// The dragged station is over the station at this index
+    DragOverStation(usize),
+    /// The dragged station is over the header of this group
+    DragOverGroup(Option<String>),
+    /// Collapse or expand the group with this name
+    ToggleGroup(String),
+    /// The mouse button was released, drop the dragged station
+    DropStation,
     /// Start deleting a station (show confirmation)
     StartDeleteStation(usize),
     /// Confirm and delete station
     ConfirmDeleteStation,
     /// Cancel deletion
     CancelDeleteStation,
+    /// Volume slider moved (percent)
+    SetVolume(u8),
+    /// Volume slider released, persist the volume
+    SaveVolume,
+    /// Toggle mute
+    ToggleMute,
+    /// Mouse wheel scrolled over the panel button
+    PanelScrolled(ScrollDelta),
+    /// Play the next channel in the list
+    NextChannel,
+    /// Play the previous channel in the list
+    PreviousChannel,
+    /// The MPRIS service is running and accepts state updates
+    MprisReady(tokio::sync::mpsc::UnboundedSender<mpris::State>),
+    /// Command received from an MPRIS client
+    Mpris(mpris::Command),
+    /// The stream failed or ended unexpectedly
+    StreamError(String),
+    /// Retry the current channel after a stream error of the play request with this number
+    Reconnect(u64),
+    /// Stream URLs resolved for the play request with this number
+    StreamResolved(u64, Result<Vec<String>, String>),
+    /// Start or stop recording the current stream
+    ToggleRecording,
+    /// Progress of the recording with this id
+    RecordingEvent(u64, recorder::Event),
+    /// The recording with this id stopped on its own
+    RecordingFinished(u64, Result<(), String>),
+    /// Open the recording settings form
+    StartEditRecordingSettings,
+    /// Recording directory input changed
+    RecordingDirChanged(String),
+    /// Split-per-track toggle changed
+    SplitRecordingsChanged(bool),
+    /// Save the recording settings
+    SaveRecordingSettings,
+    /// Close the recording settings form without saving
+    CancelRecordingSettings,
+    /// Open the keyboard shortcuts form
+    StartEditShortcuts,
+    /// Global shortcut input changed
+    ShortcutChanged(ShortcutAction, String),
+    /// Save and register the global shortcuts
+    SaveShortcuts,
+    /// Close the keyboard shortcuts form without saving
+    CancelShortcuts,
+    /// Sleep timer option picked from `sleep_timer_options`
+    SelectSleepTimer(usize),
+    /// Custom sleep timer minutes input changed
+    SleepTimerCustomChanged(String),
+    /// Start the sleep timer with the custom number of minutes
+    StartCustomSleepTimer,
+    /// Turn the sleep timer off
+    CancelSleepTimer,
+    /// Update the sleep timer fade and check whether it expired
+    SleepTimerTick,
+    /// The live stream ended, or the alarm sound finished
+    StreamEnded,
+    /// Alarms loaded from file
+    AlarmsLoaded(Vec<Alarm>),
+    /// Show or hide the alarm list
+    ToggleAlarms,
+    /// Open the form to add an alarm
+    StartAddAlarm,
+    /// Open the form to edit the alarm at this index
+    StartEditAlarm(usize),
+    /// Alarm form time changed
+    AlarmTimeChanged(String),
+    /// Alarm form station picked, by channel index
+    AlarmStationSelected(usize),
+    /// Alarm form weekday toggled, by index in `alarms::WEEKDAYS`
+    ToggleAlarmWeekday(usize),
+    /// Alarm form ramp-up minutes changed
+    AlarmRampChanged(String),
+    /// Save the alarm form
+    SaveAlarm,
+    /// Close the alarm form without saving
+    CancelAlarm,
+    /// Enable or disable the alarm at this index
+    SetAlarmEnabled(usize, bool),
+    /// Delete the alarm at this index
+    DeleteAlarm(usize),
+    /// Check whether an alarm or scheduled recording is due
+    ScheduleTick,
+    /// Raise the volume of a ringing alarm
+    AlarmRampTick,
+    /// Scheduled recordings loaded from file
+    ScheduleLoaded(Vec<ScheduledRecording>),
+    /// Stream URLs resolved for the scheduled recording of this channel id
+    ScheduledCaptureResolved(String, Result<Vec<String>, String>),
+    /// Show or hide the recording schedule
+    ToggleSchedule,
+    /// Open the form to schedule a recording
+    StartAddScheduledRecording,
+    /// Open the form to edit the scheduled recording at this index
+    StartEditScheduledRecording(usize),
+    /// Schedule form title changed
+    ScheduleTitleChanged(String),
+    /// Schedule form station picked, by channel index
+    ScheduleStationSelected(usize),
+    /// Schedule form date changed
+    ScheduleDateChanged(String),
+    /// Schedule form weekday toggled, by index in `alarms::WEEKDAYS`
+    ToggleScheduleWeekday(usize),
+    /// Schedule form start time changed
+    ScheduleStartChanged(String),
+    /// Schedule form end time changed
+    ScheduleEndChanged(String),
+    /// Save the schedule form
+    SaveScheduledRecording,
+    /// Close the schedule form without saving
+    CancelScheduledRecording,
+    /// Enable or disable the scheduled recording at this index
+    SetScheduledRecordingEnabled(usize, bool),
+    /// Delete the scheduled recording at this index
+    DeleteScheduledRecording(usize),
+    /// Hide the skipped recording notice
+    DismissScheduleNotice,
+    /// Played tracks loaded from file
+    HistoryLoaded(Vec<HistoryEntry>),
+    /// Show or hide the recently played tracks
+    ToggleHistory,
+    /// Recently played search text changed
+    HistoryQueryChanged(String),
+    /// Export the recently played tracks matching the search
+    ExportHistory(ExportFormat),
+    /// Enable or disable notifications on track changes
+    SetTrackNotifications(bool),
+    /// The notification client connected
+    NotifierReady(tokio::sync::mpsc::UnboundedSender<Notification>),
+    /// Liked tracks loaded from file
+    LikedLoaded(Vec<LikedTrack>),
+    /// Like the current track, or unlike it if already liked
+    ToggleLikeCurrent,
+    /// Show or hide the liked tracks
+    ToggleLiked,
+    /// Copy the liked track at this index to the clipboard
+    CopyLikedTrack(usize),
+    /// Search the web for the liked track at this index
+    SearchLikedTrack(usize),
+    /// Remove the liked track at this index
+    DeleteLikedTrack(usize),
+    /// Hide the error about saving liked tracks
+    DismissLikedError,
+    /// Show or hide station list import and export
+    ToggleStationLists,
+    /// Path of the station list to import changed
+    ImportPathChanged(String),
+    /// Choose whether to merge or replace on import
+    ImportModeChanged(ImportMode),
+    /// Import stations from the file at the import path
+    ImportStations,
+    /// Export the stations in this format
+    ExportStations(ListFormat),
+    /// Scrobbling settings and queued listens loaded from file
+    ScrobblingLoaded(ScrobbleSettings, Vec<Listen>),
+    /// A now playing update was sent
+    NowPlayingSent(Result<(), String>),
+    /// This many listens from the front of the queue were submitted
+    ScrobblesSubmitted(usize, Result<(), SubmitFailure>),
+    /// Submit queued listens again
+    RetryScrobbles,
+    /// Open the scrobbling settings
+    StartEditScrobbling,
+    /// Scrobbling form enabled toggle changed
+    ScrobblingEnabledChanged(bool),
+    /// Scrobbling form service picked, by index in `Service::ALL`
+    ScrobbleServiceSelected(usize),
+    /// Scrobbling form endpoint changed
+    ScrobbleEndpointChanged(String),
+    /// Scrobbling form ListenBrainz token changed
+    ScrobbleTokenChanged(String),
+    /// Scrobbling form Last.fm API key changed
+    ScrobbleApiKeyChanged(String),
+    /// Scrobbling form Last.fm API secret changed
+    ScrobbleApiSecretChanged(String),
+    /// Scrobbling form Last.fm username changed
+    ScrobbleUsernameChanged(String),
+    /// Scrobbling form Last.fm password changed
+    ScrobblePasswordChanged(String),
+    /// Scrobbling form minimum play time changed
+    ScrobbleMinPlayChanged(String),
+    /// Add an empty filter to the scrobbling form
+    AddScrobbleFilter,
+    /// Station of the filter at this position picked, by index in the station options
+    ScrobbleFilterStationSelected(usize, usize),
+    /// Pattern of the filter at this position changed
+    ScrobbleFilterPatternChanged(usize, String),
+    /// Remove the filter at this position from the scrobbling form
+    RemoveScrobbleFilter(usize),
+    /// Save the scrobbling form
+    SaveScrobbling,
+    /// Last.fm sign-in finished with a session key
+    ScrobbleSessionReceived(Result<String, String>),
+    /// Close the scrobbling form without saving
+    CancelScrobbling,
+    /// Show or hide the station directory search
+    ToggleBrowse,
+    /// Directory search name changed
+    BrowseNameChanged(String),
+    /// Directory search tag changed
+    BrowseTagChanged(String),
+    /// Directory search country changed
+    BrowseCountryChanged(String),
+    /// Directory search language changed
+    BrowseLanguageChanged(String),
+    /// Directory search codec changed
+    BrowseCodecChanged(String),
+    /// Directory server changed
+    BrowseServerChanged(String),
+    /// Search the station directory
+    SearchStations,
+    /// Results of the directory search with this id
+    StationsFound(u64, Result<Vec<Station>, String>),
+    /// Add the found station at this index to the channels
+    AddBrowsedStation(usize),
+    /// A station logo was loaded, keyed by `LogoSource::key`
+    LogoLoaded(String, Result<PathBuf, String>),
+}
+
+/// Maximum number of characters of the track shown in the panel.
+const PANEL_TRACK_MAX_CHARS: usize = 32;
+
+/// Volume change in percent for one scroll step over the panel button.
+const VOLUME_SCROLL_STEP: u8 = 5;
+
+/// Touchpad scroll distance in pixels that counts as one scroll step.
+const SCROLL_PIXELS_PER_STEP: f32 = 20.0;
+
+/// Sleep timer choices: off, one per `sleep_timer::PRESET_MINUTES`, end of track and custom.
+fn sleep_timer_options() -> Vec<String> {
+    let mut options = vec!["Off".to_string()];
+    options.extend(sleep_timer::PRESET_MINUTES.iter().map(|minutes| format!("{} minutes", minutes)));
+    options.push("End of track".to_string());
+    options.push("Custom…".to_string());
+    options
+}
+
+/// How often to check whether an alarm or scheduled recording is due.
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How often to retry submitting queued listens.
+const SCROBBLE_RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Most recently played tracks shown in the history view.
+const HISTORY_VIEW_LIMIT: usize = 100;
+
+/// Failed attempts after which a scheduled recording is skipped.
+const SCHEDULED_RECORDING_MAX_FAILURES: u32 = 3;
+
+/// Longest alarm volume ramp in minutes.
+const ALARM_MAX_RAMP_MINUTES: u32 = 60;
+
+/// Longest custom sleep timer in minutes.
+const SLEEP_TIMER_MAX_MINUTES: u64 = 24 * 60;
+
+/// Width and height of station logos.
+const LOGO_SIZE: u16 = 24;
+
+/// Longest notes shown under a station name.
+const ROW_NOTES_MAX_CHARS: usize = 60;
+
+/// Format a duration as "m:ss", or "h:mm:ss" from an hour up.
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Toggle buttons for the days of the week, in `alarms::WEEKDAYS` order.
+fn view_weekday_picker<'a>(selected: &[bool; 7], on_toggle: fn(usize) -> Message) -> Element<'a, Message> {
+    let days = alarms::WEEKDAYS.iter().enumerate().map(|(i, day)| {
+        widget::button::text(day.to_string())
+            .class(if selected[i] {
+                cosmic::theme::Button::Suggested
+            } else {
+                cosmic::theme::Button::Standard
+            })
+            .on_press(on_toggle(i))
+            .into()
+    });
+    widget::flex_row(days.collect())
+        .row_spacing(5)
+        .column_spacing(5)
+        .into()
+}
+
+/// A round badge with the initials of a station, shown when it has no logo.
+fn view_initials_badge<'a>(name: &str) -> Element<'a, Message> {
+    let (r, g, b) = logos::badge_color(name);
+    widget::container(widget::text::text(logos::initials(name)).size(10))
+        .center(cosmic::iced::Length::Fixed(f32::from(LOGO_SIZE)))
+        .class(cosmic::theme::Container::custom(move |_theme| cosmic::iced::widget::container::Style {
+            background: Some(cosmic::iced::Color::from_rgb8(r, g, b).into()),
+            text_color: Some(cosmic::iced::Color::WHITE),
+            border: cosmic::iced::Border {
+                radius: (f32::from(LOGO_SIZE) / 2.0).into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }))
+        .into()
+}
+
+/// Inputs for the optional details of a station.
+fn view_station_details<'a>(
+    details: &'a StationDetailsForm,
+    on_change: fn(StationDetail, String) -> Message,
+) -> Element<'a, Message> {
+    let input = move |placeholder: &'a str, value: &'a str, field: StationDetail| {
+        widget::text_input(placeholder, value)
+            .on_input(move |value| on_change(field, value))
+    };
+    widget::column()
+        .spacing(5)
+        .push(widget::text::text("Details (optional):").size(12))
+        .push(input("Homepage, e.g., https://example.com", &details.homepage, StationDetail::Homepage))
+        .push(input("Logo URL or file, e.g., https://example.com/logo.png", &details.favicon, StationDetail::Favicon))
+        .push(input("Tags, e.g., jazz, eclectic", &details.tags, StationDetail::Tags))
+        .push(
+            widget::row()
+                .spacing(5)
+                .push(input("Country", &details.country, StationDetail::Country))
+                .push(input("Language", &details.language, StationDetail::Language))
+        )
+        .push(
+            widget::row()
+                .spacing(5)
+                .push(input("Codec, e.g., AAC", &details.codec, StationDetail::Codec))
+                .push(input("Bitrate (kbps)", &details.bitrate, StationDetail::Bitrate))
+        )
+        .push(input("Notes", &details.notes, StationDetail::Notes))
+        .push(input("Group, e.g., Jazz", &details.group, StationDetail::Group))
+        .into()
+}
+
+/// The configured global shortcut for `action`, empty for none.
+fn configured_shortcut(config: &Config, action: ShortcutAction) -> &str {
+    match action {
+        ShortcutAction::PlayPause => &config.play_pause_shortcut,
+        ShortcutAction::NextStation => &config.next_station_shortcut,
+        ShortcutAction::PreviousStation => &config.previous_station_shortcut,
+    }
+}
+
+/// A directory search filter input, searching on Enter.
+fn browse_filter<'a>(
+    placeholder: &'a str,
+    value: &'a str,
+    on_input: fn(String) -> Message,
+) -> widget::TextInput<'a, Message> {
+    widget::text_input(placeholder, value)
+        .on_input(on_input)
+        .on_submit(|_| Message::SearchStations)
+}
+
+/// The file to import: `~` is the home directory, and relative paths are
+/// taken from the export directory.
+fn import_file_path(input: &str) -> PathBuf {
+    let input = input.trim();
+    let path = match (input.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(input),
+    };
+    if path.is_absolute() {
+        path
+    } else {
+        channels::export_directory().join(path)
+    }
+}
+
+/// Parse a custom sleep timer length in whole minutes.
+fn parse_sleep_minutes(input: &str) -> Option<u64> {
+    input.trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|minutes| (1..=SLEEP_TIMER_MAX_MINUTES).contains(minutes))
+}
+
+/// Open `url` with the default application, waiting for `xdg-open` in the
+/// background so it doesn't linger as a zombie process.
+fn open_url(url: &str) {
+    match std::process::Command::new("xdg-open").arg(url).spawn() {
+        Ok(mut child) => {
+            std::thread::spawn(move || child.wait());
+        }
+        Err(e) => tracing::error!("Failed to open {}: {}", url, e),
+    }
+}
+
+/// Shorten text to at most `max` characters, adding an ellipsis if cut.
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut shortened: String = text.chars().take(max.saturating_sub(1)).collect();
+    shortened.push('…');
+    shortened
 }
 
 /// Helper methods for AppModel
@@ -165,6 +1088,9 @@ impl AppModel {
                 )
         );
 
+        // Optional details
+        form = form.push(view_station_details(&self.new_station_details, Message::NewStationDetailChanged));
+
         // Error message
         if let Some(error) = &self.new_station_error {
             form = form.push(
@@ -185,11 +1111,110 @@ impl AppModel {
                     widget::button::text("Cancel")
                         .on_press(Message::CancelAddStation)
                 )
+                .push(
+                    widget::button::text("Browse Directory")
+                        .on_press(Message::ToggleBrowse)
+                )
         );
 
         self.core.applet.popup_container(form).into()
     }
 
+    /// View for the station directory search
+    fn view_browse(&self) -> Element<'_, Message> {
+        let mut content = widget::column()
+            .padding(10)
+            .spacing(10);
+
+        // Header
+        content = content.push(
+            widget::text::text("Browse Stations")
+                .size(16)
+        );
+
+        // Search filters, Enter in any of them searches
+        content = content.push(
+            widget::column()
+                .spacing(5)
+                .push(browse_filter("Name", &self.browse_query.name, Message::BrowseNameChanged))
+                .push(
+                    widget::row()
+                        .spacing(5)
+                        .push(browse_filter("Tag, e.g., jazz", &self.browse_query.tag, Message::BrowseTagChanged))
+                        .push(browse_filter("Country or code", &self.browse_query.country, Message::BrowseCountryChanged))
+                )
+                .push(
+                    widget::row()
+                        .spacing(5)
+                        .push(browse_filter("Language", &self.browse_query.language, Message::BrowseLanguageChanged))
+                        .push(browse_filter("Codec, e.g., AAC", &self.browse_query.codec, Message::BrowseCodecChanged))
+                )
+        );
+
+        content = content.push(
+            widget::row()
+                .spacing(10)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(
+                    widget::button::text(if self.browse_searching { "Searching…" } else { "Search" })
+                        .class(cosmic::theme::Button::Suggested)
+                        .on_press_maybe((!self.browse_searching).then_some(Message::SearchStations))
+                )
+                .push(
+                    widget::button::text("Back")
+                        .on_press(Message::ToggleBrowse)
+                )
+        );
+
+        if let Some(error) = &self.browse_error {
+            content = content.push(
+                widget::text::text(format!("Error: {}", error))
+                    .size(12)
+            );
+        }
+
+        // Results
+        let mut results = widget::column().spacing(5);
+        for (idx, station) in self.browse_results.iter().enumerate() {
+            let added = self.channels.iter()
+                .any(|c| c.streams.iter().any(|s| s.uri == station.stream_url()));
+            results = results.push(
+                widget::row()
+                    .spacing(5)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(
+                        widget::column()
+                            .width(cosmic::iced::Length::Fill)
+                            .push(widget::text::text(station.name.trim()))
+                            .push(widget::text::text(station.details_line()).size(12))
+                    )
+                    .push(
+                        widget::button::text(if added { "Added" } else { "Add" })
+                            .on_press_maybe((!added).then_some(Message::AddBrowsedStation(idx)))
+                    )
+            );
+        }
+        if !self.browse_results.is_empty() {
+            content = content.push(
+                widget::scrollable(results).height(cosmic::iced::Length::Fixed(300.0))
+            );
+        }
+
+        // Directory server
+        content = content.push(
+            widget::column()
+                .spacing(5)
+                .push(widget::text::text("Directory Server:").size(12))
+                .push(
+                    widget::text_input(radio_browser::DEFAULT_BASE_URL, &self.browse_server_input)
+                        .on_input(Message::BrowseServerChanged)
+                        .on_submit(|_| Message::SearchStations)
+                )
+        );
+
+        self.core.applet.popup_container(content).into()
+    }
+
     /// View for the edit station form
     fn view_edit_station_form(&self, _idx: usize) -> Element<'_, Message> {
         let mut form = widget::column()
@@ -224,6 +1249,34 @@ impl AppModel {
                 )
         );
 
+        // Fallback URL inputs, tried in order when the stream above fails
+        let mut fallbacks = widget::column()
+            .spacing(5)
+            .push(widget::text::text("Fallback URLs:").size(12));
+        for (i, url) in self.edit_station_fallbacks.iter().enumerate() {
+            fallbacks = fallbacks.push(
+                widget::row()
+                    .spacing(5)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(
+                        widget::text_input("e.g., http://mirror.example.com/stream.mp3", url)
+                            .on_input(move |url| Message::EditStationFallbackChanged(i, url))
+                    )
+                    .push(
+                        widget::button::icon(widget::icon::from_name("list-remove-symbolic"))
+                            .on_press(Message::RemoveEditStationFallback(i))
+                    )
+            );
+        }
+        fallbacks = fallbacks.push(
+            widget::button::text("+ Add Fallback URL")
+                .on_press(Message::AddEditStationFallback)
+        );
+        form = form.push(fallbacks);
+
+        // Optional details
+        form = form.push(view_station_details(&self.edit_station_details, Message::EditStationDetailChanged));
+
         // Error message
         if let Some(error) = &self.edit_station_error {
             form = form.push(
@@ -282,387 +1335,3822 @@ impl AppModel {
         self.core.applet.popup_container(content).into()
     }
 
-    /// View for the channel list
-    fn view_channel_list(&self) -> Element<'_, Message> {
-        // Build the channel list
-        let mut content_list = widget::column()
-            .padding(5)
-            .spacing(2);
-
-        // Add header with current status
-        let header_text = if let Some(idx) = self.current_channel_idx {
-            if let Some(channel) = self.channels.get(idx) {
-                format!("Now Playing: {}", channel.name)
-            } else {
-                "Internet Radio".to_string()
-            }
-        } else {
-            "Internet Radio".to_string()
-        };
+    /// View for the recording settings form
+    fn view_recording_settings_form(&self) -> Element<'_, Message> {
+        let mut form = widget::column()
+            .padding(10)
+            .spacing(10);
 
-        content_list = content_list.push(
-            widget::text::text(header_text)
+        // Header
+        form = form.push(
+            widget::text::text("Recording Settings")
                 .size(16)
         );
 
-        // Add stop button if playing
-        if self.play_state == State::Playing {
-            content_list = content_list.push(
-                widget::settings::item(
-                    "Stop Playback",
-                    widget::button::icon(widget::icon::from_name("media-playback-stop-symbolic"))
-                        .on_press(Message::StopPlayback),
+        // Directory input, empty for the default
+        let default_dir = recorder::default_directory();
+        form = form.push(
+            widget::column()
+                .spacing(5)
+                .push(widget::text::text("Save Recordings To:").size(12))
+                .push(
+                    widget::text_input(default_dir.to_string_lossy().into_owned(), &self.recording_dir_input)
+                        .on_input(Message::RecordingDirChanged)
                 )
-            );
-        }
-
-        // Add separator
-        content_list = content_list.push(widget::divider::horizontal::default());
-
-        // Add each channel
-        for (idx, channel) in self.channels.iter().enumerate() {
-            let is_playing = self.current_channel_idx == Some(idx) 
-                && self.play_state == State::Playing;
-            
-            let icon_name = if is_playing {
-                "media-playback-stop-symbolic"
-            } else {
-                "media-playback-start-symbolic"
-            };
+        );
 
-            // Main row with channel name and play button
-            let mut row = widget::row()
-                .spacing(5)
-                .align_y(cosmic::iced::Alignment::Center);
+        form = form.push(
+            widget::settings::item(
+                "Start a new file for every track",
+                widget::toggler(self.split_recordings_input)
+                    .on_toggle(Message::SplitRecordingsChanged),
+            )
+        );
 
-            // Channel name (expand to fill)
-            row = row.push(
-                widget::text::text(&channel.name)
-                    .width(cosmic::iced::Length::Fill)
-            );
+        // Buttons
+        form = form.push(
+            widget::row()
+                .spacing(10)
+                .push(
+                    widget::button::text("Save")
+                        .on_press(Message::SaveRecordingSettings)
+                )
+                .push(
+                    widget::button::text("Cancel")
+                        .on_press(Message::CancelRecordingSettings)
+                )
+        );
 
-            // Play/Stop button
-            row = row.push(
-                widget::button::icon(widget::icon::from_name(icon_name))
-                    .on_press(if is_playing {
-                        Message::StopPlayback
-                    } else {
-                        Message::PlayChannel(idx)
-                    })
-            );
+        self.core.applet.popup_container(form).into()
+    }
 
-            // Edit button
-            row = row.push(
-                widget::button::icon(widget::icon::from_name("edit-symbolic"))
-                    .on_press(Message::StartEditStation(idx))
-            );
+    /// View for the keyboard shortcuts form
+    fn view_shortcuts_form(&self) -> Element<'_, Message> {
+        let mut form = widget::column()
+            .padding(10)
+            .spacing(10)
+            .push(widget::text::text("Keyboard Shortcuts").size(16))
+            .push(widget::text::text(
+                "In the station list, Up and Down pick a station, Enter plays or stops it, \
+                 E edits it, F adds it to the favourites, Delete removes it and Alt+Up or \
+                 Alt+Down moves it. Esc closes forms and the popup."
+            ).size(12))
+            .push(widget::text::text(
+                "Global shortcuts work anywhere on the desktop. They are added to the \
+                 custom shortcuts in the COSMIC settings."
+            ).size(12));
 
-            // Delete button
-            row = row.push(
-                widget::button::icon(widget::icon::from_name("edit-delete-symbolic"))
-                    .on_press(Message::StartDeleteStation(idx))
+        for (action, input) in ShortcutAction::ALL.into_iter().zip(&self.shortcut_inputs) {
+            form = form.push(
+                widget::column()
+                    .spacing(5)
+                    .push(widget::text::text(action.name()).size(12))
+                    .push(
+                        widget::text_input(action.example(), input)
+                            .on_input(move |value| Message::ShortcutChanged(action, value))
+                    )
             );
-
-            content_list = content_list.push(row);
         }
 
-        // Add separator before Add Station button
-        content_list = content_list.push(widget::divider::horizontal::default());
+        if let Some(error) = &self.shortcuts_error {
+            form = form.push(widget::text::text(error).size(12));
+        }
 
-        // Add Station button
-        content_list = content_list.push(
-            widget::button::text("+ Add Station")
-                .on_press(Message::ToggleAddStation)
+        form = form.push(
+            widget::row()
+                .spacing(10)
+                .push(
+                    widget::button::text("Save")
+                        .on_press(Message::SaveShortcuts)
+                )
+                .push(
+                    widget::button::text("Cancel")
+                        .on_press(Message::CancelShortcuts)
+                )
         );
 
-        self.core.applet.popup_container(content_list).into()
+        self.core.applet.popup_container(form).into()
     }
-}
 
-/// Create a COSMIC application from the app model
-impl cosmic::Application for AppModel {
-    /// The async executor that will be used to run your application's commands.
-    type Executor = cosmic::executor::Default;
-
-    /// Data that your application receives to its init method.
-    type Flags = ();
+    /// View for the record toggle while a stream is playing or being recorded
+    fn view_recording_controls(&self) -> Option<Element<'_, Message>> {
+        let uri = self.player.as_ref().and_then(|player| player.current_uri());
+        let can_record = self.current_channel_idx.is_some()
+            && uri.is_some()
+            && matches!(self.play_state, State::Playing | State::Paused);
+        if !can_record && self.recording.is_none() {
+            return None;
+        }
 
-    /// Messages which the application and its widgets will emit.
-    type Message = Message;
+        let label = match (&self.recording, &self.recording_error) {
+            (Some(recording), _) => match recording.file.as_ref().and_then(|file| file.file_name()) {
+                Some(name) => format!("Recording to {}", name.to_string_lossy()),
+                None => "Recording…".to_string(),
+            },
+            (None, Some(error)) => format!("Recording stopped: {}", error),
+            (None, None) => "Record".to_string(),
+        };
 
-    /// Unique identifier in RDNN (reverse domain name notation) format.
-    const APP_ID: &'static str = "com.github.maxattwell.cosmic-ext-applet-radio";
+        let buttons = widget::row()
+            .spacing(5)
+            .align_y(cosmic::iced::Alignment::Center)
+            .push(
+                widget::button::icon(widget::icon::from_name(if self.recording.is_some() {
+                    "media-playback-stop-symbolic"
+                } else {
+                    "media-record-symbolic"
+                }))
+                .on_press(Message::ToggleRecording)
+            )
+            .push(
+                widget::button::icon(widget::icon::from_name("emblem-system-symbolic"))
+                    .on_press(Message::StartEditRecordingSettings)
+            );
 
-    fn core(&self) -> &cosmic::Core {
-        &self.core
+        Some(widget::settings::item(label, buttons).into())
     }
 
-    fn core_mut(&mut self) -> &mut cosmic::Core {
-        &mut self.core
+    /// Where and how to record the current channel
+    fn recording_options(&self) -> Option<RecordingOptions> {
+        let channel = self.current_channel_idx.and_then(|idx| self.channels.get(idx))?;
+        Some(RecordingOptions {
+            directory: self.recording_directory(),
+            station: channel.name.clone(),
+            split_per_track: self.config.split_recordings,
+        })
     }
 
-    /// Initializes the application with any given flags and startup commands.
-    fn init(
-        core: cosmic::Core,
-        _flags: Self::Flags,
-    ) -> (Self, Task<cosmic::Action<Self::Message>>) {
-        // Construct the app model with the runtime's core.
-        let app = AppModel {
-            core,
-            config: cosmic_config::Config::new(Self::APP_ID, Config::VERSION)
-                .map(|context| match Config::get_entry(&context) {
-                    Ok(config) => config,
-                    Err((_errors, config)) => {
-                        config
-                    }
-                })
-                .unwrap_or_default(),
-            ..Default::default()
-        };
+    /// The configured recording directory
+    fn recording_directory(&self) -> PathBuf {
+        match self.config.recording_dir.trim() {
+            "" => recorder::default_directory(),
+            dir => PathBuf::from(dir),
+        }
+    }
 
-        // Load channels asynchronously
-        let load_channels_task = Task::perform(
-            async { channels::load_channels() },
-            |result| match result {
-                Ok(list) => Message::ChannelsLoaded(list.channels),
-                Err(e) => Message::ChannelError(e.to_string()),
-            },
-        ).map(|msg| cosmic::Action::App(msg));
+    /// View for the recording schedule
+    fn view_schedule(&self) -> Element<'_, Message> {
+        let mut content = widget::column()
+            .padding(10)
+            .spacing(10)
+            .push(widget::text::text("Scheduled Recordings").size(16));
 
-        (app, load_channels_task)
-    }
+        if self.schedule.is_empty() {
+            content = content.push(widget::text::text("No recordings scheduled").size(12));
+        }
 
-    fn on_close_requested(&self, id: Id) -> Option<Message> {
-        Some(Message::PopupClosed(id))
-    }
+        for (idx, programme) in self.schedule.iter().enumerate() {
+            let station = self.channels.iter()
+                .find(|c| c.id == programme.channel_id)
+                .map(|c| c.name.as_str())
+                .unwrap_or("Missing station");
 
-    /// Describes the interface based on the current state of the application model.
-    ///
-    /// The applet's button in the panel will be drawn using the main view method.
-    /// This view should emit messages to toggle the applet's popup window, which will
-    /// be drawn using the `view_window` method.
-    fn view(&self) -> Element<'_, Self::Message> {
-        if self.player.is_none() {
-            return self.core
-                .applet
-                .icon_button("dialog-error-symbolic")
-                .into();
+            content = content.push(
+                widget::row()
+                    .spacing(5)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(
+                        widget::column()
+                            .width(cosmic::iced::Length::Fill)
+                            .push(widget::text::text(&programme.title))
+                            .push(widget::text::text(format!("{} · {}", station, programme.when_label())).size(12))
+                    )
+                    .push(
+                        widget::toggler(programme.enabled)
+                            .on_toggle(move |enabled| Message::SetScheduledRecordingEnabled(idx, enabled))
+                    )
+                    .push(
+                        widget::button::icon(widget::icon::from_name("edit-symbolic"))
+                            .on_press(Message::StartEditScheduledRecording(idx))
+                    )
+                    .push(
+                        widget::button::icon(widget::icon::from_name("edit-delete-symbolic"))
+                            .on_press(Message::DeleteScheduledRecording(idx))
+                    )
+            );
         }
 
-        let icon = if self.play_state == State::Playing {
-            "audio-card-symbolic"
-        } else {
-            "audio-card-symbolic"
-        };
+        content = content.push(
+            widget::row()
+                .spacing(10)
+                .push(
+                    widget::button::text("+ Schedule Recording")
+                        .on_press(Message::StartAddScheduledRecording)
+                )
+                .push(
+                    widget::button::text("Back")
+                        .on_press(Message::ToggleSchedule)
+                )
+        );
 
-        self.core
-            .applet
-            .icon_button(icon)
-            .on_press(Message::TogglePopup)
-            .into()
+        self.core.applet.popup_container(content).into()
     }
 
-    /// The applet's popup window will be drawn using this view method. If there are
-    /// multiple poups, you may match the id parameter to determine which popup to
-    /// create a view for.
-    fn view_window(&self, _id: Id) -> Element<'_, Self::Message> {
-        if self.player.is_none() {
-            return self.core.applet.popup_container(
-                widget::text::text("Audio initialization failed. Check logs.")
-            ).into();
-        }
+    /// View for the add/edit scheduled recording form
+    fn view_schedule_form<'a>(&'a self, form: &'a ScheduleForm) -> Element<'a, Message> {
+        let mut content = widget::column()
+            .padding(10)
+            .spacing(10);
 
-        // Show error message if there is one
-        if let Some(error) = &self.error_message {
-            let error_widget = widget::column()
-                .padding(10)
+        // Header
+        content = content.push(
+            widget::text::text(if form.idx.is_some() { "Edit Scheduled Recording" } else { "Schedule Recording" })
+                .size(16)
+        );
+
+        // Title input
+        content = content.push(
+            widget::column()
+                .spacing(5)
+                .push(widget::text::text("Programme:").size(12))
+                .push(
+                    widget::text_input("e.g., FIP jazz show", &form.title)
+                        .on_input(Message::ScheduleTitleChanged)
+                )
+        );
+
+        // Station picker
+        content = content.push(
+            widget::column()
+                .spacing(5)
+                .push(widget::text::text("Station:").size(12))
+                .push(widget::dropdown(form.station_names.as_slice(), form.channel, Message::ScheduleStationSelected))
+        );
+
+        // Start and end time inputs
+        content = content.push(
+            widget::row()
                 .spacing(10)
-                .push(widget::text::text("Error loading channels:").size(14))
-                .push(widget::text::text(error).size(12))
                 .push(
-                    widget::button::text("Use Defaults")
-                        .on_press(Message::ChannelsLoaded(channels::default_channels().channels))
-                );
-            return self.core.applet.popup_container(error_widget).into();
+                    widget::column()
+                        .spacing(5)
+                        .push(widget::text::text("Start:").size(12))
+                        .push(
+                            widget::text_input("e.g., 21:00", &form.start)
+                                .on_input(Message::ScheduleStartChanged)
+                        )
+                )
+                .push(
+                    widget::column()
+                        .spacing(5)
+                        .push(widget::text::text("End:").size(12))
+                        .push(
+                            widget::text_input("e.g., 22:00", &form.end)
+                                .on_input(Message::ScheduleEndChanged)
+                        )
+                )
+        );
+
+        // Weekly days or a one-off date
+        content = content.push(
+            widget::column()
+                .spacing(5)
+                .push(widget::text::text("Every week on:").size(12))
+                .push(view_weekday_picker(&form.weekdays, Message::ToggleScheduleWeekday))
+                .push(widget::text::text("Or once on:").size(12))
+                .push(
+                    widget::text_input("e.g., 2024-06-04", &form.date)
+                        .on_input(Message::ScheduleDateChanged)
+                )
+        );
+
+        // Error message
+        if let Some(error) = &form.error {
+            content = content.push(
+                widget::text::text(format!("Error: {}", error))
+                    .size(12)
+            );
         }
 
-        // Show edit station form
-        if let Some(idx) = self.editing_station_idx {
-            return self.view_edit_station_form(idx);
+        // Buttons
+        content = content.push(
+            widget::row()
+                .spacing(10)
+                .push(
+                    widget::button::text("Save")
+                        .on_press(Message::SaveScheduledRecording)
+                )
+                .push(
+                    widget::button::text("Cancel")
+                        .on_press(Message::CancelScheduledRecording)
+                )
+        );
+
+        self.core.applet.popup_container(content).into()
+    }
+
+    /// View for running scheduled recordings and skipped ones
+    fn view_scheduled_captures(&self) -> Vec<Element<'_, Message>> {
+        let mut items: Vec<Element<'_, Message>> = self.scheduled_captures
+            .iter()
+            .filter(|capture| capture.failures < SCHEDULED_RECORDING_MAX_FAILURES)
+            .map(|capture| {
+                let state = if capture.recording.is_some() { "Recording" } else { "Connecting to" };
+                widget::text::text(format!(
+                    "{} {} until {}",
+                    state,
+                    capture.title,
+                    capture.end.format("%H:%M")
+                ))
+                .size(12)
+                .into()
+            })
+            .collect();
+
+        if let Some(notice) = &self.schedule_notice {
+            items.push(
+                widget::settings::item(
+                    notice,
+                    widget::button::text("Dismiss").on_press(Message::DismissScheduleNotice),
+                )
+                .into(),
+            );
         }
+        items
+    }
 
-        // Show delete confirmation
-        if let Some(idx) = self.deleting_station_idx {
-            return self.view_delete_confirmation(idx);
+    /// View for the recently played tracks
+    fn view_history(&self) -> Element<'_, Message> {
+        let mut content = widget::column()
+            .padding(10)
+            .spacing(10)
+            .push(widget::text::text("Recently Played").size(16))
+            .push(
+                widget::text_input("Search artist, title or station", &self.history_query)
+                    .on_input(Message::HistoryQueryChanged)
+            );
+
+        let today = chrono::Local::now().date_naive();
+        let mut tracks = widget::column().spacing(5);
+        let mut matches = self.history.iter()
+            .rev()
+            .filter(|entry| entry.matches(&self.history_query))
+            .take(HISTORY_VIEW_LIMIT)
+            .peekable();
+        if matches.peek().is_none() {
+            tracks = tracks.push(widget::text::text(if self.history.is_empty() {
+                "Nothing played yet"
+            } else {
+                "No matching tracks"
+            }).size(12));
+        }
+        for entry in matches {
+            let played_at = entry.played_at.with_timezone(&chrono::Local);
+            let time = if played_at.date_naive() == today {
+                played_at.format("%H:%M").to_string()
+            } else {
+                played_at.format("%a %d %b %H:%M").to_string()
+            };
+            tracks = tracks.push(
+                widget::column()
+                    .push(widget::text::text(entry.track.track_line()))
+                    .push(widget::text::text(format!("{} · {}", time, entry.track.station_name)).size(12))
+            );
         }
+        content = content.push(
+            widget::scrollable(tracks).height(cosmic::iced::Length::Fixed(300.0))
+        );
 
-        // Show add station form
-        if self.adding_station {
-            return self.view_add_station_form();
+        if let Some(notice) = &self.history_notice {
+            content = content.push(widget::text::text(notice).size(12));
         }
 
-        // Show message if no channels loaded yet
-        if self.channels.is_empty() {
-            let loading_widget = widget::column()
-                .padding(10)
+        content = content.push(
+            widget::row()
                 .spacing(10)
-                .push(widget::text::text("Loading channels..."))
                 .push(
-                    widget::button::text("Add Station")
-                        .on_press(Message::ToggleAddStation)
-                );
-            return self.core.applet.popup_container(loading_widget).into();
-        }
+                    widget::button::text("Export CSV")
+                        .on_press(Message::ExportHistory(ExportFormat::Csv))
+                )
+                .push(
+                    widget::button::text("Export JSON")
+                        .on_press(Message::ExportHistory(ExportFormat::Json))
+                )
+                .push(
+                    widget::button::text("Back")
+                        .on_press(Message::ToggleHistory)
+                )
+        );
 
-        self.view_channel_list()
+        self.core.applet.popup_container(content).into()
     }
 
-    /// Register subscriptions for this application.
-    ///
-    /// Subscriptions are long-lived async tasks running in the background which
-    /// emit messages to the application through a channel. They may be conditionally
-    /// activated by selectively appending to the subscription batch, and will
-    /// continue to execute for the duration that they remain in the batch.
-    fn subscription(&self) -> Subscription<Self::Message> {
-        struct MySubscription;
-        struct PlayerSubscription;
+    /// View for importing and exporting station lists
+    fn view_station_lists(&self) -> Element<'_, Message> {
+        let mode_button = |label, mode| {
+            widget::button::text(label)
+                .class(if self.import_mode == mode {
+                    cosmic::theme::Button::Suggested
+                } else {
+                    cosmic::theme::Button::Standard
+                })
+                .on_press(Message::ImportModeChanged(mode))
+        };
 
-        let mut subs = vec![
-            // Create a subscription which emits updates through a channel.
-            Subscription::run_with_id(
-                std::any::TypeId::of::<MySubscription>(),
-                cosmic::iced::stream::channel(4, move |mut channel| async move {
-                    _ = channel.send(Message::SubscriptionChannel).await;
+        let mut content = widget::column()
+            .padding(10)
+            .spacing(10)
+            .push(widget::text::text("Import & Export").size(16))
+            .push(
+                widget::column()
+                    .spacing(5)
+                    .push(widget::text::text("Import From:").size(12))
+                    .push(
+                        widget::text_input("~/Downloads/stations.m3u", &self.import_path)
+                            .on_input(Message::ImportPathChanged)
+                            .on_submit(|_| Message::ImportStations)
+                    )
+                    .push(
+                        widget::text::text("M3U, PLS, XSPF or OPML. Stations already listed are skipped.")
+                            .size(12)
+                    )
+            )
+            .push(
+                widget::row()
+                    .spacing(5)
+                    .push(mode_button("Merge", ImportMode::Merge))
+                    .push(mode_button("Replace", ImportMode::Replace))
+                    .push(
+                        widget::button::text("Import")
+                            .on_press_maybe((!self.import_path.trim().is_empty()).then_some(Message::ImportStations))
+                    )
+            );
 
-                    futures_util::future::pending().await
-                }),
-            ),
-            // Watch for application configuration changes.
-            self.core()
-                .watch_config::<Config>(Self::APP_ID)
-                .map(|update| {
-                    Message::UpdateConfig(update.config)
-                }),
-        ];
+        let exports = ListFormat::ALL.iter().map(|format| {
+            widget::button::text(format!("Export {}", format.name()))
+                .on_press(Message::ExportStations(*format))
+                .into()
+        });
+        content = content.push(
+            widget::flex_row(exports.collect())
+                .row_spacing(5)
+                .column_spacing(5)
+        );
 
-        if let Some(player) = &self.player {
-            let bus = player.bus();
-            let pipeline = player.pipeline().clone();
+        if let Some(notice) = &self.station_list_notice {
+            content = content.push(widget::text::text(notice).size(12));
+        }
 
-            subs.push(Subscription::run_with_id(
-                std::any::TypeId::of::<PlayerSubscription>(),
-                cosmic::iced::stream::channel(10, move |mut channel| async move {
-                    let mut bus_stream = bus.stream();
+        content = content.push(
+            widget::button::text("Back")
+                .on_press(Message::ToggleStationLists)
+        );
+
+        self.core.applet.popup_container(content).into()
+    }
+
+    /// View for the liked tracks
+    fn view_liked(&self) -> Element<'_, Message> {
+        let mut tracks = widget::column().spacing(5);
+        if self.liked.is_empty() {
+            tracks = tracks.push(widget::text::text("No liked tracks yet").size(12));
+        }
+        for (idx, liked) in self.liked.iter().enumerate().rev() {
+            let liked_at = liked.liked_at.with_timezone(&chrono::Local);
+            tracks = tracks.push(
+                widget::row()
+                    .spacing(5)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(
+                        widget::column()
+                            .width(cosmic::iced::Length::Fill)
+                            .push(widget::text::text(liked.track.track_line()))
+                            .push(widget::text::text(format!(
+                                "{} · {}",
+                                liked_at.format("%a %d %b %Y %H:%M"),
+                                liked.track.station_name
+                            )).size(12))
+                    )
+                    .push(
+                        widget::button::icon(widget::icon::from_name("edit-copy-symbolic"))
+                            .on_press(Message::CopyLikedTrack(idx))
+                    )
+                    .push(
+                        widget::button::icon(widget::icon::from_name("system-search-symbolic"))
+                            .on_press(Message::SearchLikedTrack(idx))
+                    )
+                    .push(
+                        widget::button::icon(widget::icon::from_name("edit-delete-symbolic"))
+                            .on_press(Message::DeleteLikedTrack(idx))
+                    )
+            );
+        }
+
+        let mut content = widget::column()
+            .padding(10)
+            .spacing(10)
+            .push(widget::text::text("Liked Tracks").size(16))
+            .push(widget::scrollable(tracks).height(cosmic::iced::Length::Fixed(300.0)));
+        if let Some(error) = self.view_liked_error() {
+            content = content.push(error);
+        }
+        content = content.push(
+            widget::button::text("Back")
+                .on_press(Message::ToggleLiked)
+        );
+
+        self.core.applet.popup_container(content).into()
+    }
+
+    /// View for a failed save of the liked tracks
+    fn view_liked_error(&self) -> Option<Element<'_, Message>> {
+        let error = self.liked_error.as_ref()?;
+        Some(
+            widget::settings::item(
+                error,
+                widget::button::text("Dismiss").on_press(Message::DismissLikedError),
+            )
+            .into(),
+        )
+    }
+
+    /// The song playing on the current channel, if its title is known
+    fn current_track(&self) -> Option<StationTrack> {
+        let channel = self.current_channel_idx.and_then(|idx| self.channels.get(idx))?;
+        StationTrack::now_playing(channel, &self.now_playing)
+    }
+
+    /// Index of the liked track matching the current track
+    fn current_liked_idx(&self) -> Option<usize> {
+        let title = self.now_playing.title.as_deref()?;
+        self.liked
+            .iter()
+            .position(|track| track.is_song(self.now_playing.artist.as_deref(), title))
+    }
+
+    /// Write the liked tracks to disk
+    fn save_liked(&self) -> Result<(), liked::LikedError> {
+        let list = LikedList {
+            tracks: self.liked.clone(),
+        };
+        liked::save_liked(&list)
+    }
+
+    /// View for the scrobbling settings form
+    fn view_scrobble_form<'a>(&'a self, form: &'a ScrobbleForm) -> Element<'a, Message> {
+        let mut content = widget::column()
+            .padding(10)
+            .spacing(10)
+            .push(widget::text::text("Scrobbling").size(16))
+            .push(widget::settings::item(
+                "Scrobble played tracks",
+                widget::toggler(form.enabled).on_toggle(Message::ScrobblingEnabledChanged),
+            ))
+            .push(widget::settings::item(
+                "Service",
+                widget::dropdown(form.service_names.as_slice(), Some(form.service), Message::ScrobbleServiceSelected),
+            ));
+
+        let service = Service::ALL[form.service];
+        content = content.push(
+            widget::column()
+                .spacing(5)
+                .push(widget::text::text("Server:").size(12))
+                .push(
+                    widget::text_input(service.default_endpoint(), &form.endpoint)
+                        .on_input(Message::ScrobbleEndpointChanged)
+                )
+        );
+
+        // Credentials of the selected service
+        match service {
+            Service::ListenBrainz => {
+                content = content.push(
+                    widget::column()
+                        .spacing(5)
+                        .push(widget::text::text("User token:").size(12))
+                        .push(
+                            widget::secure_input("From your ListenBrainz settings", &form.token, None, true)
+                                .on_input(Message::ScrobbleTokenChanged)
+                        )
+                );
+            }
+            Service::LastFm => {
+                content = content.push(
+                    widget::column()
+                        .spacing(5)
+                        .push(widget::text::text("API key and secret:").size(12))
+                        .push(
+                            widget::text_input("API key", &form.api_key)
+                                .on_input(Message::ScrobbleApiKeyChanged)
+                        )
+                        .push(
+                            widget::secure_input("API secret", &form.api_secret, None, true)
+                                .on_input(Message::ScrobbleApiSecretChanged)
+                        )
+                );
+                let sign_in_label = if form.session_key.is_empty() {
+                    "Sign in:"
+                } else {
+                    "Signed in. Enter your password to sign in again:"
+                };
+                content = content.push(
+                    widget::column()
+                        .spacing(5)
+                        .push(widget::text::text(sign_in_label).size(12))
+                        .push(
+                            widget::text_input("Username", &form.username)
+                                .on_input(Message::ScrobbleUsernameChanged)
+                        )
+                        .push(
+                            widget::secure_input("Password", &form.password, None, true)
+                                .on_input(Message::ScrobblePasswordChanged)
+                        )
+                );
+            }
+        }
+
+        content = content.push(
+            widget::column()
+                .spacing(5)
+                .push(widget::text::text("Scrobble after (seconds):").size(12))
+                .push(
+                    widget::text_input("90", &form.min_play_seconds)
+                        .on_input(Message::ScrobbleMinPlayChanged)
+                )
+        );
+
+        // Filters for ads and station IDs
+        let mut filters = widget::column()
+            .spacing(5)
+            .push(widget::text::text("Don't scrobble titles matching:").size(12));
+        for (i, filter) in form.filters.iter().enumerate() {
+            let selected = match &filter.station_id {
+                None => Some(0),
+                Some(id) => self.channels.iter().position(|c| &c.id == id).map(|idx| idx + 1),
+            };
+            filters = filters.push(
+                widget::row()
+                    .spacing(5)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(
+                        widget::dropdown(form.station_options.as_slice(), selected, move |station| {
+                            Message::ScrobbleFilterStationSelected(i, station)
+                        })
+                    )
+                    .push(
+                        widget::text_input("e.g., ^Advert|Station ID", &filter.pattern)
+                            .on_input(move |pattern| Message::ScrobbleFilterPatternChanged(i, pattern))
+                    )
+                    .push(
+                        widget::button::icon(widget::icon::from_name("list-remove-symbolic"))
+                            .on_press(Message::RemoveScrobbleFilter(i))
+                    )
+            );
+        }
+        filters = filters.push(
+            widget::button::text("+ Add Filter")
+                .on_press(Message::AddScrobbleFilter)
+        );
+        content = content.push(filters);
+
+        // Queue status
+        if !self.scrobble_queue.is_empty() {
+            content = content.push(
+                widget::text::text(format!("{} scrobbles waiting to be sent", self.scrobble_queue.len()))
+                    .size(12)
+            );
+        }
+        if let Some(error) = &self.scrobble_error {
+            content = content.push(
+                widget::text::text(format!("Last submission failed: {}", error))
+                    .size(12)
+            );
+        }
+
+        // Error message
+        if let Some(error) = &form.error {
+            content = content.push(
+                widget::text::text(format!("Error: {}", error))
+                    .size(12)
+            );
+        }
+
+        // Buttons
+        content = content.push(
+            widget::row()
+                .spacing(10)
+                .push(
+                    widget::button::text(if form.signing_in { "Signing in…" } else { "Save" })
+                        .on_press_maybe((!form.signing_in).then_some(Message::SaveScrobbling))
+                )
+                .push(
+                    widget::button::text("Cancel")
+                        .on_press(Message::CancelScrobbling)
+                )
+        );
+
+        self.core.applet.popup_container(content).into()
+    }
+
+    /// Settings from the scrobbling form, or why they are invalid
+    fn scrobble_form_settings(&self, form: &ScrobbleForm) -> Result<ScrobbleSettings, String> {
+        let service = Service::ALL[form.service];
+        let min_play_seconds = form.min_play_seconds.trim().parse::<u64>()
+            .map_err(|_| "Scrobble time must be a number of seconds".to_string())?;
+        let endpoint = match form.endpoint.trim() {
+            "" => None,
+            endpoint => {
+                url::Url::parse(endpoint).map_err(|_| "Invalid server URL".to_string())?;
+                Some(endpoint.to_string())
+            }
+        };
+        let settings = ScrobbleSettings {
+            enabled: form.enabled,
+            service,
+            endpoint,
+            token: form.token.trim().to_string(),
+            api_key: form.api_key.trim().to_string(),
+            api_secret: form.api_secret.trim().to_string(),
+            session_key: form.session_key.clone(),
+            min_play_seconds,
+            filters: form.filters.iter()
+                .filter(|filter| !filter.pattern.is_empty())
+                .map(|filter| scrobbler::Filter {
+                    station_id: filter.station_id.clone(),
+                    pattern: filter.pattern.clone(),
+                })
+                .collect(),
+        };
+
+        // Check the filters compile
+        Scrobbler::new(settings.clone()).map_err(|e| e.to_string())?;
+        if settings.enabled {
+            match service {
+                Service::ListenBrainz if settings.token.is_empty() => {
+                    return Err("A user token is required".to_string());
+                }
+                Service::LastFm if settings.api_key.is_empty() || settings.api_secret.is_empty() => {
+                    return Err("An API key and secret are required".to_string());
+                }
+                Service::LastFm if settings.session_key.is_empty() && form.password.is_empty() => {
+                    return Err("Sign in with your Last.fm username and password".to_string());
+                }
+                _ => {}
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Save and use new scrobbling settings
+    fn apply_scrobble_settings(&mut self, settings: ScrobbleSettings) -> Task<cosmic::Action<Message>> {
+        let scrobbler = match Scrobbler::new(settings.clone()) {
+            Ok(scrobbler) => scrobbler,
+            Err(e) => {
+                if let Some(form) = &mut self.scrobble_form {
+                    form.error = Some(e.to_string());
+                }
+                return Task::none();
+            }
+        };
+        if let Err(e) = scrobbler::save_settings(&settings) {
+            tracing::error!("Failed to save scrobbling settings: {}", e);
+            if let Some(form) = &mut self.scrobble_form {
+                form.error = Some(format!("Failed to save: {}", e));
+            }
+            return Task::none();
+        }
+
+        self.scrobbler = Some(scrobbler);
+        self.scrobble_form = None;
+        self.scrobble_error = None;
+        self.submit_scrobbles()
+    }
+
+    /// Time the current track for scrobbling after a title change, or the
+    /// end of playback if `playing` is false
+    fn scrobble_track_change(&mut self, playing: bool) -> Task<cosmic::Action<Message>> {
+        let station_id = self.current_channel_idx
+            .and_then(|idx| self.channels.get(idx))
+            .filter(|_| playing)
+            .map(|channel| channel.id.clone());
+        let Some(scrobbler) = self.scrobbler.as_mut().filter(|scrobbler| scrobbler.is_active()) else {
+            return Task::none();
+        };
+
+        let (listen, track) = scrobbler.track_changed(station_id.as_deref(), &self.now_playing, chrono::Utc::now());
+        let mut tasks = Vec::new();
+        if let Some(track) = track {
+            let settings = scrobbler.settings().clone();
+            tasks.push(
+                Task::perform(
+                    async move { scrobbler::now_playing(&settings, &track).await.map_err(|e| e.to_string()) },
+                    Message::NowPlayingSent,
+                )
+                .map(cosmic::Action::App),
+            );
+        }
+        if let Some(listen) = listen {
+            self.scrobble_queue.push(listen);
+            self.save_scrobble_queue();
+            tasks.push(self.submit_scrobbles());
+        }
+        Task::batch(tasks)
+    }
+
+    /// Submit the oldest queued listens, unless a submission is running
+    fn submit_scrobbles(&mut self) -> Task<cosmic::Action<Message>> {
+        let Some(scrobbler) = self.scrobbler.as_ref().filter(|scrobbler| scrobbler.is_active()) else {
+            return Task::none();
+        };
+        if self.scrobble_submitting || self.scrobble_queue.is_empty() {
+            return Task::none();
+        }
+
+        let settings = scrobbler.settings().clone();
+        let batch: Vec<Listen> = self.scrobble_queue.iter()
+            .take(settings.service.max_batch())
+            .cloned()
+            .collect();
+        let count = batch.len();
+        self.scrobble_submitting = true;
+        Task::perform(
+            async move { scrobbler::submit(&settings, &batch).await.map_err(SubmitFailure::from) },
+            move |result| Message::ScrobblesSubmitted(count, result),
+        )
+        .map(cosmic::Action::App)
+    }
+
+    /// Write the scrobble queue to disk
+    fn save_scrobble_queue(&self) {
+        if let Err(e) = scrobbler::save_queue(&self.scrobble_queue) {
+            tracing::error!("Failed to save scrobble queue: {}", e);
+        }
+    }
+
+    /// Show a notification for the current track, if enabled
+    fn notify_track(&self) {
+        let Some(notifier) = &self.notifier else {
+            return;
+        };
+        let channel = self.current_channel_idx.and_then(|idx| self.channels.get(idx));
+        if let (Some(channel), Some(track)) = (channel, self.now_playing.track_line()) {
+            let _ = notifier.send(Notification {
+                summary: channel.name.clone(),
+                body: track,
+            });
+        }
+    }
+
+    /// Add the current track to the history
+    fn record_history(&mut self) {
+        let Some(track) = self.current_track() else {
+            return;
+        };
+
+        let entry = HistoryEntry {
+            played_at: chrono::Utc::now(),
+            track,
+        };
+        if let Err(e) = history::append_entry(&entry) {
+            tracing::error!("Failed to save history: {}", e);
+        }
+        self.history.push(entry);
+    }
+
+    /// Write the recording schedule to disk
+    fn save_schedule(&self) -> Result<(), recording_schedule::ScheduleError> {
+        let schedule = Schedule {
+            recordings: self.schedule.clone(),
+        };
+        recording_schedule::save_schedule(&schedule)
+    }
+
+    /// Start, extend and stop scheduled recordings for the local time `now`
+    fn update_scheduled_captures(&mut self, now: chrono::NaiveDateTime) -> Task<cosmic::Action<Message>> {
+        let due = recording_schedule::due(&self.schedule, now);
+
+        // Dropping a capture ends its recording subscription
+        self.scheduled_captures.retain(|capture| {
+            let running = due.iter().any(|programme| programme.channel_id == capture.channel_id);
+            if !running {
+                tracing::info!("Scheduled recording of {} ended", capture.title);
+            }
+            running
+        });
+
+        let mut tasks = Vec::new();
+        for programme in due {
+            let idx = match self.scheduled_captures.iter().position(|c| c.channel_id == programme.channel_id) {
+                Some(idx) => {
+                    self.scheduled_captures[idx].end = programme.end;
+                    idx
+                }
+                None => {
+                    tracing::info!("Scheduled recording of {} started", programme.title);
+                    self.scheduled_captures.push(ScheduledCapture {
+                        channel_id: programme.channel_id,
+                        title: programme.title,
+                        end: programme.end,
+                        recording: None,
+                        resolving: false,
+                        failures: 0,
+                    });
+                    self.scheduled_captures.len() - 1
+                }
+            };
+
+            // (Re)connect unless running, connecting or skipped
+            let capture = &self.scheduled_captures[idx];
+            if capture.recording.is_some()
+                || capture.resolving
+                || capture.failures >= SCHEDULED_RECORDING_MAX_FAILURES
+            {
+                continue;
+            }
+            let Some(channel) = self.channels.iter().find(|c| c.id == capture.channel_id) else {
+                self.scheduled_capture_failed(idx, "the station no longer exists".to_string());
+                continue;
+            };
+
+            let uris = channel.uris();
+            let channel_id = capture.channel_id.clone();
+            self.scheduled_captures[idx].resolving = true;
+            tasks.push(
+                Task::perform(
+                    async move { playlist::resolve_all(&uris).await.map_err(|e| e.to_string()) },
+                    move |result| Message::ScheduledCaptureResolved(channel_id.clone(), result),
+                )
+                .map(cosmic::Action::App),
+            );
+        }
+        Task::batch(tasks)
+    }
+
+    /// Count a failed attempt of the scheduled capture at `idx`, skipping it after too many
+    fn scheduled_capture_failed(&mut self, idx: usize, error: String) {
+        let Some(capture) = self.scheduled_captures.get_mut(idx) else {
+            return;
+        };
+        capture.recording = None;
+        capture.resolving = false;
+        capture.failures = if self.channels.iter().any(|c| c.id == capture.channel_id) {
+            capture.failures + 1
+        } else {
+            SCHEDULED_RECORDING_MAX_FAILURES
+        };
+
+        if capture.failures >= SCHEDULED_RECORDING_MAX_FAILURES {
+            tracing::error!("Skipping scheduled recording of {}: {}", capture.title, error);
+            self.schedule_notice = Some(format!("Skipped recording {}: {}", capture.title, error));
+        } else {
+            tracing::warn!("Scheduled recording of {} failed, retrying: {}", capture.title, error);
+        }
+    }
+
+    /// Stop the recording in progress, if any
+    ///
+    /// Dropping it ends its subscription, which closes the file.
+    fn stop_recording(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            tracing::info!("Stopped recording {}", recording.uri);
+        }
+    }
+
+    /// View for the alarm list
+    fn view_alarms(&self) -> Element<'_, Message> {
+        let mut content = widget::column()
+            .padding(10)
+            .spacing(10)
+            .push(widget::text::text("Alarms").size(16));
+
+        if self.alarms.is_empty() {
+            content = content.push(widget::text::text("No alarms set").size(12));
+        }
+
+        for (idx, alarm) in self.alarms.iter().enumerate() {
+            let station = self.channels.iter()
+                .find(|c| c.id == alarm.channel_id)
+                .map(|c| c.name.as_str())
+                .unwrap_or("Missing station");
+
+            content = content.push(
+                widget::row()
+                    .spacing(5)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(
+                        widget::column()
+                            .width(cosmic::iced::Length::Fill)
+                            .push(widget::text::text(format!("{} · {}", alarm.time.format("%H:%M"), alarm.days_label())))
+                            .push(widget::text::text(station).size(12))
+                    )
+                    .push(
+                        widget::toggler(alarm.enabled)
+                            .on_toggle(move |enabled| Message::SetAlarmEnabled(idx, enabled))
+                    )
+                    .push(
+                        widget::button::icon(widget::icon::from_name("edit-symbolic"))
+                            .on_press(Message::StartEditAlarm(idx))
+                    )
+                    .push(
+                        widget::button::icon(widget::icon::from_name("edit-delete-symbolic"))
+                            .on_press(Message::DeleteAlarm(idx))
+                    )
+            );
+        }
+
+        content = content.push(
+            widget::row()
+                .spacing(10)
+                .push(
+                    widget::button::text("+ Add Alarm")
+                        .on_press(Message::StartAddAlarm)
+                )
+                .push(
+                    widget::button::text("Back")
+                        .on_press(Message::ToggleAlarms)
+                )
+        );
+
+        self.core.applet.popup_container(content).into()
+    }
+
+    /// View for the add/edit alarm form
+    fn view_alarm_form<'a>(&'a self, form: &'a AlarmForm) -> Element<'a, Message> {
+        let mut content = widget::column()
+            .padding(10)
+            .spacing(10);
+
+        // Header
+        content = content.push(
+            widget::text::text(if form.idx.is_some() { "Edit Alarm" } else { "Add Alarm" })
+                .size(16)
+        );
+
+        // Time input
+        content = content.push(
+            widget::column()
+                .spacing(5)
+                .push(widget::text::text("Time:").size(12))
+                .push(
+                    widget::text_input("e.g., 07:30", &form.time)
+                        .on_input(Message::AlarmTimeChanged)
+                )
+        );
+
+        // Station picker
+        content = content.push(
+            widget::column()
+                .spacing(5)
+                .push(widget::text::text("Station:").size(12))
+                .push(widget::dropdown(form.station_names.as_slice(), form.channel, Message::AlarmStationSelected))
+        );
+
+        // Weekday toggles, none selected means once
+        content = content.push(
+            widget::column()
+                .spacing(5)
+                .push(widget::text::text("Repeat on (none for once):").size(12))
+                .push(view_weekday_picker(&form.weekdays, Message::ToggleAlarmWeekday))
+        );
+
+        // Volume ramp input
+        content = content.push(
+            widget::column()
+                .spacing(5)
+                .push(widget::text::text("Volume ramp-up (minutes):").size(12))
+                .push(
+                    widget::text_input("e.g., 2", &form.ramp_minutes)
+                        .on_input(Message::AlarmRampChanged)
+                )
+        );
+
+        // Error message
+        if let Some(error) = &form.error {
+            content = content.push(
+                widget::text::text(format!("Error: {}", error))
+                    .size(12)
+            );
+        }
+
+        // Buttons
+        content = content.push(
+            widget::row()
+                .spacing(10)
+                .push(
+                    widget::button::text("Save")
+                        .on_press(Message::SaveAlarm)
+                )
+                .push(
+                    widget::button::text("Cancel")
+                        .on_press(Message::CancelAlarm)
+                )
+        );
+
+        self.core.applet.popup_container(content).into()
+    }
+
+    /// View for a ringing alarm
+    fn view_alarm_status(&self) -> Option<Element<'_, Message>> {
+        let alarm = self.ringing_alarm.as_ref()?;
+        let label = if alarm.fallback {
+            "Alarm: station unavailable, playing the alarm sound"
+        } else {
+            "Alarm"
+        };
+        Some(
+            widget::settings::item(
+                label,
+                widget::button::text("Stop").on_press(Message::StopPlayback),
+            )
+            .into(),
+        )
+    }
+
+    /// Write the alarms to disk
+    fn save_alarms(&self) -> Result<(), alarms::AlarmError> {
+        let list = AlarmList {
+            alarms: self.alarms.clone(),
+        };
+        alarms::save_alarms(&list)
+    }
+
+    /// Play the station of the alarm at `alarm_idx`, ramping the volume up
+    fn ring_alarm(&mut self, alarm_idx: usize) -> Task<cosmic::Action<Message>> {
+        let Some(alarm) = self.alarms.get(alarm_idx).cloned() else {
+            return Task::none();
+        };
+        tracing::info!("Alarm for {} at {} went off", alarm.channel_id, alarm.time);
+
+        // An alarm has to be heard
+        if self.config.muted {
+            self.config.muted = false;
+            self.save_volume();
+        }
+
+        self.reconnector.reset();
+        self.reconnect_status = None;
+        self.ringing_alarm = Some(RingingAlarm {
+            started: Instant::now(),
+            ramp: alarm.ramp(),
+            fallback: false,
+        });
+        self.apply_volume();
+
+        match self.channels.iter().position(|c| c.id == alarm.channel_id) {
+            Some(idx) => self.start_channel(idx),
+            None => {
+                tracing::warn!("Alarm station {} no longer exists", alarm.channel_id);
+                self.play_alarm_fallback();
+                Task::none()
+            }
+        }
+    }
+
+    /// Play the bundled alarm sound instead of the alarm's station
+    fn play_alarm_fallback(&mut self) {
+        let Some(player) = &mut self.player else {
+            return;
+        };
+        // Drop the result of any station still resolving
+        self.play_request += 1;
+
+        let uri = match alarms::fallback_sound_uri() {
+            Ok(uri) => uri,
+            Err(e) => {
+                tracing::error!("Failed to prepare the alarm sound: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = player.play_candidates(vec![uri]) {
+            tracing::error!("Failed to play the alarm sound: {}", e);
+            return;
+        }
+        // The recording would pick up the alarm sound
+        self.stop_recording();
+        self.play_state = State::Null;
+        if let Some(alarm) = &mut self.ringing_alarm {
+            alarm.fallback = true;
+        }
+    }
+
+    /// View for the volume slider and mute toggle
+    fn view_volume_controls(&self) -> Element<'_, Message> {
+        let icon_name = if self.config.muted || self.config.volume == 0 {
+            "audio-volume-muted-symbolic"
+        } else if self.config.volume < 34 {
+            "audio-volume-low-symbolic"
+        } else if self.config.volume < 67 {
+            "audio-volume-medium-symbolic"
+        } else {
+            "audio-volume-high-symbolic"
+        };
+
+        widget::row()
+            .spacing(5)
+            .align_y(cosmic::iced::Alignment::Center)
+            .push(
+                widget::button::icon(widget::icon::from_name(icon_name))
+                    .on_press(Message::ToggleMute)
+            )
+            .push(
+                widget::slider(0..=100, self.config.volume, Message::SetVolume)
+                    .on_release(Message::SaveVolume)
+                    .width(cosmic::iced::Length::Fill)
+            )
+            .push(
+                widget::text::text(format!("{}%", self.config.volume))
+                    .size(12)
+            )
+            .into()
+    }
+
+    /// Persist the current volume and mute state
+    fn save_volume(&self) {
+        // The in-memory config already holds the new values, so write
+        // them directly instead of through the `set_*` helpers.
+        if let Some(handler) = &self.config_handler {
+            if let Err(e) = handler.set("volume", self.config.volume) {
+                tracing::error!("Failed to save volume: {}", e);
+            }
+            if let Err(e) = handler.set("muted", self.config.muted) {
+                tracing::error!("Failed to save mute state: {}", e);
+            }
+        }
+    }
+
+    /// Text for the panel button tooltip
+    fn tooltip_text(&self) -> String {
+        let Some(channel) = self.current_channel_idx.and_then(|idx| self.channels.get(idx)) else {
+            return "Internet Radio".to_string();
+        };
+
+        let mut text = match self.now_playing.track_line() {
+            Some(track) => format!("{}\n{}", channel.name, track),
+            None => channel.name.clone(),
+        };
+        if let Some(sleep) = self.sleep_timer_text() {
+            text.push('\n');
+            text.push_str(&sleep);
+        }
+        text
+    }
+
+    /// Remaining sleep timer time, if the timer is running
+    fn sleep_timer_text(&self) -> Option<String> {
+        let timer = self.sleep_timer.as_ref()?;
+        match timer.remaining(Instant::now()) {
+            Some(remaining) => Some(format!("Sleeping in {}", format_duration(remaining))),
+            None => Some("Sleeping after this track".to_string()),
+        }
+    }
+
+    /// View for the sleep timer while playing or while the timer runs
+    fn view_sleep_timer(&self) -> Option<Element<'_, Message>> {
+        if let Some(text) = self.sleep_timer_text() {
+            return Some(
+                widget::settings::item(
+                    text,
+                    widget::button::text("Cancel").on_press(Message::CancelSleepTimer),
+                )
+                .into(),
+            );
+        }
+        if self.current_channel_idx.is_none() {
+            return None;
+        }
+
+        let mut column = widget::column()
+            .spacing(5)
+            .push(widget::settings::item(
+                "Sleep timer",
+                widget::dropdown(
+                    self.sleep_timer_options.as_slice(),
+                    Some(if self.sleep_timer_custom.is_some() { self.sleep_timer_options.len() - 1 } else { 0 }),
+                    Message::SelectSleepTimer,
+                ),
+            ));
+
+        if let Some(minutes) = &self.sleep_timer_custom {
+            let valid = parse_sleep_minutes(minutes).is_some();
+            column = column.push(
+                widget::row()
+                    .spacing(5)
+                    .align_y(cosmic::iced::Alignment::Center)
+                    .push(
+                        widget::text_input("Minutes", minutes)
+                            .on_input(Message::SleepTimerCustomChanged)
+                            .on_submit(|_| Message::StartCustomSleepTimer)
+                    )
+                    .push(
+                        widget::button::text("Start")
+                            .on_press_maybe(valid.then_some(Message::StartCustomSleepTimer))
+                    )
+            );
+        }
+
+        Some(column.into())
+    }
+
+    /// Start playing the channel at `idx`, replacing any current playback
+    ///
+    /// Playlist URLs are resolved first, playback starts on `StreamResolved`.
+    fn start_channel(&mut self, idx: usize) -> Task<cosmic::Action<Message>> {
+        let Some(uris) = self.channels.get(idx).map(Channel::uris) else {
+            return Task::none();
+        };
+        let Some(player) = &mut self.player else {
+            return Task::none();
+        };
+
+        // Stop any current playback
+        if let Err(e) = player.stop() {
+            tracing::error!("Failed to stop previous playback: {}", e);
+        }
+        if self.current_channel_idx != Some(idx) {
+            self.stop_recording();
+            // The track the timer waits for was on the previous station
+            if let Some(SleepTimer::EndOfTrack { .. }) = self.sleep_timer {
+                self.sleep_timer = Some(SleepTimer::end_of_track(None));
+            }
+        }
+
+        let scrobble = self.scrobble_track_change(false);
+        self.play_state = State::Null;
+        self.paused_at = None;
+        self.timeshift_delay = Duration::ZERO;
+        self.current_channel_idx = Some(idx);
+        self.last_channel_idx = Some(idx);
+        self.now_playing = NowPlaying::default();
+        self.error_message = None;
+
+        self.play_request += 1;
+        let request = self.play_request;
+        let resolve = Task::perform(
+            async move { playlist::resolve_all(&uris).await.map_err(|e| e.to_string()) },
+            move |result| Message::StreamResolved(request, result),
+        )
+        .map(cosmic::Action::App);
+        Task::batch([scrobble, resolve])
+    }
+
+    /// View for pause/resume, stop and jump-to-live controls while playing or paused
+    fn view_playback_controls(&self) -> Option<Element<'_, Message>> {
+        let paused = self.play_state == State::Paused && self.paused_at.is_some();
+        if self.play_state != State::Playing && !paused {
+            return None;
+        }
+
+        let live = self.timeshift_delay < player::LIVE_MARGIN;
+        let label = if paused && live {
+            "Paused".to_string()
+        } else if paused {
+            format!("Paused, {} behind live", format_duration(self.timeshift_delay))
+        } else if live {
+            "Live".to_string()
+        } else {
+            format!("{} behind live", format_duration(self.timeshift_delay))
+        };
+
+        let mut buttons = widget::row()
+            .spacing(5)
+            .align_y(cosmic::iced::Alignment::Center);
+
+        buttons = buttons.push(if paused {
+            widget::button::icon(widget::icon::from_name("media-playback-start-symbolic"))
+                .on_press(Message::ResumePlayback)
+        } else {
+            widget::button::icon(widget::icon::from_name("media-playback-pause-symbolic"))
+                .on_press(Message::PausePlayback)
+        });
+
+        buttons = buttons.push(
+            widget::button::icon(widget::icon::from_name("media-playback-stop-symbolic"))
+                .on_press(Message::StopPlayback)
+        );
+
+        if paused || !live {
+            buttons = buttons.push(
+                widget::button::text("Jump to Live")
+                    .on_press(Message::JumpToLive)
+            );
+        }
+
+        Some(widget::settings::item(label, buttons).into())
+    }
+
+    /// View for the reconnect status, if reconnecting or given up
+    fn view_reconnect_status(&self) -> Option<Element<'_, Message>> {
+        let item = match self.reconnect_status? {
+            ReconnectStatus::Retrying { attempt } => widget::settings::item(
+                format!(
+                    "Connection lost, reconnecting ({} of {})…",
+                    attempt,
+                    self.reconnector.policy().max_attempts
+                ),
+                widget::button::text("Cancel").on_press(Message::StopPlayback),
+            ),
+            ReconnectStatus::GaveUp { channel_idx } => {
+                let name = self.channels.get(channel_idx)
+                    .map(|c| c.name.as_str())
+                    .unwrap_or("station");
+                widget::settings::item(
+                    format!("Could not reconnect to {}", name),
+                    widget::button::text("Retry").on_press(Message::PlayChannel(channel_idx)),
+                )
+            }
+            ReconnectStatus::LostWhilePaused { channel_idx } => {
+                let name = self.channels.get(channel_idx)
+                    .map(|c| c.name.as_str())
+                    .unwrap_or("station");
+                widget::settings::item(
+                    format!("Lost the connection to {} while paused", name),
+                    widget::button::text("Play live").on_press(Message::PlayChannel(channel_idx)),
+                )
+            }
+        };
+        Some(item.into())
+    }
+
+    /// Index of the channel `step` positions away from the current one, wrapping around
+    ///
+    /// Cycles through the favourites if there are any, otherwise through all channels.
+    fn adjacent_channel(&self, step: isize) -> Option<usize> {
+        let mut cycle: Vec<usize> = self.channels.iter()
+            .enumerate()
+            .filter(|(_, c)| c.favourite)
+            .map(|(idx, _)| idx)
+            .collect();
+        if cycle.is_empty() {
+            cycle = (0..self.channels.len()).collect();
+        }
+        if cycle.is_empty() {
+            return None;
+        }
+
+        let Some(current) = self.current_channel_idx.or(self.last_channel_idx) else {
+            return Some(cycle[0]);
+        };
+        let pos = match cycle.iter().position(|idx| *idx == current) {
+            Some(pos) => pos as isize + step,
+            // Not in the cycle, so continue from where it would sit in the list
+            None => {
+                let insert = cycle.partition_point(|idx| *idx < current) as isize;
+                if step > 0 { insert + step - 1 } else { insert + step }
+            }
+        };
+        Some(cycle[pos.rem_euclid(cycle.len() as isize) as usize])
+    }
+
+    /// Current state as exposed over MPRIS
+    fn mpris_state(&self) -> mpris::State {
+        let channel = self.current_channel_idx.and_then(|idx| self.channels.get(idx));
+        let status = match (self.play_state, channel) {
+            (State::Playing, Some(_)) => mpris::PlaybackStatus::Playing,
+            (State::Paused, Some(_)) => mpris::PlaybackStatus::Paused,
+            _ => mpris::PlaybackStatus::Stopped,
+        };
+
+        mpris::State {
+            status,
+            volume: if self.config.muted { 0.0 } else { f64::from(self.config.volume) / 100.0 },
+            channel_idx: self.current_channel_idx,
+            station: channel.map(|c| c.name.clone()),
+            uri: channel
+                .and(self.player.as_ref())
+                .and_then(|player| player.current_uri())
+                .map(str::to_string),
+            now_playing: self.now_playing.clone(),
+            can_switch: !self.channels.is_empty(),
+        }
+    }
+
+    /// Start loading the logos of stations that haven't been requested yet.
+    fn load_logos(&mut self) -> Task<cosmic::Action<Message>> {
+        let mut tasks = Vec::new();
+        for source in self.channels.iter().filter_map(LogoSource::for_channel) {
+            let key = source.key();
+            if self.logos.contains_key(&key) {
+                continue;
+            }
+            self.logos.insert(key.clone(), LogoState::Loading);
+            tasks.push(
+                Task::perform(
+                    async move { logos::fetch(&source, &logos::cache_dir()).await.map_err(|e| e.to_string()) },
+                    move |result| Message::LogoLoaded(key.clone(), result),
+                )
+                .map(cosmic::Action::App),
+            );
+        }
+        Task::batch(tasks)
+    }
+
+    /// Point the current and last channel indices at the same stations
+    /// after the channel list changed, stopping playback if the current one is gone.
+    fn reindex_channels(&mut self, previous: &[Channel]) {
+        let find = |idx: Option<usize>| {
+            let id = &previous.get(idx?)?.id;
+            self.channels.iter().position(|channel| &channel.id == id)
+        };
+        let current = find(self.current_channel_idx);
+        let last = find(self.last_channel_idx);
+        let selected = find(self.selected_station);
+        // A failed reconnect can only be retried while its station is listed
+        let gave_up = match self.reconnect_status {
+            Some(ReconnectStatus::GaveUp { channel_idx }) => find(Some(channel_idx))
+                .map(|channel_idx| ReconnectStatus::GaveUp { channel_idx }),
+            Some(ReconnectStatus::LostWhilePaused { channel_idx }) => find(Some(channel_idx))
+                .map(|channel_idx| ReconnectStatus::LostWhilePaused { channel_idx }),
+            status => status,
+        };
+
+        if self.current_channel_idx.is_some() && current.is_none() {
+            if let Some(player) = &mut self.player {
+                let _ = player.stop();
+            }
+            self.stop_recording();
+            self.reconnect_status = None;
+        } else {
+            self.reconnect_status = gave_up;
+        }
+        self.current_channel_idx = current;
+        self.last_channel_idx = last;
+        self.selected_station = selected;
+    }
+
+    /// The message closing the form or view shown instead of the station
+    /// list, in the order `view_window` picks them.
+    fn close_message(&self) -> Option<Message> {
+        if self.editing_station_idx.is_some() {
+            Some(Message::CancelEditStation)
+        } else if self.deleting_station_idx.is_some() {
+            Some(Message::CancelDeleteStation)
+        } else if self.alarm_form.is_some() {
+            Some(Message::CancelAlarm)
+        } else if self.showing_alarms {
+            Some(Message::ToggleAlarms)
+        } else if self.schedule_form.is_some() {
+            Some(Message::CancelScheduledRecording)
+        } else if self.showing_schedule {
+            Some(Message::ToggleSchedule)
+        } else if self.showing_history {
+            Some(Message::ToggleHistory)
+        } else if self.showing_liked {
+            Some(Message::ToggleLiked)
+        } else if self.scrobble_form.is_some() {
+            Some(Message::CancelScrobbling)
+        } else if self.showing_station_lists {
+            Some(Message::ToggleStationLists)
+        } else if self.editing_recording_settings {
+            Some(Message::CancelRecordingSettings)
+        } else if self.editing_shortcuts {
+            Some(Message::CancelShortcuts)
+        } else if self.browsing {
+            Some(Message::ToggleBrowse)
+        } else if self.adding_station {
+            Some(Message::CancelAddStation)
+        } else {
+            None
+        }
+    }
+
+    /// Send the current state to the MPRIS service, if it is running
+    fn publish_mpris(&self) {
+        if let Some(mpris) = &self.mpris {
+            let _ = mpris.send(self.mpris_state());
+        }
+    }
+
+    /// Apply the configured volume and mute state to the player
+    ///
+    /// The volume is lowered while the sleep timer fades out and while an
+    /// alarm ramps up.
+    fn apply_volume(&self) {
+        if let Some(player) = &self.player {
+            let now = Instant::now();
+            let fade = self.sleep_timer.as_ref()
+                .map_or(1.0, |timer| timer.volume_factor(now));
+            let ramp = self.ringing_alarm.as_ref()
+                .map_or(1.0, |alarm| alarm.volume_factor(now));
+            player.set_volume(f64::from(self.config.volume) / 100.0 * fade * ramp);
+            player.set_muted(self.config.muted);
+        }
+    }
+
+    /// View for a single channel row with its actions
+    fn view_channel_row<'a>(&'a self, idx: usize, channel: &'a Channel) -> Element<'a, Message> {
+        let is_playing = self.current_channel_idx == Some(idx) 
+            && self.play_state == State::Playing;
+        
+        let icon_name = if is_playing {
+            "media-playback-stop-symbolic"
+        } else {
+            "media-playback-start-symbolic"
+        };
+
+        // Main row with channel name and play button
+        let mut row = widget::row()
+            .spacing(5)
+            .align_y(cosmic::iced::Alignment::Center);
+
+        // Drag handle
+        row = row.push(
+            cosmic::iced::widget::mouse_area(
+                widget::icon::from_name("list-drag-handle-symbolic").size(16)
+            )
+            .interaction(cosmic::iced::mouse::Interaction::Grab)
+            .on_press(Message::StartDragStation(idx))
+        );
+
+        // Logo, or initials until it's loaded
+        let logo = LogoSource::for_channel(channel).and_then(|source| self.logos.get(&source.key()));
+        row = row.push(match logo {
+            Some(LogoState::Ready(handle)) => widget::icon(handle.clone()).size(LOGO_SIZE).into(),
+            _ => view_initials_badge(&channel.name),
+        });
+
+        // Channel name and details (expand to fill)
+        let mut info = widget::column()
+            .width(cosmic::iced::Length::Fill)
+            .push(widget::text::text(&channel.name));
+        if let Some(details) = channel.details_line() {
+            info = info.push(widget::text::text(details).size(12));
+        }
+        if let Some(notes) = &channel.notes {
+            info = info.push(widget::text::text(truncate(notes, ROW_NOTES_MAX_CHARS)).size(12));
+        }
+        row = row.push(info);
+
+        // Play/Stop button
+        row = row.push(
+            widget::button::icon(widget::icon::from_name(icon_name))
+                .on_press(if is_playing {
+                    Message::StopPlayback
+                } else {
+                    Message::PlayChannel(idx)
+                })
+        );
+
+        // Favourite toggle
+        row = row.push(
+            widget::button::icon(widget::icon::from_name(if channel.favourite {
+                "starred-symbolic"
+            } else {
+                "non-starred-symbolic"
+            }))
+            .on_press(Message::ToggleFavourite(idx))
+        );
+
+        // Homepage button
+        if channel.homepage.is_some() {
+            row = row.push(
+                widget::button::icon(widget::icon::from_name("web-browser-symbolic"))
+                    .on_press(Message::OpenHomepage(idx))
+            );
+        }
+
+        // Move up and down within the section
+        row = row.push(
+            widget::button::icon(widget::icon::from_name("go-up-symbolic"))
+                .on_press_maybe(self.section_neighbour(idx, true).map(|_| Message::MoveStationUp(idx)))
+        );
+        row = row.push(
+            widget::button::icon(widget::icon::from_name("go-down-symbolic"))
+                .on_press_maybe(self.section_neighbour(idx, false).map(|_| Message::MoveStationDown(idx)))
+        );
+
+        // Edit button
+        row = row.push(
+            widget::button::icon(widget::icon::from_name("edit-symbolic"))
+                .on_press(Message::StartEditStation(idx))
+        );
+
+        // Delete button
+        row = row.push(
+            widget::button::icon(widget::icon::from_name("edit-delete-symbolic"))
+                .on_press(Message::StartDeleteStation(idx))
+        );
+
+        // While dragging, rows track the pointer and the drop target is highlighted
+        if self.dragging_station_idx.is_none() {
+            return row.into();
+        }
+        let mut row = widget::container(row);
+        if self.drop_target == Some(DropTarget::Station(idx)) {
+            row = row.class(cosmic::theme::Container::Card);
+        }
+        cosmic::iced::widget::mouse_area(row)
+            .on_enter(Message::DragOverStation(idx))
+            .into()
+    }
+
+    /// Header of a group section, collapsing it on click. While dragging a
+    /// station, it can be dropped on the header to move it to the group.
+    fn view_group_header<'a>(&'a self, group: Option<&'a String>, label: &'a str) -> Element<'a, Message> {
+        let header: Element<'a, Message> = match group {
+            Some(name) => {
+                let collapsed = self.config.collapsed_groups.contains(name);
+                let count = self.channels.iter().filter(|c| c.group.as_ref() == Some(name)).count();
+                widget::button::custom(
+                    widget::row()
+                        .spacing(5)
+                        .align_y(cosmic::iced::Alignment::Center)
+                        .push(widget::icon::from_name(if collapsed { "go-next-symbolic" } else { "go-down-symbolic" }).size(16))
+                        .push(widget::text::text(format!("{} ({})", label, count)).size(14))
+                )
+                .class(cosmic::theme::Button::Text)
+                .on_press(Message::ToggleGroup(name.clone()))
+                .into()
+            }
+            None => widget::text::text(label).size(14).into(),
+        };
+
+        if self.dragging_station_idx.is_none() {
+            return header;
+        }
+        let target = DropTarget::Group(group.cloned());
+        let mut header = widget::container(header).width(cosmic::iced::Length::Fill);
+        if self.drop_target.as_ref() == Some(&target) {
+            header = header.class(cosmic::theme::Container::Card);
+        }
+        cosmic::iced::widget::mouse_area(header)
+            .on_enter(Message::DragOverGroup(group.cloned()))
+            .into()
+    }
+
+    /// The station before (`up`) or after `idx` in the same section of the list.
+    fn section_neighbour(&self, idx: usize, up: bool) -> Option<usize> {
+        let channel = self.channels.get(idx)?;
+        let same_section = |i: &usize| {
+            self.channels[*i].favourite == channel.favourite && self.channels[*i].group == channel.group
+        };
+        if up {
+            (0..idx).rev().find(same_section)
+        } else {
+            (idx + 1..self.channels.len()).find(same_section)
+        }
+    }
+
+    /// Save the channels with their groups.
+    fn save_channels(&self) -> Result<(), ChannelError> {
+        channels::save_channels(&ChannelList::from_flat(&self.channels, &self.groups))
+    }
+
+    fn channels_save_failed(&mut self, error: ChannelError) {
+        tracing::error!("Failed to save channels: {}", error);
+        self.error_message = Some(format!("Failed to save: {}", error));
+    }
+
+    /// Move the station at `from` to position `to` and save the new order,
+    /// keeping the indices of the playing and last played stations correct.
+    fn move_station(&mut self, from: usize, to: usize) -> Result<(), ChannelError> {
+        if from >= self.channels.len() || to >= self.channels.len() {
+            return Ok(());
+        }
+        channels::move_channel(&mut self.channels, from, to);
+        if let Err(e) = self.save_channels() {
+            channels::move_channel(&mut self.channels, to, from);
+            return Err(e);
+        }
+
+        let moved = |idx| channels::moved_index(idx, from, to);
+        self.current_channel_idx = self.current_channel_idx.map(moved);
+        self.last_channel_idx = self.last_channel_idx.map(moved);
+        self.selected_station = self.selected_station.map(moved);
+        match self.reconnect_status {
+            Some(ReconnectStatus::GaveUp { channel_idx }) => {
+                self.reconnect_status = Some(ReconnectStatus::GaveUp { channel_idx: moved(channel_idx) });
+            }
+            Some(ReconnectStatus::LostWhilePaused { channel_idx }) => {
+                self.reconnect_status = Some(ReconnectStatus::LostWhilePaused { channel_idx: moved(channel_idx) });
+            }
+            _ => {}
+        }
+        self.publish_mpris();
+        Ok(())
+    }
+
+    /// Move the station at `from` into `group`, taking the place of the
+    /// station at `to` in that group, or else going to the end of it.
+    fn move_station_to_group(&mut self, from: usize, group: Option<String>, to: Option<usize>) -> Result<(), ChannelError> {
+        let Some(channel) = self.channels.get_mut(from) else {
+            return Ok(());
+        };
+        let previous = std::mem::replace(&mut channel.group, group.clone());
+        if let Some(name) = &group
+            && !self.groups.contains(name)
+        {
+            self.groups.push(name.clone());
+        }
+
+        // The station already counts towards the end of its new group
+        let to = to.unwrap_or_else(|| channels::group_end(&self.channels, &self.groups, group.as_deref()) - 1);
+        if let Err(e) = self.move_station(from, to) {
+            self.channels[from].group = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Add a station at the end of its group and save.
+    fn insert_station(&mut self, channel: Channel) -> Result<(), ChannelError> {
+        if let Some(name) = &channel.group
+            && !self.groups.contains(name)
+        {
+            self.groups.push(name.clone());
+        }
+        let to = channels::group_end(&self.channels, &self.groups, channel.group.as_deref());
+        self.channels.push(channel);
+        let from = self.channels.len() - 1;
+        if let Err(e) = self.move_station(from, to) {
+            self.channels.pop();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Favourites pinned at the top, then the ungrouped channels and a
+    /// collapsible section per group.
+    fn view_station_sections(&self) -> Vec<Element<'_, Message>> {
+        let selected = self.selected_station(&self.listed_stations());
+        let mut items: Vec<Element<'_, Message>> = Vec::new();
+        let has_favourites = self.channels.iter().any(|c| c.favourite);
+        let has_groups = self.channels.iter().any(|c| c.group.is_some());
+        if has_favourites {
+            items.push(widget::text::text("Favourites").size(14).into());
+            for (idx, channel) in self.channels.iter().enumerate().filter(|(_, c)| c.favourite) {
+                items.push(self.view_station_item(idx, channel, selected));
+            }
+        }
+        if has_favourites || has_groups {
+            items.push(self.view_group_header(None, "Stations"));
+        }
+        for (idx, channel) in self.channels.iter().enumerate().filter(|(_, c)| !c.favourite && c.group.is_none()) {
+            items.push(self.view_station_item(idx, channel, selected));
+        }
+        for group in &self.groups {
+            let in_group = |c: &&Channel| c.group.as_ref() == Some(group);
+            if !self.channels.iter().any(|c| in_group(&c)) {
+                continue;
+            }
+            items.push(self.view_group_header(Some(group), group));
+            if self.config.collapsed_groups.contains(group) {
+                continue;
+            }
+            for (idx, channel) in self.channels.iter().enumerate().filter(|(_, c)| !c.favourite && in_group(c)) {
+                items.push(self.view_station_item(idx, channel, selected));
+            }
+        }
+        items
+    }
+
+    /// The channels matching the filter, best match first, with the one
+    /// Enter plays highlighted.
+    fn view_station_matches(&self) -> Vec<Element<'_, Message>> {
+        let matches = self.station_matches();
+        if matches.is_empty() {
+            return vec![widget::text::text("No matching stations").size(12).into()];
+        }
+        let selected = self.selected_station(&matches);
+        matches
+            .into_iter()
+            .map(|idx| self.view_station_item(idx, &self.channels[idx], selected))
+            .collect()
+    }
+
+    /// A station row, highlighted if it's the `selected` one.
+    fn view_station_item<'a>(&'a self, idx: usize, channel: &'a Channel, selected: Option<usize>) -> Element<'a, Message> {
+        let row = self.view_channel_row(idx, channel);
+        if selected != Some(idx) {
+            return row;
+        }
+        widget::container(row)
+            .class(cosmic::theme::Container::Card)
+            .into()
+    }
+
+    /// Indices of the channels matching the filter, best match first.
+    fn station_matches(&self) -> Vec<usize> {
+        channels::search(&self.channels, &self.station_query)
+    }
+
+    /// Indices of the listed channels in the order shown: the matches while
+    /// filtering, otherwise the sections without collapsed groups.
+    fn listed_stations(&self) -> Vec<usize> {
+        if !self.station_query.trim().is_empty() {
+            return self.station_matches();
+        }
+        let section = |favourite: bool, group: Option<&String>| {
+            self.channels
+                .iter()
+                .enumerate()
+                .filter(move |(_, c)| c.favourite == favourite && (favourite || c.group.as_ref() == group))
+                .map(|(idx, _)| idx)
+        };
+        let expanded = self.groups.iter().filter(|group| !self.config.collapsed_groups.contains(group));
+        section(true, None)
+            .chain(section(false, None))
+            .chain(expanded.flat_map(|group| section(false, Some(group))))
+            .collect()
+    }
+
+    /// The highlighted channel if it's listed. While filtering, the best
+    /// match is highlighted until another one is picked.
+    fn selected_station(&self, listed: &[usize]) -> Option<usize> {
+        match self.selected_station {
+            Some(idx) if listed.contains(&idx) => Some(idx),
+            _ if !self.station_query.trim().is_empty() => listed.first().copied(),
+            _ => None,
+        }
+    }
+
+    /// View for the channel list
+    fn view_channel_list(&self) -> Element<'_, Message> {
+        // Build the channel list
+        let mut content_list = widget::column()
+            .padding(5)
+            .spacing(2);
+
+        // Add header with current status
+        let header_text = if let Some(idx) = self.current_channel_idx {
+            if let Some(channel) = self.channels.get(idx) {
+                format!("Now Playing: {}", channel.name)
+            } else {
+                "Internet Radio".to_string()
+            }
+        } else {
+            "Internet Radio".to_string()
+        };
+
+        content_list = content_list.push(
+            widget::text::text(header_text)
+                .size(16)
+        );
+
+        // Add the current track and stream details
+        if self.current_channel_idx.is_some() {
+            if let Some(track) = self.now_playing.track_line() {
+                let liked = self.current_liked_idx().is_some();
+                content_list = content_list.push(
+                    widget::row()
+                        .spacing(5)
+                        .align_y(cosmic::iced::Alignment::Center)
+                        .push(
+                            widget::text::text(track)
+                                .size(14)
+                                .width(cosmic::iced::Length::Fill)
+                        )
+                        .push(
+                            widget::button::icon(widget::icon::from_name("emblem-favorite-symbolic"))
+                                .class(if liked {
+                                    cosmic::theme::Button::Suggested
+                                } else {
+                                    cosmic::theme::Button::Icon
+                                })
+                                .on_press(Message::ToggleLikeCurrent)
+                        )
+                );
+            }
+            if let Some(error) = self.view_liked_error() {
+                content_list = content_list.push(error);
+            }
+            if let Some(details) = self.now_playing.details_line() {
+                content_list = content_list.push(
+                    widget::text::text(details)
+                        .size(12)
+                );
+            }
+        }
+
+        // Add stop button if playing
+        if let Some(controls) = self.view_playback_controls() {
+            content_list = content_list.push(controls);
+        }
+
+        // Add alarm status
+        if let Some(status) = self.view_alarm_status() {
+            content_list = content_list.push(status);
+        }
+
+        // Add reconnect status
+        if let Some(status) = self.view_reconnect_status() {
+            content_list = content_list.push(status);
+        }
+
+        // Add record toggle
+        if let Some(recording) = self.view_recording_controls() {
+            content_list = content_list.push(recording);
+        }
+
+        // Add scheduled recordings in progress
+        for item in self.view_scheduled_captures() {
+            content_list = content_list.push(item);
+        }
+
+        // Add sleep timer
+        if let Some(sleep_timer) = self.view_sleep_timer() {
+            content_list = content_list.push(sleep_timer);
+        }
+
+        // Add volume controls
+        content_list = content_list.push(self.view_volume_controls());
+
+        // Add track change notifications toggle
+        content_list = content_list.push(widget::settings::item(
+            "Notify on track change",
+            widget::toggler(self.config.track_notifications)
+                .on_toggle(Message::SetTrackNotifications),
+        ));
+
+        // Add separator
+        content_list = content_list.push(widget::divider::horizontal::default());
+
+        // Add the station filter
+        content_list = content_list.push(
+            widget::text_input("Search stations", &self.station_query)
+                .on_input(Message::StationQueryChanged)
+                .on_submit(|_| Message::StationKey(StationKey::Play))
+        );
+
+        // Add the matching stations while filtering, otherwise all of them
+        let stations = if self.station_query.trim().is_empty() {
+            self.view_station_sections()
+        } else {
+            self.view_station_matches()
+        };
+        for item in stations {
+            content_list = content_list.push(item);
+        }
+
+        // Add separator before Add Station button
+        content_list = content_list.push(widget::divider::horizontal::default());
+
+        // Add Station, Alarms, Schedule, History, Liked, Scrobbling, Import & Export and Shortcuts buttons
+        content_list = content_list.push(
+            widget::flex_row(vec![
+                widget::button::text("+ Add Station")
+                    .on_press(Message::ToggleAddStation)
+                    .into(),
+                widget::button::text("Alarms")
+                    .on_press(Message::ToggleAlarms)
+                    .into(),
+                widget::button::text("Schedule")
+                    .on_press(Message::ToggleSchedule)
+                    .into(),
+                widget::button::text("History")
+                    .on_press(Message::ToggleHistory)
+                    .into(),
+                widget::button::text("Liked")
+                    .on_press(Message::ToggleLiked)
+                    .into(),
+                widget::button::text("Scrobbling")
+                    .on_press(Message::StartEditScrobbling)
+                    .into(),
+                widget::button::text("Import & Export")
+                    .on_press(Message::ToggleStationLists)
+                    .into(),
+                widget::button::text("Shortcuts")
+                    .on_press(Message::StartEditShortcuts)
+                    .into(),
+            ])
+            .row_spacing(5)
+            .column_spacing(10)
+        );
+
+        self.core.applet.popup_container(content_list).into()
+    }
+}
+
+/// Create a COSMIC application from the app model
+impl cosmic::Application for AppModel {
+    /// The async executor that will be used to run your application's commands.
+    type Executor = cosmic::executor::Default;
+
+    /// Data that your application receives to its init method.
+    type Flags = ();
+
+    /// Messages which the application and its widgets will emit.
+    type Message = Message;
+
+    /// Unique identifier in RDNN (reverse domain name notation) format.
+    const APP_ID: &'static str = "com.github.maxattwell.cosmic-ext-applet-radio";
+
+    fn core(&self) -> &cosmic::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::Core {
+        &mut self.core
+    }
+
+    /// Initializes the application with any given flags and startup commands.
+    fn init(
+        core: cosmic::Core,
+        _flags: Self::Flags,
+    ) -> (Self, Task<cosmic::Action<Self::Message>>) {
+        let config_handler = cosmic_config::Config::new(Self::APP_ID, Config::VERSION).ok();
+
+        // Construct the app model with the runtime's core.
+        let app = AppModel {
+            core,
+            config: config_handler
+                .as_ref()
+                .map(|context| match Config::get_entry(context) {
+                    Ok(config) => config,
+                    Err((_errors, config)) => {
+                        config
+                    }
+                })
+                .unwrap_or_default(),
+            config_handler,
+            ..Default::default()
+        };
+
+        // Restore the volume from the last run
+        app.apply_volume();
+
+        // Load channels asynchronously
+        let load_channels_task = Task::perform(
+            async { channels::load_channels() },
+            |result| match result {
+                Ok(list) => Message::ChannelsLoaded(list),
+                Err(e) => Message::ChannelError(e.to_string()),
+            },
+        ).map(|msg| cosmic::Action::App(msg));
+
+        // Load alarms, a broken file only disables them
+        let load_alarms_task = Task::perform(
+            async { alarms::load_alarms() },
+            |result| match result {
+                Ok(list) => Message::AlarmsLoaded(list.alarms),
+                Err(e) => {
+                    tracing::error!("Failed to load alarms: {}", e);
+                    Message::AlarmsLoaded(Vec::new())
+                }
+            },
+        ).map(cosmic::Action::App);
+
+        // Load the recording schedule
+        let load_schedule_task = Task::perform(
+            async { recording_schedule::load_schedule() },
+            |result| match result {
+                Ok(schedule) => Message::ScheduleLoaded(schedule.recordings),
+                Err(e) => {
+                    tracing::error!("Failed to load recording schedule: {}", e);
+                    Message::ScheduleLoaded(Vec::new())
+                }
+            },
+        ).map(cosmic::Action::App);
+
+        // Load the played tracks
+        let load_history_task = Task::perform(
+            async { history::load_history() },
+            |result| match result {
+                Ok(history) => Message::HistoryLoaded(history),
+                Err(e) => {
+                    tracing::error!("Failed to load history: {}", e);
+                    Message::HistoryLoaded(Vec::new())
+                }
+            },
+        ).map(cosmic::Action::App);
+
+        // Load the liked tracks
+        let load_liked_task = Task::perform(
+            async { liked::load_liked() },
+            |result| match result {
+                Ok(list) => Message::LikedLoaded(list.tracks),
+                Err(e) => {
+                    tracing::error!("Failed to load liked tracks: {}", e);
+                    Message::LikedLoaded(Vec::new())
+                }
+            },
+        ).map(cosmic::Action::App);
+
+        // Load the scrobbling settings and queue
+        let load_scrobbling_task = Task::perform(
+            async {
+                let settings = scrobbler::load_settings().unwrap_or_else(|e| {
+                    tracing::error!("Failed to load scrobbling settings: {}", e);
+                    ScrobbleSettings::default()
+                });
+                let queue = scrobbler::load_queue().unwrap_or_else(|e| {
+                    tracing::error!("Failed to load scrobble queue: {}", e);
+                    Vec::new()
+                });
+                (settings, queue)
+            },
+            |(settings, queue)| Message::ScrobblingLoaded(settings, queue),
+        ).map(cosmic::Action::App);
+
+        (app, Task::batch([
+            load_channels_task,
+            load_alarms_task,
+            load_schedule_task,
+            load_history_task,
+            load_liked_task,
+            load_scrobbling_task,
+        ]))
+    }
+
+    fn on_close_requested(&self, id: Id) -> Option<Message> {
+        Some(Message::PopupClosed(id))
+    }
+
+    /// Esc closes the open form or view, then clears the station filter,
+    /// then closes the popup.
+    fn on_escape(&mut self) -> Task<cosmic::Action<Self::Message>> {
+        if let Some(message) = self.close_message() {
+            return self.update(message);
+        }
+        if !self.station_query.is_empty() {
+            return self.update(Message::StationQueryChanged(String::new()));
+        }
+        if self.popup.is_some() {
+            return self.update(Message::TogglePopup);
+        }
+        Task::none()
+    }
+
+    /// Describes the interface based on the current state of the application model.
+    ///
+    /// The applet's button in the panel will be drawn using the main view method.
+    /// This view should emit messages to toggle the applet's popup window, which will
+    /// be drawn using the `view_window` method.
+    fn view(&self) -> Element<'_, Self::Message> {
+        if self.player.is_none() {
+            return self.core
+                .applet
+                .icon_button("dialog-error-symbolic")
+                .into();
+        }
+
+        let icon = if self.play_state == State::Playing {
+            "audio-card-symbolic"
+        } else {
+            "audio-card-symbolic"
+        };
+
+        // Show the current track next to the icon on horizontal panels
+        let track = self.now_playing.track_line()
+            .filter(|_| self.current_channel_idx.is_some() && self.core.applet.is_horizontal());
+
+        let button: Element<'_, Message> = if let Some(track) = track {
+            let content = widget::row()
+                .spacing(4)
+                .align_y(cosmic::iced::Alignment::Center)
+                .push(
+                    widget::icon::from_name(icon)
+                        .size(self.core.applet.suggested_size(true).0)
+                        .symbolic(true)
+                        .icon()
+                )
+                .push(self.core.applet.text(truncate(&track, PANEL_TRACK_MAX_CHARS)));
+
+            widget::button::custom(content)
+                .padding([0, self.core.applet.suggested_padding(true)])
+                .class(cosmic::theme::Button::AppletIcon)
+                .on_press(Message::TogglePopup)
+                .into()
+        } else {
+            self.core
+                .applet
+                .icon_button(icon)
+                .on_press(Message::TogglePopup)
+                .into()
+        };
+
+        // Scroll changes the volume, middle-click toggles playback
+        let button = cosmic::iced::widget::mouse_area(button)
+            .on_middle_press(Message::TogglePlayback)
+            .on_scroll(Message::PanelScrolled);
+
+        widget::tooltip(
+            button,
+            widget::text::text(self.tooltip_text()),
+            widget::tooltip::Position::Bottom,
+        )
+        .into()
+    }
+
+    /// The applet's popup window will be drawn using this view method. If there are
+    /// multiple poups, you may match the id parameter to determine which popup to
+    /// create a view for.
+    fn view_window(&self, _id: Id) -> Element<'_, Self::Message> {
+        if self.player.is_none() {
+            return self.core.applet.popup_container(
+                widget::text::text("Audio initialization failed. Check logs.")
+            ).into();
+        }
+
+        // Show error message if there is one
+        if let Some(error) = &self.error_message {
+            let error_widget = widget::column()
+                .padding(10)
+                .spacing(10)
+                .push(widget::text::text("Error loading channels:").size(14))
+                .push(widget::text::text(error).size(12))
+                .push(
+                    widget::button::text("Use Defaults")
+                        .on_press(Message::ChannelsLoaded(channels::default_channels()))
+                );
+            return self.core.applet.popup_container(error_widget).into();
+        }
+
+        // Show edit station form
+        if let Some(idx) = self.editing_station_idx {
+            return self.view_edit_station_form(idx);
+        }
+
+        // Show delete confirmation
+        if let Some(idx) = self.deleting_station_idx {
+            return self.view_delete_confirmation(idx);
+        }
+
+        // Show alarm form and list
+        if let Some(form) = &self.alarm_form {
+            return self.view_alarm_form(form);
+        }
+        if self.showing_alarms {
+            return self.view_alarms();
+        }
+
+        // Show schedule form and list
+        if let Some(form) = &self.schedule_form {
+            return self.view_schedule_form(form);
+        }
+        if self.showing_schedule {
+            return self.view_schedule();
+        }
+
+        // Show recently played tracks
+        if self.showing_history {
+            return self.view_history();
+        }
+
+        // Show liked tracks
+        if self.showing_liked {
+            return self.view_liked();
+        }
+
+        // Show scrobbling settings
+        if let Some(form) = &self.scrobble_form {
+            return self.view_scrobble_form(form);
+        }
+
+        // Show station list import and export
+        if self.showing_station_lists {
+            return self.view_station_lists();
+        }
+
+        // Show recording settings form
+        if self.editing_recording_settings {
+            return self.view_recording_settings_form();
+        }
+
+        // Show keyboard shortcuts form
+        if self.editing_shortcuts {
+            return self.view_shortcuts_form();
+        }
+
+        // Show station directory search
+        if self.browsing {
+            return self.view_browse();
+        }
+
+        // Show add station form
+        if self.adding_station {
+            return self.view_add_station_form();
+        }
+
+        // Show message if no channels loaded yet
+        if self.channels.is_empty() {
+            let loading_widget = widget::column()
+                .padding(10)
+                .spacing(10)
+                .push(widget::text::text("Loading channels..."))
+                .push(
+                    widget::button::text("Add Station")
+                        .on_press(Message::ToggleAddStation)
+                );
+            return self.core.applet.popup_container(loading_widget).into();
+        }
+
+        self.view_channel_list()
+    }
+
+    /// Register subscriptions for this application.
+    ///
+    /// Subscriptions are long-lived async tasks running in the background which
+    /// emit messages to the application through a channel. They may be conditionally
+    /// activated by selectively appending to the subscription batch, and will
+    /// continue to execute for the duration that they remain in the batch.
+    fn subscription(&self) -> Subscription<Self::Message> {
+        struct MySubscription;
+        struct PlayerSubscription;
+        struct MprisSubscription;
+        struct RecordingSubscription;
+        struct NotificationSubscription;
+
+        let mut subs = vec![
+            // Create a subscription which emits updates through a channel.
+            Subscription::run_with_id(
+                std::any::TypeId::of::<MySubscription>(),
+                cosmic::iced::stream::channel(4, move |mut channel| async move {
+                    _ = channel.send(Message::SubscriptionChannel).await;
+
+                    futures_util::future::pending().await
+                }),
+            ),
+            // Watch for application configuration changes.
+            self.core()
+                .watch_config::<Config>(Self::APP_ID)
+                .map(|update| {
+                    Message::UpdateConfig(update.config)
+                }),
+            // Serve MPRIS on the session bus, forwarding commands and state updates.
+            Subscription::run_with_id(
+                std::any::TypeId::of::<MprisSubscription>(),
+                cosmic::iced::stream::channel(10, move |mut channel| async move {
+                    let (commands_tx, mut commands_rx) = tokio::sync::mpsc::unbounded_channel();
+                    let server = match mpris::Server::start(commands_tx).await {
+                        Ok(server) => server,
+                        Err(e) => {
+                            tracing::error!("Failed to start MPRIS service: {}", e);
+                            return futures_util::future::pending().await;
+                        }
+                    };
+
+                    let (state_tx, mut state_rx) = tokio::sync::mpsc::unbounded_channel();
+                    let _ = channel.send(Message::MprisReady(state_tx)).await;
+
+                    loop {
+                        tokio::select! {
+                            Some(command) = commands_rx.recv() => {
+                                let _ = channel.send(Message::Mpris(command)).await;
+                            }
+                            Some(state) = state_rx.recv() => {
+                                if let Err(e) = server.publish(state).await {
+                                    tracing::error!("Failed to publish MPRIS state: {}", e);
+                                }
+                            }
+                            else => break,
+                        }
+                    }
+
+                    futures_util::future::pending().await
+                }),
+            ),
+        ];
+
+        if let Some(player) = &self.player {
+            let bus = player.bus();
+            let pipeline = player.pipeline().clone();
+
+            subs.push(Subscription::run_with_id(
+                std::any::TypeId::of::<PlayerSubscription>(),
+                cosmic::iced::stream::channel(10, move |mut channel| async move {
+                    let mut bus_stream = bus.stream();
+
+                    while let Some(msg) = bus_stream.next().await {
+                        match msg.view() {
+                            MessageView::StateChanged(state_changed) => {
+                                if let Some(src) = msg.src() {
+                                    if let Some(src_pipeline) = src.downcast_ref::<gstreamer::Pipeline>() {
+                                        if src_pipeline == &pipeline {
+                                            let new_state = state_changed.current();
+                                            let _ = channel.send(Message::PlayerStateChanged(new_state)).await;
+                                        }
+                                    }
+                                }
+                            }
+                            MessageView::Tag(tags_msg) => {
+                                let tags = tags_msg.tags();
+                                let _ = channel.send(Message::MetadataUpdated(tags)).await;
+                            }
+                            MessageView::Error(err) => {
+                                tracing::error!("GStreamer error: {} ({:?})", err.error(), err.debug());
+                                let _ = channel.send(Message::StreamError(err.error().to_string())).await;
+                            }
+                            MessageView::Eos(_) => {
+                                let _ = channel.send(Message::StreamEnded).await;
+                            }
+                            _ => (),
+                        }
+                    }
+
+                    futures_util::future::pending().await
+                }),
+            ));
+        }
+
+        // Show notifications on track changes while enabled.
+        if self.config.track_notifications {
+            subs.push(Subscription::run_with_id(
+                std::any::TypeId::of::<NotificationSubscription>(),
+                cosmic::iced::stream::channel(1, move |mut channel| async move {
+                    let notifier = match notifications::Notifier::connect().await {
+                        Ok(notifier) => notifier,
+                        Err(e) => {
+                            tracing::error!("Failed to connect to the notification service: {}", e);
+                            return futures_util::future::pending().await;
+                        }
+                    };
+
+                    let (notifications_tx, notifications_rx) = tokio::sync::mpsc::unbounded_channel();
+                    let _ = channel.send(Message::NotifierReady(notifications_tx)).await;
+                    notifications::run(notifier, notifications_rx, notifications::MIN_INTERVAL).await;
+
+                    futures_util::future::pending().await
+                }),
+            ));
+        }
+
+        // Retry submitting listens queued while offline.
+        let scrobbling = self.scrobbler.as_ref().is_some_and(|scrobbler| scrobbler.is_active());
+        if scrobbling && !self.scrobble_queue.is_empty() {
+            subs.push(cosmic::iced::time::every(SCROBBLE_RETRY_INTERVAL).map(|_| Message::RetryScrobbles));
+        }
+
+        // Check for alarms and scheduled recordings, and raise the volume while an alarm ramps up.
+        subs.push(cosmic::iced::time::every(SCHEDULE_CHECK_INTERVAL).map(|_| Message::ScheduleTick));
+
+        // Keys for the highlighted station, unless a text input took them
+        let showing_list = self.error_message.is_none() && self.close_message().is_none();
+        if self.popup.is_some() && showing_list && self.dragging_station_idx.is_none() {
+            subs.push(cosmic::iced::event::listen_with(|event, status, _window| {
+                use cosmic::iced::keyboard::{key::Named, Event, Key};
+                let cosmic::iced::Event::Keyboard(Event::KeyPressed { key, modifiers, .. }) = event else {
+                    return None;
+                };
+                if status == cosmic::iced::event::Status::Captured {
+                    return None;
+                }
+                let plain = !modifiers.control() && !modifiers.alt() && !modifiers.logo();
+                let key = match key.as_ref() {
+                    Key::Named(Named::ArrowUp) if modifiers.alt() => StationKey::MoveUp,
+                    Key::Named(Named::ArrowDown) if modifiers.alt() => StationKey::MoveDown,
+                    Key::Named(Named::ArrowUp) => return Some(Message::SelectStation(false)),
+                    Key::Named(Named::ArrowDown) => return Some(Message::SelectStation(true)),
+                    Key::Named(Named::Enter) => StationKey::Play,
+                    Key::Named(Named::Delete) => StationKey::Delete,
+                    Key::Character("e" | "E") if plain => StationKey::Edit,
+                    Key::Character("f" | "F") if plain => StationKey::Favourite,
+                    _ => return None,
+                };
+                Some(Message::StationKey(key))
+            }));
+        }
+
+        // A drag ends wherever the mouse button is released
+        if self.dragging_station_idx.is_some() {
+            subs.push(cosmic::iced::event::listen_with(|event, _status, _window| match event {
+                cosmic::iced::Event::Mouse(cosmic::iced::mouse::Event::ButtonReleased(cosmic::iced::mouse::Button::Left)) => {
+                    Some(Message::DropStation)
+                }
+                _ => None,
+            }));
+        }
+        if self.ringing_alarm.as_ref().is_some_and(|alarm| !alarm.ramp.is_zero()) {
+            subs.push(cosmic::iced::time::every(Duration::from_millis(500)).map(|_| Message::AlarmRampTick));
+        }
+
+        // Count up how far playback is behind while paused or catching up.
+        if self.paused_at.is_some() || self.timeshift_delay >= player::LIVE_MARGIN {
+            subs.push(cosmic::iced::time::every(Duration::from_secs(1)).map(|_| Message::TimeshiftTick));
+        }
+
+        // Fade out and stop once the sleep timer runs out.
+        if let Some(SleepTimer::Until(_)) = &self.sleep_timer {
+            subs.push(cosmic::iced::time::every(Duration::from_secs(1)).map(|_| Message::SleepTimerTick));
+        }
+
+        // Record while a recording is active, dropping it stops the recording.
+        let scheduled = self.scheduled_captures.iter().filter_map(|capture| capture.recording.as_ref());
+        for recording in self.recording.iter().chain(scheduled) {
+            let id = recording.id;
+            let uri = recording.uri.clone();
+            let tap = recording.tap.clone();
+            let options = recording.options.clone();
+
+            subs.push(Subscription::run_with_id(
+                (std::any::TypeId::of::<RecordingSubscription>(), id),
+                cosmic::iced::stream::channel(10, move |mut channel| async move {
+                    let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel();
+                    let recording = async {
+                        match tap {
+                            Some(tap) => recorder::record_tapped(tap.attach(), &options, events_tx).await,
+                            None => recorder::record(&uri, &options, events_tx).await,
+                        }
+                    };
+                    tokio::pin!(recording);
+
+                    let result = loop {
+                        tokio::select! {
+                            result = &mut recording => break result,
+                            Some(event) = events_rx.recv() => {
+                                let _ = channel.send(Message::RecordingEvent(id, event)).await;
+                            }
+                        }
+                    };
+                    while let Ok(event) = events_rx.try_recv() {
+                        let _ = channel.send(Message::RecordingEvent(id, event)).await;
+                    }
+                    let _ = channel.send(Message::RecordingFinished(id, result.map_err(|e| e.to_string()))).await;
+
+                    futures_util::future::pending().await
+                }),
+            ));
+        }
+
+        Subscription::batch(subs)
+    }
+
+    /// Handles messages emitted by the application and its widgets.
+    ///
+    /// Tasks may be returned for asynchronous execution of code in the background
+    /// on the application's async runtime. The application will not exit until all
+    /// tasks are finished.
+    fn update(&mut self, message: Self::Message) -> Task<cosmic::Action<Self::Message>> {
+        match message {
+            Message::SubscriptionChannel => {
+                // For example purposes only.
+            }
+            Message::UpdateConfig(config) => {
+                if !config.track_notifications {
+                    self.notifier = None;
+                }
+                self.config = config;
+                self.apply_volume();
+                self.publish_mpris();
+            }
+            Message::TogglePlayback => {
+                if self.current_channel_idx.is_some() {
+                    return self.update(Message::StopPlayback);
+                }
+                if !self.channels.is_empty() {
+                    let idx = self.last_channel_idx
+                        .filter(|idx| *idx < self.channels.len())
+                        .unwrap_or(0);
+                    return self.update(Message::PlayChannel(idx));
+                }
+            },
+            Message::PlayChannel(idx) => {
+                self.reconnector.reset();
+                self.reconnect_status = None;
+                if self.ringing_alarm.take().is_some() {
+                    self.apply_volume();
+                }
+                return self.start_channel(idx);
+            }
+            Message::StreamResolved(request, result) => {
+                // Ignore if superseded by another channel or stopped meanwhile
+                if request != self.play_request {
+                    return Task::none();
+                }
+                let Some(channel) = self.current_channel_idx.and_then(|idx| self.channels.get(idx)) else {
+                    return Task::none();
+                };
+
+                let uris = match result {
+                    Ok(uris) => uris,
+                    Err(e) => return self.update(Message::StreamError(e)),
+                };
+
+                if let Some(player) = &mut self.player {
+                    if let Err(e) = player.play_candidates(uris) {
+                        tracing::error!("Failed to start playback of {}: {}", channel.name, e);
+                        self.error_message = Some(format!("Failed to play {}", channel.name));
+                        self.current_channel_idx = None;
+                    } else {
+                        tracing::info!("Started playing: {} ({})", channel.name, player.current_uri().unwrap_or_default());
+                    }
+                }
+            }
+            Message::StopPlayback => {
+                if let Some(player) = &mut self.player {
+                    if let Err(e) = player.stop() {
+                        tracing::error!("Failed to stop playback: {}", e);
+                    }
+                }
+                self.stop_recording();
+                // Playback is over, so the timer has nothing left to stop
+                self.sleep_timer = None;
+                self.sleep_timer_custom = None;
+                self.ringing_alarm = None;
+                self.apply_volume();
+                self.reconnect_status = None;
+                self.paused_at = None;
+                self.timeshift_delay = Duration::ZERO;
+                let scrobble = self.scrobble_track_change(false);
+                self.current_channel_idx = None;
+                self.now_playing = NowPlaying::default();
+                self.publish_mpris();
+                return scrobble;
+            }
+            Message::ToggleRecording => {
+                if self.recording.is_some() {
+                    self.stop_recording();
+                    return Task::none();
+                }
+
+                let Some(player) = &self.player else {
+                    return Task::none();
+                };
+                let uri = player.current_uri().map(str::to_string);
+                let tap = player.tap();
+                if let (Some(uri), Some(options)) = (uri, self.recording_options()) {
+                    tracing::info!("Recording {} to {}", uri, options.directory.display());
+                    self.recording_id += 1;
+                    self.recording_error = None;
+                    self.recording = Some(Recording {
+                        id: self.recording_id,
+                        uri,
+                        tap: Some(tap),
+                        options,
+                        file: None,
+                    });
+                }
+            }
+            Message::RecordingEvent(id, event) => {
+                let scheduled = self.scheduled_captures.iter_mut().filter_map(|capture| capture.recording.as_mut());
+                if let Some(recording) = self.recording.iter_mut().chain(scheduled).find(|r| r.id == id) {
+                    match event {
+                        recorder::Event::FileStarted(path) => recording.file = Some(path),
+                    }
+                }
+            }
+            Message::RecordingFinished(id, result) => {
+                let scheduled = self.scheduled_captures.iter()
+                    .position(|capture| capture.recording.as_ref().is_some_and(|r| r.id == id));
+                if let Some(idx) = scheduled {
+                    // Retried on the next check while the programme runs
+                    let error = result.err().unwrap_or_else(|| "the stream ended".to_string());
+                    self.scheduled_capture_failed(idx, error);
+                } else if self.recording.as_ref().is_some_and(|r| r.id == id) {
+                    self.recording = None;
+                    match result {
+                        Ok(()) => {
+                            tracing::info!("Recording ended with the stream");
+                            self.recording_error = Some("the stream ended".to_string());
+                        }
+                        Err(e) => {
+                            tracing::error!("Recording failed: {}", e);
+                            self.recording_error = Some(e);
+                        }
+                    }
+                }
+            }
+            Message::StartEditRecordingSettings => {
+                self.editing_recording_settings = true;
+                self.recording_dir_input = self.config.recording_dir.clone();
+                self.split_recordings_input = self.config.split_recordings;
+            }
+            Message::RecordingDirChanged(dir) => {
+                self.recording_dir_input = dir;
+            }
+            Message::SplitRecordingsChanged(split) => {
+                self.split_recordings_input = split;
+            }
+            Message::SaveRecordingSettings => {
+                let dir = self.recording_dir_input.trim().to_string();
+                let split = self.split_recordings_input;
+                if let Some(handler) = &self.config_handler {
+                    if let Err(e) = self.config.set_recording_dir(handler, dir.clone()) {
+                        tracing::error!("Failed to save recording directory: {}", e);
+                    }
+                    if let Err(e) = self.config.set_split_recordings(handler, split) {
+                        tracing::error!("Failed to save recording split setting: {}", e);
+                    }
+                }
+                // Applies to the next recording
+                self.config.recording_dir = dir;
+                self.config.split_recordings = split;
+                self.editing_recording_settings = false;
+            }
+            Message::CancelRecordingSettings => {
+                self.editing_recording_settings = false;
+            }
+            Message::StartEditShortcuts => {
+                self.editing_shortcuts = true;
+                self.shortcut_inputs = ShortcutAction::ALL
+                    .iter()
+                    .map(|&action| configured_shortcut(&self.config, action).to_string())
+                    .collect();
+                self.shortcuts_error = None;
+            }
+            Message::ShortcutChanged(action, value) => {
+                let position = ShortcutAction::ALL.iter().position(|&a| a == action);
+                if let Some(input) = position.and_then(|position| self.shortcut_inputs.get_mut(position)) {
+                    *input = value;
+                }
+            }
+            Message::SaveShortcuts => {
+                let mut bindings = Vec::new();
+                for (action, input) in ShortcutAction::ALL.into_iter().zip(&self.shortcut_inputs) {
+                    if input.trim().is_empty() {
+                        continue;
+                    }
+                    match shortcuts::Binding::parse(input) {
+                        Ok(binding) => bindings.push((action, binding)),
+                        Err(e) => {
+                            self.shortcuts_error = Some(e.to_string());
+                            return Task::none();
+                        }
+                    }
+                }
+                if let Err(e) = shortcuts::register(&bindings) {
+                    tracing::error!("Failed to register shortcuts: {}", e);
+                    self.shortcuts_error = Some(e.to_string());
+                    return Task::none();
+                }
+
+                let inputs: Vec<String> = self.shortcut_inputs.iter().map(|input| input.trim().to_string()).collect();
+                for (action, input) in ShortcutAction::ALL.into_iter().zip(inputs) {
+                    if let Some(handler) = &self.config_handler {
+                        let result = match action {
+                            ShortcutAction::PlayPause => self.config.set_play_pause_shortcut(handler, input.clone()),
+                            ShortcutAction::NextStation => self.config.set_next_station_shortcut(handler, input.clone()),
+                            ShortcutAction::PreviousStation => self.config.set_previous_station_shortcut(handler, input.clone()),
+                        };
+                        if let Err(e) = result {
+                            tracing::error!("Failed to save shortcut: {}", e);
+                        }
+                    }
+                    match action {
+                        ShortcutAction::PlayPause => self.config.play_pause_shortcut = input,
+                        ShortcutAction::NextStation => self.config.next_station_shortcut = input,
+                        ShortcutAction::PreviousStation => self.config.previous_station_shortcut = input,
+                    }
+                }
+                self.editing_shortcuts = false;
+            }
+            Message::CancelShortcuts => {
+                self.editing_shortcuts = false;
+            }
+            Message::SelectSleepTimer(option) => {
+                let now = Instant::now();
+                match option {
+                    0 => return self.update(Message::CancelSleepTimer),
+                    n if n <= sleep_timer::PRESET_MINUTES.len() => {
+                        let minutes = sleep_timer::PRESET_MINUTES[n - 1];
+                        self.sleep_timer = Some(SleepTimer::after(Duration::from_secs(minutes * 60), now));
+                        self.sleep_timer_custom = None;
+                    }
+                    n if n == sleep_timer::PRESET_MINUTES.len() + 1 => {
+                        self.sleep_timer = Some(SleepTimer::end_of_track(self.now_playing.track_line()));
+                        self.sleep_timer_custom = None;
+                    }
+                    _ => self.sleep_timer_custom = Some(String::new()),
+                }
+            }
+            Message::SleepTimerCustomChanged(minutes) => {
+                self.sleep_timer_custom = Some(minutes);
+            }
+            Message::StartCustomSleepTimer => {
+                if let Some(minutes) = self.sleep_timer_custom.as_deref().and_then(parse_sleep_minutes) {
+                    self.sleep_timer = Some(SleepTimer::after(Duration::from_secs(minutes * 60), Instant::now()));
+                    self.sleep_timer_custom = None;
+                }
+            }
+            Message::CancelSleepTimer => {
+                self.sleep_timer = None;
+                self.sleep_timer_custom = None;
+                self.apply_volume();
+            }
+            Message::AlarmsLoaded(alarms) => {
+                tracing::info!("Loaded {} alarms", alarms.len());
+                self.alarms = alarms;
+            }
+            Message::ToggleAlarms => {
+                self.showing_alarms = !self.showing_alarms;
+            }
+            Message::StartAddAlarm => {
+                self.alarm_form = Some(AlarmForm {
+                    channel: self.current_channel_idx.or(self.last_channel_idx).or(Some(0)),
+                    station_names: self.channels.iter().map(|c| c.name.clone()).collect(),
+                    ramp_minutes: "2".to_string(),
+                    ..Default::default()
+                });
+            }
+            Message::StartEditAlarm(idx) => {
+                if let Some(alarm) = self.alarms.get(idx) {
+                    self.alarm_form = Some(AlarmForm {
+                        idx: Some(idx),
+                        time: alarm.time.format("%H:%M").to_string(),
+                        channel: self.channels.iter().position(|c| c.id == alarm.channel_id),
+                        station_names: self.channels.iter().map(|c| c.name.clone()).collect(),
+                        weekdays: alarms::WEEKDAYS.map(|day| alarm.weekdays.contains(&day)),
+                        ramp_minutes: alarm.ramp_minutes.to_string(),
+                        error: None,
+                    });
+                }
+            }
+            Message::AlarmTimeChanged(time) => {
+                if let Some(form) = &mut self.alarm_form {
+                    form.time = time;
+                    form.error = None;
+                }
+            }
+            Message::AlarmStationSelected(channel) => {
+                if let Some(form) = &mut self.alarm_form {
+                    form.channel = Some(channel);
+                    form.error = None;
+                }
+            }
+            Message::ToggleAlarmWeekday(day) => {
+                if let Some(selected) = self.alarm_form.as_mut().and_then(|form| form.weekdays.get_mut(day)) {
+                    *selected = !*selected;
+                }
+            }
+            Message::AlarmRampChanged(minutes) => {
+                if let Some(form) = &mut self.alarm_form {
+                    form.ramp_minutes = minutes;
+                    form.error = None;
+                }
+            }
+            Message::SaveAlarm => {
+                let Some(form) = &mut self.alarm_form else {
+                    return Task::none();
+                };
+
+                // Validate inputs
+                let Ok(time) = form.time.trim().parse::<chrono::NaiveTime>() else {
+                    form.error = Some("Time must be in HH:MM format".to_string());
+                    return Task::none();
+                };
+                let Some(channel) = form.channel.and_then(|idx| self.channels.get(idx)) else {
+                    form.error = Some("Station is required".to_string());
+                    return Task::none();
+                };
+                let ramp_minutes = match form.ramp_minutes.trim().parse::<u32>() {
+                    Ok(minutes) if minutes <= ALARM_MAX_RAMP_MINUTES => minutes,
+                    _ => {
+                        form.error = Some(format!("Ramp-up must be 0 to {} minutes", ALARM_MAX_RAMP_MINUTES));
+                        return Task::none();
+                    }
+                };
+
+                let alarm = Alarm {
+                    channel_id: channel.id.clone(),
+                    time,
+                    weekdays: alarms::WEEKDAYS.iter()
+                        .zip(form.weekdays)
+                        .filter(|(_, selected)| *selected)
+                        .map(|(day, _)| *day)
+                        .collect(),
+                    ramp_minutes,
+                    enabled: true,
+                };
+
+                let previous = self.alarms.clone();
+                match form.idx.filter(|idx| *idx < self.alarms.len()) {
+                    Some(idx) => self.alarms[idx] = alarm,
+                    None => self.alarms.push(alarm),
+                }
+
+                if let Err(e) = self.save_alarms() {
+                    tracing::error!("Failed to save alarms: {}", e);
+                    self.alarms = previous;
+                    if let Some(form) = &mut self.alarm_form {
+                        form.error = Some(format!("Failed to save: {}", e));
+                    }
+                } else {
+                    self.alarm_form = None;
+                }
+            }
+            Message::CancelAlarm => {
+                self.alarm_form = None;
+            }
+            Message::SetAlarmEnabled(idx, enabled) => {
+                if let Some(alarm) = self.alarms.get_mut(idx) {
+                    alarm.enabled = enabled;
+                    if let Err(e) = self.save_alarms() {
+                        tracing::error!("Failed to save alarms: {}", e);
+                        // Restore the previous state
+                        self.alarms[idx].enabled = !enabled;
+                    }
+                }
+            }
+            Message::DeleteAlarm(idx) => {
+                if idx < self.alarms.len() {
+                    let removed = self.alarms.remove(idx);
+                    if let Err(e) = self.save_alarms() {
+                        tracing::error!("Failed to save alarms: {}", e);
+                        self.alarms.insert(idx, removed);
+                    }
+                }
+            }
+            Message::ScheduleTick => {
+                let now = chrono::Local::now().naive_local();
+                let mut tasks = vec![self.update_scheduled_captures(now)];
+                let due = self.alarm_scheduler.due(&self.alarms, now);
+                // One-off alarms only go off once, even those that couldn't play
+                let mut one_offs_done = false;
+                for &idx in &due {
+                    if !self.alarms[idx].repeats() {
+                        self.alarms[idx].enabled = false;
+                        one_offs_done = true;
+                    }
+                }
+                if one_offs_done {
+                    if let Err(e) = self.save_alarms() {
+                        tracing::error!("Failed to save alarms: {}", e);
+                    }
+                }
+                // Several alarms at once can only play one station
+                if let Some(&idx) = due.first() {
+                    tasks.push(self.ring_alarm(idx));
+                }
+                return Task::batch(tasks);
+            }
+            Message::ScheduleLoaded(schedule) => {
+                tracing::info!("Loaded {} scheduled recordings", schedule.len());
+                self.schedule = schedule;
+            }
+            Message::ScheduledCaptureResolved(channel_id, result) => {
+                // Ignore if the programme ended meanwhile
+                let Some(idx) = self.scheduled_captures.iter().position(|c| c.channel_id == channel_id) else {
+                    return Task::none();
+                };
+                let uri = result.and_then(|uris| {
+                    uris.into_iter().next().ok_or_else(|| "no stream found".to_string())
+                });
+                match uri {
+                    Ok(uri) => {
+                        self.recording_id += 1;
+                        let options = RecordingOptions {
+                            directory: self.recording_directory(),
+                            station: self.scheduled_captures[idx].title.clone(),
+                            split_per_track: self.config.split_recordings,
+                        };
+                        let capture = &mut self.scheduled_captures[idx];
+                        tracing::info!("Recording {} from {}", capture.title, uri);
+                        capture.resolving = false;
+                        capture.recording = Some(Recording {
+                            id: self.recording_id,
+                            uri,
+                            tap: None,
+                            options,
+                            file: None,
+                        });
+                    }
+                    Err(e) => self.scheduled_capture_failed(idx, format!("the station is unreachable ({})", e)),
+                }
+            }
+            Message::ToggleSchedule => {
+                self.showing_schedule = !self.showing_schedule;
+            }
+            Message::StartAddScheduledRecording => {
+                self.schedule_form = Some(ScheduleForm {
+                    channel: self.current_channel_idx.or(self.last_channel_idx).or(Some(0)),
+                    station_names: self.channels.iter().map(|c| c.name.clone()).collect(),
+                    ..Default::default()
+                });
+            }
+            Message::StartEditScheduledRecording(idx) => {
+                if let Some(programme) = self.schedule.get(idx) {
+                    self.schedule_form = Some(ScheduleForm {
+                        idx: Some(idx),
+                        title: programme.title.clone(),
+                        channel: self.channels.iter().position(|c| c.id == programme.channel_id),
+                        station_names: self.channels.iter().map(|c| c.name.clone()).collect(),
+                        date: programme.date.map(|date| date.to_string()).unwrap_or_default(),
+                        weekdays: alarms::WEEKDAYS.map(|day| programme.weekdays.contains(&day)),
+                        start: programme.start.format("%H:%M").to_string(),
+                        end: programme.end.format("%H:%M").to_string(),
+                        error: None,
+                    });
+                }
+            }
+            Message::ScheduleTitleChanged(title) => {
+                if let Some(form) = &mut self.schedule_form {
+                    form.title = title;
+                    form.error = None;
+                }
+            }
+            Message::ScheduleStationSelected(channel) => {
+                if let Some(form) = &mut self.schedule_form {
+                    form.channel = Some(channel);
+                    form.error = None;
+                }
+            }
+            Message::ScheduleDateChanged(date) => {
+                if let Some(form) = &mut self.schedule_form {
+                    form.date = date;
+                    form.error = None;
+                }
+            }
+            Message::ToggleScheduleWeekday(day) => {
+                if let Some(form) = &mut self.schedule_form {
+                    if let Some(selected) = form.weekdays.get_mut(day) {
+                        *selected = !*selected;
+                    }
+                    form.error = None;
+                }
+            }
+            Message::ScheduleStartChanged(start) => {
+                if let Some(form) = &mut self.schedule_form {
+                    form.start = start;
+                    form.error = None;
+                }
+            }
+            Message::ScheduleEndChanged(end) => {
+                if let Some(form) = &mut self.schedule_form {
+                    form.end = end;
+                    form.error = None;
+                }
+            }
+            Message::SaveScheduledRecording => {
+                let Some(form) = &mut self.schedule_form else {
+                    return Task::none();
+                };
+
+                // Validate inputs
+                let title = form.title.trim();
+                if title.is_empty() {
+                    form.error = Some("Programme name is required".to_string());
+                    return Task::none();
+                }
+                let Some(channel) = form.channel.and_then(|idx| self.channels.get(idx)) else {
+                    form.error = Some("Station is required".to_string());
+                    return Task::none();
+                };
+                let (Ok(start), Ok(end)) = (
+                    form.start.trim().parse::<chrono::NaiveTime>(),
+                    form.end.trim().parse::<chrono::NaiveTime>(),
+                ) else {
+                    form.error = Some("Start and end must be in HH:MM format".to_string());
+                    return Task::none();
+                };
+                if start == end {
+                    form.error = Some("End must differ from start".to_string());
+                    return Task::none();
+                }
+                let date = match form.date.trim() {
+                    "" => None,
+                    date => match date.parse::<chrono::NaiveDate>() {
+                        Ok(date) => Some(date),
+                        Err(_) => {
+                            form.error = Some("Date must be in YYYY-MM-DD format".to_string());
+                            return Task::none();
+                        }
+                    },
+                };
+                let weekdays: Vec<chrono::Weekday> = alarms::WEEKDAYS.iter()
+                    .zip(form.weekdays)
+                    .filter(|(_, selected)| *selected)
+                    .map(|(day, _)| *day)
+                    .collect();
+                if date.is_none() && weekdays.is_empty() {
+                    form.error = Some("Pick weekdays or a date".to_string());
+                    return Task::none();
+                }
+
+                let programme = ScheduledRecording {
+                    title: title.to_string(),
+                    channel_id: channel.id.clone(),
+                    // A date makes it a one-off recording
+                    weekdays: if date.is_some() { Vec::new() } else { weekdays },
+                    date,
+                    start,
+                    end,
+                    enabled: true,
+                };
+
+                let previous = self.schedule.clone();
+                match form.idx.filter(|idx| *idx < self.schedule.len()) {
+                    Some(idx) => self.schedule[idx] = programme,
+                    None => self.schedule.push(programme),
+                }
+
+                if let Err(e) = self.save_schedule() {
+                    tracing::error!("Failed to save recording schedule: {}", e);
+                    self.schedule = previous;
+                    if let Some(form) = &mut self.schedule_form {
+                        form.error = Some(format!("Failed to save: {}", e));
+                    }
+                } else {
+                    self.schedule_form = None;
+                }
+            }
+            Message::CancelScheduledRecording => {
+                self.schedule_form = None;
+            }
+            Message::SetScheduledRecordingEnabled(idx, enabled) => {
+                if let Some(programme) = self.schedule.get_mut(idx) {
+                    programme.enabled = enabled;
+                    if let Err(e) = self.save_schedule() {
+                        tracing::error!("Failed to save recording schedule: {}", e);
+                        // Restore the previous state
+                        self.schedule[idx].enabled = !enabled;
+                    }
+                }
+            }
+            Message::DeleteScheduledRecording(idx) => {
+                if idx < self.schedule.len() {
+                    let removed = self.schedule.remove(idx);
+                    if let Err(e) = self.save_schedule() {
+                        tracing::error!("Failed to save recording schedule: {}", e);
+                        self.schedule.insert(idx, removed);
+                    }
+                }
+            }
+            Message::DismissScheduleNotice => {
+                self.schedule_notice = None;
+            }
+            Message::HistoryLoaded(history) => {
+                tracing::info!("Loaded {} played tracks", history.len());
+                // Keep tracks logged while loading
+                let logged = std::mem::replace(&mut self.history, history);
+                self.history.extend(logged);
+            }
+            Message::ToggleHistory => {
+                self.showing_history = !self.showing_history;
+                self.history_notice = None;
+            }
+            Message::HistoryQueryChanged(query) => {
+                self.history_query = query;
+                self.history_notice = None;
+            }
+            Message::ExportHistory(format) => {
+                let entries = self.history.iter().filter(|entry| entry.matches(&self.history_query));
+                self.history_notice = Some(match history::export(entries, format, &channels::export_directory()) {
+                    Ok(path) => format!("Exported to {}", path.display()),
+                    Err(e) => {
+                        tracing::error!("Failed to export history: {}", e);
+                        format!("Export failed: {}", e)
+                    }
+                });
+            }
+            Message::SetTrackNotifications(enabled) => {
+                if let Some(handler) = &self.config_handler {
+                    if let Err(e) = self.config.set_track_notifications(handler, enabled) {
+                        tracing::error!("Failed to save notification setting: {}", e);
+                    }
+                }
+                self.config.track_notifications = enabled;
+                if !enabled {
+                    self.notifier = None;
+                }
+            }
+            Message::NotifierReady(sender) => {
+                self.notifier = Some(sender);
+            }
+            Message::LikedLoaded(tracks) => {
+                tracing::info!("Loaded {} liked tracks", tracks.len());
+                // Keep tracks liked while loading
+                let liked = std::mem::replace(&mut self.liked, tracks);
+                self.liked.extend(liked);
+            }
+            Message::ToggleLikeCurrent => {
+                let previous = self.liked.clone();
+                if let Some(idx) = self.current_liked_idx() {
+                    self.liked.remove(idx);
+                } else {
+                    let Some(track) = self.current_track() else {
+                        return Task::none();
+                    };
+                    self.liked.push(LikedTrack {
+                        liked_at: chrono::Utc::now(),
+                        track,
+                    });
+                }
+
+                if let Err(e) = self.save_liked() {
+                    tracing::error!("Failed to save liked tracks: {}", e);
+                    self.liked_error = Some(format!("Failed to save liked tracks: {}", e));
+                    self.liked = previous;
+                }
+            }
+            Message::ToggleLiked => {
+                self.showing_liked = !self.showing_liked;
+            }
+            Message::ToggleStationLists => {
+                self.showing_station_lists = !self.showing_station_lists;
+                self.station_list_notice = None;
+            }
+            Message::ImportPathChanged(path) => {
+                self.import_path = path;
+            }
+            Message::ImportModeChanged(mode) => {
+                self.import_mode = mode;
+            }
+            Message::ImportStations => {
+                let path = import_file_path(&self.import_path);
+                let stations = match station_lists::read(&path) {
+                    Ok(stations) => stations,
+                    Err(e) => {
+                        tracing::error!("Failed to import {}: {}", path.display(), e);
+                        self.station_list_notice = Some(format!("Import failed: {}", e));
+                        return Task::none();
+                    }
+                };
+
+                let (mut imported, summary) = station_lists::import(&self.channels, stations, self.import_mode);
+                channels::sort_by_group(&mut imported, &self.groups);
+                let previous = std::mem::replace(&mut self.channels, imported);
+                if let Err(e) = self.save_channels() {
+                    tracing::error!("Failed to save imported channels: {}", e);
+                    self.station_list_notice = Some(format!("Failed to save: {}", e));
+                    self.channels = previous;
+                    return Task::none();
+                }
+
+                tracing::info!("Imported {} stations from {}", summary.added, path.display());
+                self.reindex_channels(&previous);
+                self.publish_mpris();
+                self.station_list_notice = Some(match summary.duplicates {
+                    0 => format!("Imported {} stations", summary.added),
+                    duplicates => format!("Imported {} stations, {} already listed", summary.added, duplicates),
+                });
+                return self.load_logos();
+            }
+            Message::ExportStations(format) => {
+                self.station_list_notice = Some(
+                    match station_lists::export(&self.channels, format, &channels::export_directory()) {
+                        Ok(path) => format!("Exported to {}", path.display()),
+                        Err(e) => {
+                            tracing::error!("Failed to export stations: {}", e);
+                            format!("Export failed: {}", e)
+                        }
+                    },
+                );
+            }
+            Message::CopyLikedTrack(idx) => {
+                if let Some(liked) = self.liked.get(idx) {
+                    return cosmic::iced::clipboard::write(liked.track.track_line());
+                }
+            }
+            Message::SearchLikedTrack(idx) => {
+                if let Some(liked) = self.liked.get(idx) {
+                    open_url(&liked.search_url());
+                }
+            }
+            Message::ScrobblingLoaded(settings, queue) => {
+                tracing::info!("Loaded {} queued scrobbles", queue.len());
+                self.scrobbler = match Scrobbler::new(settings) {
+                    Ok(scrobbler) => Some(scrobbler),
+                    Err(e) => {
+                        tracing::error!("Scrobbling disabled: {}", e);
+                        None
+                    }
+                };
+                // Keep listens queued while loading
+                let queued = std::mem::replace(&mut self.scrobble_queue, queue);
+                self.scrobble_queue.extend(queued);
+                return self.submit_scrobbles();
+            }
+            Message::NowPlayingSent(result) => {
+                if let Err(e) = result {
+                    tracing::warn!("Failed to send now playing: {}", e);
+                }
+            }
+            Message::ScrobblesSubmitted(count, result) => {
+                self.scrobble_submitting = false;
+                let count = count.min(self.scrobble_queue.len());
+                match result {
+                    Ok(()) => {
+                        tracing::info!("Submitted {} scrobbles", count);
+                        self.scrobble_error = None;
+                    }
+                    Err(failure) if failure.permanent => {
+                        tracing::error!("Dropping {} rejected scrobbles: {}", count, failure.message);
+                        self.scrobble_error = Some(format!("Dropped {} scrobbles: {}", count, failure.message));
+                    }
+                    Err(failure) => {
+                        // Retried later
+                        tracing::warn!("Failed to submit scrobbles: {}", failure.message);
+                        self.scrobble_error = Some(failure.message);
+                        return Task::none();
+                    }
+                }
+                self.scrobble_queue.drain(..count);
+                self.save_scrobble_queue();
+                return self.submit_scrobbles();
+            }
+            Message::RetryScrobbles => {
+                return self.submit_scrobbles();
+            }
+            Message::StartEditScrobbling => {
+                let settings = self.scrobbler.as_ref()
+                    .map(|scrobbler| scrobbler.settings().clone())
+                    .unwrap_or_else(|| scrobbler::load_settings().unwrap_or_default());
+                let mut station_options = vec!["All stations".to_string()];
+                station_options.extend(self.channels.iter().map(|c| c.name.clone()));
+                self.scrobble_form = Some(ScrobbleForm {
+                    enabled: settings.enabled,
+                    service: Service::ALL.iter().position(|s| *s == settings.service).unwrap_or(0),
+                    service_names: Service::ALL.iter().map(|s| s.name().to_string()).collect(),
+                    endpoint: settings.endpoint.clone().unwrap_or_default(),
+                    token: settings.token.clone(),
+                    api_key: settings.api_key.clone(),
+                    api_secret: settings.api_secret.clone(),
+                    session_key: settings.session_key.clone(),
+                    min_play_seconds: settings.min_play_seconds.to_string(),
+                    filters: settings.filters.iter()
+                        .map(|filter| FilterInput {
+                            station_id: filter.station_id.clone(),
+                            pattern: filter.pattern.clone(),
+                        })
+                        .collect(),
+                    station_options,
+                    ..Default::default()
+                });
+            }
+            Message::ScrobblingEnabledChanged(enabled) => {
+                if let Some(form) = &mut self.scrobble_form {
+                    form.enabled = enabled;
+                    form.error = None;
+                }
+            }
+            Message::ScrobbleServiceSelected(service) => {
+                if let Some(form) = &mut self.scrobble_form {
+                    if service < Service::ALL.len() {
+                        form.service = service;
+                    }
+                    form.error = None;
+                }
+            }
+            Message::ScrobbleEndpointChanged(endpoint) => {
+                if let Some(form) = &mut self.scrobble_form {
+                    form.endpoint = endpoint;
+                    form.error = None;
+                }
+            }
+            Message::ScrobbleTokenChanged(token) => {
+                if let Some(form) = &mut self.scrobble_form {
+                    form.token = token;
+                    form.error = None;
+                }
+            }
+            Message::ScrobbleApiKeyChanged(api_key) => {
+                if let Some(form) = &mut self.scrobble_form {
+                    form.api_key = api_key;
+                    form.error = None;
+                }
+            }
+            Message::ScrobbleApiSecretChanged(api_secret) => {
+                if let Some(form) = &mut self.scrobble_form {
+                    form.api_secret = api_secret;
+                    form.error = None;
+                }
+            }
+            Message::ScrobbleUsernameChanged(username) => {
+                if let Some(form) = &mut self.scrobble_form {
+                    form.username = username;
+                    form.error = None;
+                }
+            }
+            Message::ScrobblePasswordChanged(password) => {
+                if let Some(form) = &mut self.scrobble_form {
+                    form.password = password;
+                    form.error = None;
+                }
+            }
+            Message::ScrobbleMinPlayChanged(seconds) => {
+                if let Some(form) = &mut self.scrobble_form {
+                    form.min_play_seconds = seconds;
+                    form.error = None;
+                }
+            }
+            Message::AddScrobbleFilter => {
+                if let Some(form) = &mut self.scrobble_form {
+                    form.filters.push(FilterInput::default());
+                }
+            }
+            Message::ScrobbleFilterStationSelected(i, station) => {
+                let station_id = match station {
+                    0 => None,
+                    station => self.channels.get(station - 1).map(|c| c.id.clone()),
+                };
+                if let Some(filter) = self.scrobble_form.as_mut().and_then(|form| form.filters.get_mut(i)) {
+                    filter.station_id = station_id;
+                }
+            }
+            Message::ScrobbleFilterPatternChanged(i, pattern) => {
+                if let Some(form) = &mut self.scrobble_form {
+                    if let Some(filter) = form.filters.get_mut(i) {
+                        filter.pattern = pattern;
+                    }
+                    form.error = None;
+                }
+            }
+            Message::RemoveScrobbleFilter(i) => {
+                if let Some(form) = &mut self.scrobble_form {
+                    if i < form.filters.len() {
+                        form.filters.remove(i);
+                    }
+                    form.error = None;
+                }
+            }
+            Message::SaveScrobbling => {
+                let Some(form) = &self.scrobble_form else {
+                    return Task::none();
+                };
+                let settings = match self.scrobble_form_settings(form) {
+                    Ok(settings) => settings,
+                    Err(e) => {
+                        if let Some(form) = &mut self.scrobble_form {
+                            form.error = Some(e);
+                        }
+                        return Task::none();
+                    }
+                };
 
-                    while let Some(msg) = bus_stream.next().await {
-                        match msg.view() {
-                            MessageView::StateChanged(state_changed) => {
-                                if let Some(src) = msg.src() {
-                                    if let Some(src_pipeline) = src.downcast_ref::<gstreamer::Pipeline>() {
-                                        if src_pipeline == &pipeline {
-                                            let new_state = state_changed.current();
-                                            let _ = channel.send(Message::PlayerStateChanged(new_state)).await;
-                                        }
-                                    }
+                // Exchange the Last.fm password for a session key first
+                if settings.service == Service::LastFm && !form.password.is_empty() {
+                    let username = form.username.trim().to_string();
+                    let password = form.password.clone();
+                    if let Some(form) = &mut self.scrobble_form {
+                        form.signing_in = true;
+                    }
+                    return Task::perform(
+                        async move {
+                            scrobbler::lastfm_session(&settings, &username, &password)
+                                .await
+                                .map_err(|e| e.to_string())
+                        },
+                        Message::ScrobbleSessionReceived,
+                    )
+                    .map(cosmic::Action::App);
+                }
+                return self.apply_scrobble_settings(settings);
+            }
+            Message::ScrobbleSessionReceived(result) => {
+                let Some(form) = &mut self.scrobble_form else {
+                    return Task::none();
+                };
+                form.signing_in = false;
+                match result {
+                    Ok(session_key) => {
+                        form.session_key = session_key;
+                        form.password.clear();
+                        let form = form.clone();
+                        match self.scrobble_form_settings(&form) {
+                            Ok(settings) => return self.apply_scrobble_settings(settings),
+                            Err(e) => {
+                                if let Some(form) = &mut self.scrobble_form {
+                                    form.error = Some(e);
                                 }
                             }
-                            MessageView::Tag(tags_msg) => {
-                                let tags = tags_msg.tags();
-                                let _ = channel.send(Message::MetadataUpdated(tags)).await;
-                            }
-                            MessageView::Error(err) => {
-                                tracing::error!("GStreamer error: {} ({:?})", err.error(), err.debug());
-                                let _ = channel.send(Message::PlayerStateChanged(State::Null)).await;
-                            }
-                            _ => (),
                         }
                     }
+                    Err(e) => {
+                        tracing::error!("Last.fm sign-in failed: {}", e);
+                        form.error = Some(format!("Sign-in failed: {}", e));
+                    }
+                }
+            }
+            Message::CancelScrobbling => {
+                self.scrobble_form = None;
+            }
+            Message::ToggleBrowse => {
+                self.browsing = !self.browsing;
+                if self.browsing {
+                    self.browse_server_input = self.config.radio_browser_url.clone();
+                }
+            }
+            Message::BrowseNameChanged(name) => {
+                self.browse_query.name = name;
+            }
+            Message::BrowseTagChanged(tag) => {
+                self.browse_query.tag = tag;
+            }
+            Message::BrowseCountryChanged(country) => {
+                self.browse_query.country = country;
+            }
+            Message::BrowseLanguageChanged(language) => {
+                self.browse_query.language = language;
+            }
+            Message::BrowseCodecChanged(codec) => {
+                self.browse_query.codec = codec;
+            }
+            Message::BrowseServerChanged(server) => {
+                self.browse_server_input = server;
+            }
+            Message::SearchStations => {
+                if self.browse_query.is_empty() {
+                    self.browse_error = Some("Enter a name, tag, country, language or codec".to_string());
+                    return Task::none();
+                }
 
-                    futures_util::future::pending().await
-                }),
-            ));
-        }
-
-        Subscription::batch(subs)
-    }
+                // Remember the directory server
+                let server = self.browse_server_input.trim().to_string();
+                if server != self.config.radio_browser_url {
+                    if let Some(handler) = &self.config_handler {
+                        if let Err(e) = self.config.set_radio_browser_url(handler, server.clone()) {
+                            tracing::error!("Failed to save directory server: {}", e);
+                        }
+                    }
+                    self.config.radio_browser_url = server.clone();
+                }
+                let base_url = if server.is_empty() {
+                    radio_browser::DEFAULT_BASE_URL.to_string()
+                } else {
+                    server
+                };
 
-    /// Handles messages emitted by the application and its widgets.
-    ///
-    /// Tasks may be returned for asynchronous execution of code in the background
-    /// on the application's async runtime. The application will not exit until all
-    /// tasks are finished.
-    fn update(&mut self, message: Self::Message) -> Task<cosmic::Action<Self::Message>> {
-        match message {
-            Message::SubscriptionChannel => {
-                // For example purposes only.
-            }
-            Message::UpdateConfig(config) => {
-                self.config = config;
+                self.browse_request += 1;
+                self.browse_searching = true;
+                self.browse_error = None;
+                let request = self.browse_request;
+                let query = self.browse_query.clone();
+                return Task::perform(
+                    async move { radio_browser::search(&base_url, &query).await.map_err(|e| e.to_string()) },
+                    move |result| Message::StationsFound(request, result),
+                )
+                .map(cosmic::Action::App);
             }
-            Message::TogglePlayback => {
-                // Legacy toggle - stops if playing, otherwise no-op
-                if let Some(player) = &self.player {
-                    if self.play_state == State::Playing {
-                        if let Err(e) = player.stop() {
-                            tracing::error!("Failed to stop playback: {}", e);
+            Message::StationsFound(request, result) => {
+                if request != self.browse_request {
+                    return Task::none();
+                }
+                self.browse_searching = false;
+                match result {
+                    Ok(stations) => {
+                        if stations.is_empty() {
+                            self.browse_error = Some("No stations found".to_string());
                         }
-                        self.current_channel_idx = None;
+                        self.browse_results = stations;
+                    }
+                    Err(e) => {
+                        tracing::error!("Station search failed: {}", e);
+                        self.browse_error = Some(e);
                     }
                 }
-            },
-            Message::PlayChannel(idx) => {
-                if let Some(channel) = self.channels.get(idx) {
+            }
+            Message::AddBrowsedStation(idx) => {
+                let Some(station) = self.browse_results.get(idx) else {
+                    return Task::none();
+                };
+                let Some(id) = channels::unique_id(&station.name, &self.channels)
+                    .or_else(|| channels::unique_id("station", &self.channels))
+                else {
+                    return Task::none();
+                };
+                let channel = station.to_channel(id);
+                let name = channel.name.clone();
+                if let Err(e) = self.insert_station(channel) {
+                    tracing::error!("Failed to save channels: {}", e);
+                    self.browse_error = Some(format!("Failed to save: {}", e));
+                } else {
+                    tracing::info!("Added station from directory: {}", name);
+                    self.publish_mpris();
+                    return self.load_logos();
+                }
+            }
+            Message::DeleteLikedTrack(idx) => {
+                if idx < self.liked.len() {
+                    let removed = self.liked.remove(idx);
+                    if let Err(e) = self.save_liked() {
+                        tracing::error!("Failed to save liked tracks: {}", e);
+                        self.liked_error = Some(format!("Failed to save liked tracks: {}", e));
+                        self.liked.insert(idx, removed);
+                    }
+                }
+            }
+            Message::DismissLikedError => {
+                self.liked_error = None;
+            }
+            Message::AlarmRampTick => {
+                // Once the ramp is over, end it so the configured volume applies exactly
+                if let Some(alarm) = &mut self.ringing_alarm {
+                    if alarm.started.elapsed() >= alarm.ramp {
+                        alarm.ramp = Duration::ZERO;
+                    }
+                }
+                self.apply_volume();
+            }
+            Message::SleepTimerTick => {
+                if let Some(timer) = &self.sleep_timer {
+                    if timer.is_expired(Instant::now()) {
+                        tracing::info!("Sleep timer expired, stopping playback");
+                        return self.update(Message::StopPlayback);
+                    }
+                    self.apply_volume();
+                }
+            }
+            Message::PausePlayback => {
+                if self.play_state == State::Playing {
                     if let Some(player) = &self.player {
-                        // Stop any current playback
-                        if let Err(e) = player.stop() {
-                            tracing::error!("Failed to stop previous playback: {}", e);
-                        }
-                        
-                        // Start playing the selected channel
-                        if let Err(e) = player.play(&channel.uri) {
-                            tracing::error!("Failed to start playback of {}: {}", channel.name, e);
-                            self.error_message = Some(format!("Failed to play {}", channel.name));
+                        if let Err(e) = player.pause() {
+                            tracing::error!("Failed to pause playback: {}", e);
                         } else {
-                            self.current_channel_idx = Some(idx);
-                            self.error_message = None;
-                            tracing::info!("Started playing: {} ({})", channel.name, channel.uri);
+                            self.paused_at = Some(Instant::now());
                         }
                     }
                 }
             }
-            Message::StopPlayback => {
+            Message::ResumePlayback => {
+                if let (Some(_), Some(player)) = (self.paused_at, &self.player) {
+                    if let Err(e) = player.resume() {
+                        tracing::error!("Failed to resume playback: {}", e);
+                    } else {
+                        self.paused_at = None;
+                        self.timeshift_delay = player.behind_live();
+                    }
+                }
+            }
+            Message::JumpToLive => {
                 if let Some(player) = &self.player {
-                    if let Err(e) = player.stop() {
-                        tracing::error!("Failed to stop playback: {}", e);
+                    if let Err(e) = player.jump_to_live() {
+                        tracing::error!("Failed to jump to live: {}", e);
+                    } else {
+                        self.paused_at = None;
+                        self.timeshift_delay = Duration::ZERO;
                     }
                 }
-                self.current_channel_idx = None;
+            }
+            Message::TimeshiftTick => {
+                if let Some(player) = &self.player {
+                    self.timeshift_delay = player.behind_live();
+                }
             }
             Message::PlayerStateChanged(state) => {
                 self.play_state = state;
+                if state == State::Playing {
+                    self.reconnector.connected(Instant::now());
+                    self.reconnect_status = None;
+                }
                 // If playback stops unexpectedly, clear current channel
                 if state == State::Null {
                     self.current_channel_idx = None;
+                    self.now_playing = NowPlaying::default();
                 }
+                self.publish_mpris();
             }
-            Message::MetadataUpdated(_tags) => {
-                // Placeholder for metadata extraction
+            Message::MetadataUpdated(tags) => {
+                let mut scrobble = Task::none();
+                if self.current_channel_idx.is_some() {
+                    let previous_title = self.now_playing.title.clone();
+                    self.now_playing.update_from_tags(&tags);
+                    self.publish_mpris();
+
+                    // Tags are resent with bitrate updates, only log new titles
+                    if self.now_playing.title != previous_title {
+                        self.record_history();
+                        self.notify_track();
+                        scrobble = self.scrobble_track_change(true);
+                    }
+
+                    let track = self.now_playing.track_line();
+                    if self.sleep_timer.as_mut().is_some_and(|timer| timer.track_changed(track)) {
+                        tracing::info!("Track ended, stopping playback for the sleep timer");
+                        return Task::batch([scrobble, self.update(Message::StopPlayback)]);
+                    }
+                }
+                return scrobble;
             }
-            Message::ChannelsLoaded(channels) => {
-                self.channels = channels;
+            Message::ChannelsLoaded(list) => {
+                (self.channels, self.groups) = list.flatten();
+                self.selected_station = None;
                 self.error_message = None;
                 tracing::info!("Loaded {} channels", self.channels.len());
+                self.publish_mpris();
+                return self.load_logos();
+            }
+            Message::LogoLoaded(key, result) => {
+                let state = match result {
+                    Ok(path) => LogoState::Ready(widget::icon::from_path(path)),
+                    Err(e) => {
+                        tracing::debug!("No logo from {}: {}", key, e);
+                        LogoState::Failed
+                    }
+                };
+                self.logos.insert(key, state);
             }
             Message::ChannelError(error) => {
                 tracing::error!("Failed to load channels: {}", error);
                 self.error_message = Some(error);
             }
+            Message::StationQueryChanged(query) => {
+                self.station_query = query;
+                self.selected_station = None;
+            }
+            Message::SelectStation(next) => {
+                let listed = self.listed_stations();
+                let position = self.selected_station(&listed)
+                    .and_then(|idx| listed.iter().position(|&listed_idx| listed_idx == idx));
+                let position = match position {
+                    Some(position) if next => (position + 1).min(listed.len() - 1),
+                    Some(position) => position.saturating_sub(1),
+                    None if next => 0,
+                    None => listed.len().saturating_sub(1),
+                };
+                self.selected_station = listed.get(position).copied();
+            }
+            Message::StationKey(key) => {
+                let Some(idx) = self.selected_station(&self.listed_stations()) else {
+                    return Task::none();
+                };
+                let is_playing = self.current_channel_idx == Some(idx) && self.play_state == State::Playing;
+                return self.update(match key {
+                    StationKey::Play if is_playing => Message::StopPlayback,
+                    StationKey::Play => Message::PlayChannel(idx),
+                    StationKey::Delete => Message::StartDeleteStation(idx),
+                    StationKey::Edit => Message::StartEditStation(idx),
+                    StationKey::Favourite => Message::ToggleFavourite(idx),
+                    StationKey::MoveUp => Message::MoveStationUp(idx),
+                    StationKey::MoveDown => Message::MoveStationDown(idx),
+                });
+            }
             Message::ToggleAddStation => {
                 self.adding_station = !self.adding_station;
                 if !self.adding_station {
                     // Clear form when closing
                     self.new_station_name.clear();
                     self.new_station_url.clear();
+                    self.new_station_details = StationDetailsForm::default();
                     self.new_station_error = None;
                 }
             }
@@ -674,6 +5162,10 @@ impl cosmic::Application for AppModel {
                 self.new_station_url = url;
                 self.new_station_error = None;
             }
+            Message::NewStationDetailChanged(field, value) => {
+                self.new_station_details.set(field, value);
+                self.new_station_error = None;
+            }
             Message::SaveNewStation => {
                 // Validate inputs
                 let name = self.new_station_name.trim();
@@ -695,57 +5187,56 @@ impl cosmic::Application for AppModel {
                     return Task::none();
                 }
                 
-                // Generate ID from name
-                let id = name.to_lowercase()
-                    .replace(' ', "-")
-                    .replace(|c: char| !c.is_alphanumeric() && c != '-', "");
+                if let Err(e) = self.new_station_details.validate() {
+                    self.new_station_error = Some(e);
+                    return Task::none();
+                }
                 
-                if id.is_empty() {
+                // Generate ID from name
+                let Some(id) = channels::unique_id(name, &self.channels) else {
                     self.new_station_error = Some("Invalid station name".to_string());
                     return Task::none();
-                }
+                };
                 
                 // Create new channel
-                let new_channel = Channel {
+                let mut new_channel = Channel {
                     id,
                     name: name.to_string(),
-                    uri: url.to_string(),
+                    streams: vec![StreamEndpoint::new(url)],
                     favourite: false,
+                    ..Default::default()
                 };
+                self.new_station_details.apply(&mut new_channel);
+                new_channel.group = self.new_station_details.group();
                 
-                // Add to list
-                self.channels.push(new_channel);
-                
-                // Save to file
-                let list = ChannelList {
-                    channels: self.channels.clone(),
-                };
-                
-                if let Err(e) = channels::save_channels(&list) {
-                    tracing::error!("Failed to save channels: {}", e);
-                    self.error_message = Some(format!("Failed to save: {}", e));
-                    // Remove the channel we just added
-                    self.channels.pop();
+                // Add to the end of its group and save
+                if let Err(e) = self.insert_station(new_channel) {
+                    self.channels_save_failed(e);
                 } else {
                     tracing::info!("Added new station: {}", name);
                     // Clear form and close
                     self.new_station_name.clear();
                     self.new_station_url.clear();
+                    self.new_station_details = StationDetailsForm::default();
                     self.new_station_error = None;
                     self.adding_station = false;
+                    return self.load_logos();
                 }
             }
             Message::CancelAddStation => {
                 self.adding_station = false;
                 self.new_station_name.clear();
                 self.new_station_url.clear();
+                self.new_station_details = StationDetailsForm::default();
                 self.new_station_error = None;
             }
             Message::StartEditStation(idx) => {
                 if let Some(channel) = self.channels.get(idx) {
                     self.editing_station_idx = Some(idx);
                     self.edit_station_name = channel.name.clone();
-                    self.edit_station_url = channel.uri.clone();
+                    self.edit_station_url = channel.primary_uri().to_string();
+                    self.edit_station_fallbacks = channel.uris().into_iter().skip(1).collect();
+                    self.edit_station_details = StationDetailsForm::from_channel(channel);
                     self.edit_station_error = None;
                 }
             }
@@ -757,6 +5248,25 @@ impl cosmic::Application for AppModel {
                 self.edit_station_url = url;
                 self.edit_station_error = None;
             }
+            Message::AddEditStationFallback => {
+                self.edit_station_fallbacks.push(String::new());
+            }
+            Message::EditStationFallbackChanged(i, url) => {
+                if let Some(fallback) = self.edit_station_fallbacks.get_mut(i) {
+                    *fallback = url;
+                }
+                self.edit_station_error = None;
+            }
+            Message::RemoveEditStationFallback(i) => {
+                if i < self.edit_station_fallbacks.len() {
+                    self.edit_station_fallbacks.remove(i);
+                }
+                self.edit_station_error = None;
+            }
+            Message::EditStationDetailChanged(field, value) => {
+                self.edit_station_details.set(field, value);
+                self.edit_station_error = None;
+            }
             Message::SaveEditStation => {
                 if let Some(idx) = self.editing_station_idx {
                     // Validate inputs
@@ -778,42 +5288,71 @@ impl cosmic::Application for AppModel {
                         self.edit_station_error = Some("URL must start with http:// or https://".to_string());
                         return Task::none();
                     }
+
+                    // Empty fallbacks are dropped, the rest must be valid too
+                    let fallbacks: Vec<&str> = self.edit_station_fallbacks
+                        .iter()
+                        .map(|url| url.trim())
+                        .filter(|url| !url.is_empty())
+                        .collect();
+                    if fallbacks.iter().any(|url| !url.starts_with("http://") && !url.starts_with("https://")) {
+                        self.edit_station_error = Some("Fallback URLs must start with http:// or https://".to_string());
+                        return Task::none();
+                    }
+
+                    if let Err(e) = self.edit_station_details.validate() {
+                        self.edit_station_error = Some(e);
+                        return Task::none();
+                    }
+                    let group = self.edit_station_details.group();
                     
-                    // Update the channel
+                    // Update the channel. The id stays the same so alarms, scheduled
+                    // recordings and scrobble filters keep referring to it.
                     if let Some(channel) = self.channels.get_mut(idx) {
-                        let old_id = channel.id.clone();
                         channel.name = name.to_string();
-                        channel.uri = url.to_string();
-                        // Only regenerate ID if name changed significantly
-                        if name.to_lowercase().replace(' ', "-") != old_id {
-                            channel.id = name.to_lowercase()
-                                .replace(' ', "-")
-                                .replace(|c: char| !c.is_alphanumeric() && c != '-', "");
-                        }
+                        // Keep bitrate and codec labels of endpoints that are unchanged
+                        channel.streams = std::iter::once(url)
+                            .chain(fallbacks)
+                            .map(|url| {
+                                channel.streams
+                                    .iter()
+                                    .find(|endpoint| endpoint.uri == url)
+                                    .cloned()
+                                    .unwrap_or_else(|| StreamEndpoint::new(url))
+                            })
+                            .collect();
+                        self.edit_station_details.apply(channel);
                         
                         // Save to file
-                        let list = ChannelList {
-                            channels: self.channels.clone(),
-                        };
-                        
-                        if let Err(e) = channels::save_channels(&list) {
-                            tracing::error!("Failed to save channels: {}", e);
-                            self.error_message = Some(format!("Failed to save: {}", e));
+                        if let Err(e) = self.save_channels() {
+                            self.channels_save_failed(e);
                         } else {
                             tracing::info!("Updated station: {}", name);
                             // Clear form and close
                             self.editing_station_idx = None;
                             self.edit_station_name.clear();
                             self.edit_station_url.clear();
+                            self.edit_station_fallbacks.clear();
+                            self.edit_station_details = StationDetailsForm::default();
                             self.edit_station_error = None;
                             
                             // If this was the currently playing channel, stop playback
                             if self.current_channel_idx == Some(idx) {
-                                if let Some(player) = &self.player {
+                                if let Some(player) = &mut self.player {
                                     let _ = player.stop();
                                 }
+                                self.stop_recording();
                                 self.current_channel_idx = None;
+                                self.reconnect_status = None;
+                            }
+
+                            // Moving to another group saves again
+                            if self.channels.get(idx).is_some_and(|channel| channel.group != group) {
+                                if let Err(e) = self.move_station_to_group(idx, group, None) {
+                                    self.channels_save_failed(e);
+                                }
                             }
+                            return self.load_logos();
                         }
                     }
                 }
@@ -822,8 +5361,98 @@ impl cosmic::Application for AppModel {
                 self.editing_station_idx = None;
                 self.edit_station_name.clear();
                 self.edit_station_url.clear();
+                self.edit_station_fallbacks.clear();
+                self.edit_station_details = StationDetailsForm::default();
                 self.edit_station_error = None;
             }
+            Message::ToggleFavourite(idx) => {
+                if let Some(channel) = self.channels.get_mut(idx) {
+                    channel.favourite = !channel.favourite;
+
+                    // Save to file
+                    if let Err(e) = self.save_channels() {
+                        self.channels_save_failed(e);
+                        // Restore the previous state
+                        if let Some(channel) = self.channels.get_mut(idx) {
+                            channel.favourite = !channel.favourite;
+                        }
+                    }
+                }
+            }
+            Message::OpenHomepage(idx) => {
+                if let Some(url) = self.channels.get(idx).and_then(|c| c.homepage.as_ref()) {
+                    open_url(url);
+                }
+            }
+            Message::MoveStationUp(idx) => {
+                if let Some(to) = self.section_neighbour(idx, true) {
+                    if let Err(e) = self.move_station(idx, to) {
+                        self.channels_save_failed(e);
+                    }
+                }
+            }
+            Message::MoveStationDown(idx) => {
+                if let Some(to) = self.section_neighbour(idx, false) {
+                    if let Err(e) = self.move_station(idx, to) {
+                        self.channels_save_failed(e);
+                    }
+                }
+            }
+            Message::StartDragStation(idx) => {
+                self.dragging_station_idx = Some(idx);
+                self.drop_target = None;
+            }
+            Message::DragOverStation(idx) => {
+                // Favourites are reordered within their group, other
+                // stations may also be dropped into another group
+                let allowed = self.dragging_station_idx
+                    .and_then(|from| self.channels.get(from))
+                    .zip(self.channels.get(idx))
+                    .is_some_and(|(dragged, target)| {
+                        dragged.favourite == target.favourite
+                            && (!target.favourite || dragged.group == target.group)
+                    });
+                self.drop_target = allowed.then_some(DropTarget::Station(idx));
+            }
+            Message::DragOverGroup(group) => {
+                self.drop_target = Some(DropTarget::Group(group));
+            }
+            Message::DropStation => {
+                let Some(from) = self.dragging_station_idx.take() else {
+                    return Task::none();
+                };
+                let Some(group) = self.channels.get(from).map(|channel| channel.group.clone()) else {
+                    return Task::none();
+                };
+                let result = match self.drop_target.take() {
+                    Some(DropTarget::Station(to)) => match self.channels.get(to) {
+                        Some(target) if target.group == group => self.move_station(from, to),
+                        Some(target) => self.move_station_to_group(from, target.group.clone(), Some(to)),
+                        None => Ok(()),
+                    },
+                    Some(DropTarget::Group(target)) if target != group => {
+                        self.move_station_to_group(from, target, None)
+                    }
+                    _ => Ok(()),
+                };
+                if let Err(e) = result {
+                    self.channels_save_failed(e);
+                }
+            }
+            Message::ToggleGroup(name) => {
+                let mut collapsed = self.config.collapsed_groups.clone();
+                if let Some(i) = collapsed.iter().position(|group| *group == name) {
+                    collapsed.remove(i);
+                } else {
+                    collapsed.push(name);
+                }
+                if let Some(handler) = &self.config_handler {
+                    if let Err(e) = self.config.set_collapsed_groups(handler, collapsed.clone()) {
+                        tracing::error!("Failed to save collapsed groups: {}", e);
+                    }
+                }
+                self.config.collapsed_groups = collapsed;
+            }
             Message::StartDeleteStation(idx) => {
                 self.deleting_station_idx = Some(idx);
             }
@@ -834,11 +5463,7 @@ impl cosmic::Application for AppModel {
                         let removed_channel = self.channels.remove(idx);
                         
                         // Save to file
-                        let list = ChannelList {
-                            channels: self.channels.clone(),
-                        };
-                        
-                        if let Err(e) = channels::save_channels(&list) {
+                        if let Err(e) = self.save_channels() {
                             tracing::error!("Failed to save channels after deletion: {}", e);
                             self.error_message = Some(format!("Failed to save: {}", e));
                             // Restore the channel
@@ -848,16 +5473,35 @@ impl cosmic::Application for AppModel {
                             
                             // If this was the currently playing channel, stop playback
                             if self.current_channel_idx == Some(idx) {
-                                if let Some(player) = &self.player {
+                                if let Some(player) = &mut self.player {
                                     let _ = player.stop();
                                 }
+                                self.stop_recording();
                                 self.current_channel_idx = None;
+                                self.reconnect_status = None;
                             } else if let Some(current_idx) = self.current_channel_idx {
                                 // Adjust current channel index if needed
                                 if current_idx > idx {
                                     self.current_channel_idx = Some(current_idx - 1);
                                 }
                             }
+
+                            // A failed reconnect can't be retried once its station is gone
+                            if let Some(ReconnectStatus::GaveUp { .. } | ReconnectStatus::LostWhilePaused { .. }) = self.reconnect_status {
+                                self.reconnect_status = None;
+                            }
+
+                            // Keep the last played channel pointing at the same station
+                            match self.last_channel_idx {
+                                Some(last_idx) if last_idx == idx => self.last_channel_idx = None,
+                                Some(last_idx) if last_idx > idx => self.last_channel_idx = Some(last_idx - 1),
+                                _ => {}
+                            }
+                            match self.selected_station {
+                                Some(selected) if selected == idx => self.selected_station = None,
+                                Some(selected) if selected > idx => self.selected_station = Some(selected - 1),
+                                _ => {}
+                            }
                         }
                     }
                     self.deleting_station_idx = None;
@@ -866,6 +5510,184 @@ impl cosmic::Application for AppModel {
             Message::CancelDeleteStation => {
                 self.deleting_station_idx = None;
             }
+            Message::SetVolume(volume) => {
+                // Only applied to the player while dragging, saved on release
+                self.config.volume = volume.min(100);
+                self.config.muted = false;
+                self.apply_volume();
+                self.publish_mpris();
+            }
+            Message::SaveVolume => {
+                self.save_volume();
+            }
+            Message::PanelScrolled(delta) => {
+                // Touchpads report pixels, so accumulate them into whole steps
+                let steps = match delta {
+                    // Horizontal wheels and tilts report no vertical lines
+                    ScrollDelta::Lines { y, .. } if y > 0.0 => 1,
+                    ScrollDelta::Lines { y, .. } if y < 0.0 => -1,
+                    ScrollDelta::Lines { .. } => 0,
+                    ScrollDelta::Pixels { y, .. } => {
+                        self.scroll_accumulator += y;
+                        let steps = (self.scroll_accumulator / SCROLL_PIXELS_PER_STEP).trunc();
+                        self.scroll_accumulator -= steps * SCROLL_PIXELS_PER_STEP;
+                        steps as i32
+                    }
+                };
+
+                if steps != 0 {
+                    let volume = i32::from(self.config.volume) + steps * i32::from(VOLUME_SCROLL_STEP);
+                    self.config.volume = volume.clamp(0, 100) as u8;
+                    self.config.muted = false;
+                    self.apply_volume();
+                    self.save_volume();
+                    self.publish_mpris();
+                }
+            }
+            Message::NextChannel => {
+                if let Some(idx) = self.adjacent_channel(1) {
+                    return self.update(Message::PlayChannel(idx));
+                }
+            }
+            Message::PreviousChannel => {
+                if let Some(idx) = self.adjacent_channel(-1) {
+                    return self.update(Message::PlayChannel(idx));
+                }
+            }
+            Message::StreamError(error) => {
+                let Some(idx) = self.current_channel_idx else {
+                    return Task::none();
+                };
+
+                // Don't resume a paused station behind the user's back, the
+                // buffered audio is gone with the pipeline
+                if self.paused_at.is_some() {
+                    tracing::warn!("Stream error while paused: {}", error);
+                    if let Some(player) = &mut self.player {
+                        let _ = player.stop();
+                    }
+                    self.play_state = State::Null;
+                    self.paused_at = None;
+                    self.timeshift_delay = Duration::ZERO;
+                    self.stop_recording();
+                    let scrobble = self.scrobble_track_change(false);
+                    self.reconnect_status = Some(ReconnectStatus::LostWhilePaused { channel_idx: idx });
+                    self.current_channel_idx = None;
+                    self.now_playing = NowPlaying::default();
+                    self.publish_mpris();
+                    return scrobble;
+                }
+
+                // Fail over to the next stream if this one never started
+                if self.play_state != State::Playing {
+                    if let Some(player) = &mut self.player {
+                        match player.play_next_candidate() {
+                            Ok(true) => return Task::none(),
+                            Ok(false) => {}
+                            Err(e) => tracing::error!("Failed to fail over: {}", e),
+                        }
+                    }
+                }
+
+                // Wake up to the alarm sound rather than to silence
+                if self.ringing_alarm.is_some_and(|alarm| !alarm.fallback) {
+                    tracing::warn!("Alarm stream failed ({}), playing the alarm sound", error);
+                    self.play_alarm_fallback();
+                    return Task::none();
+                }
+
+                if let Some(player) = &mut self.player {
+                    let _ = player.stop();
+                }
+                self.play_state = State::Null;
+
+                match self.reconnector.failed(Instant::now()) {
+                    Some(delay) => {
+                        tracing::warn!("Stream error ({}), reconnecting in {:?}", error, delay);
+                        self.reconnect_status = Some(ReconnectStatus::Retrying {
+                            attempt: self.reconnector.attempt(),
+                        });
+                        let request = self.play_request;
+                        return Task::perform(tokio::time::sleep(delay), move |_| Message::Reconnect(request))
+                            .map(cosmic::Action::App);
+                    }
+                    None => {
+                        tracing::error!("Giving up reconnecting after {} attempts", self.reconnector.policy().max_attempts);
+                        self.stop_recording();
+                        self.reconnect_status = Some(ReconnectStatus::GaveUp { channel_idx: idx });
+                        self.current_channel_idx = None;
+                        self.now_playing = NowPlaying::default();
+                        self.publish_mpris();
+                    }
+                }
+            }
+            Message::StreamEnded => {
+                // Loop the alarm sound until it is stopped
+                if self.ringing_alarm.is_some_and(|alarm| alarm.fallback) {
+                    self.play_alarm_fallback();
+                    return Task::none();
+                }
+                // Live streams never end, so this means the server closed the connection
+                return self.update(Message::StreamError("Stream ended".to_string()));
+            }
+            Message::Reconnect(request) => {
+                // Ignore if the user stopped or switched channels while waiting.
+                // The current index follows the station when the list is reordered.
+                let retrying = matches!(self.reconnect_status, Some(ReconnectStatus::Retrying { .. }));
+                if let Some(idx) = self.current_channel_idx.filter(|_| retrying && request == self.play_request) {
+                    return self.start_channel(idx);
+                }
+            }
+            Message::MprisReady(sender) => {
+                self.mpris = Some(sender);
+                self.publish_mpris();
+            }
+            Message::Mpris(command) => match command {
+                mpris::Command::Play => {
+                    if self.paused_at.is_some() {
+                        return self.update(Message::ResumePlayback);
+                    }
+                    if self.current_channel_idx.is_none() {
+                        return self.update(Message::TogglePlayback);
+                    }
+                }
+                mpris::Command::Pause => {
+                    return self.update(Message::PausePlayback);
+                }
+                mpris::Command::Stop => {
+                    return self.update(Message::StopPlayback);
+                }
+                mpris::Command::PlayPause => {
+                    if self.play_state == State::Playing {
+                        return self.update(Message::PausePlayback);
+                    }
+                    if self.paused_at.is_some() {
+                        return self.update(Message::ResumePlayback);
+                    }
+                    return self.update(Message::TogglePlayback);
+                }
+                mpris::Command::Next => {
+                    return self.update(Message::NextChannel);
+                }
+                mpris::Command::Previous => {
+                    return self.update(Message::PreviousChannel);
+                }
+                mpris::Command::SetVolume(volume) => {
+                    let _ = self.update(Message::SetVolume((volume * 100.0).round() as u8));
+                    self.save_volume();
+                }
+            },
+            Message::ToggleMute => {
+                let muted = !self.config.muted;
+                if let Some(handler) = &self.config_handler {
+                    if let Err(e) = self.config.set_muted(handler, muted) {
+                        tracing::error!("Failed to save mute state: {}", e);
+                    }
+                }
+                self.config.muted = muted;
+                self.apply_volume();
+                self.publish_mpris();
+            }
             Message::TogglePopup => {
                 return if let Some(p) = self.popup.take() {
                     destroy_popup(p)
@@ -1,23 +1,286 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::fs;
 use std::path::PathBuf;
 use thiserror::Error;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Channel {
     pub id: String,
     pub name: String,
-    pub uri: String,
+    /// Stream endpoints in order of preference.
+    ///
+    /// Older files with a single `uri` key load as one endpoint.
+    #[serde(alias = "uri", deserialize_with = "deserialize_streams")]
+    pub streams: Vec<StreamEndpoint>,
     pub favourite: bool,
+    /// Website of the station.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// URL of the station icon.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
+    /// Genre tags, e.g. "jazz".
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Free-form notes about the station.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Name of the group the channel is in, `None` for ungrouped channels.
+    ///
+    /// Saved by nesting the channel in its group rather than per channel.
+    #[serde(skip)]
+    pub group: Option<String>,
+}
+
+impl Channel {
+    /// The preferred stream URI, or an empty string if there is none.
+    pub fn primary_uri(&self) -> &str {
+        self.streams.first().map(|s| s.uri.as_str()).unwrap_or_default()
+    }
+
+    /// All stream URIs in order of preference.
+    pub fn uris(&self) -> Vec<String> {
+        self.streams.iter().map(|s| s.uri.clone()).collect()
+    }
+
+    /// Country, language, stream label and tags in one line, e.g.
+    /// "France · French · 192 kbps AAC · jazz, eclectic", if any are set.
+    pub fn details_line(&self) -> Option<String> {
+        let tags = (!self.tags.is_empty()).then(|| self.tags.join(", "));
+        let parts: Vec<String> = [
+            self.country.clone(),
+            self.language.clone(),
+            self.streams.first().and_then(StreamEndpoint::label),
+            tags,
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(" · "))
+    }
+
+    /// How well the channel matches every word of `query`, `None` if it
+    /// doesn't. Words are matched fuzzily against the name, tags and stream
+    /// URLs, with the name counting double.
+    pub fn search_score(&self, query: &str) -> Option<u32> {
+        query.split_whitespace().try_fold(0, |total, word| {
+            let name = fuzzy_score(word, &self.name).map(|score| score * 2);
+            let others = self.tags.iter().chain(self.streams.iter().map(|s| &s.uri));
+            let best = others.filter_map(|text| fuzzy_score(word, text)).chain(name).max()?;
+            Some(total + best)
+        })
+    }
+}
+
+/// Indices of the channels matching `query`, best match first and in list
+/// order among equally good ones.
+pub fn search(channels: &[Channel], query: &str) -> Vec<usize> {
+    let mut matches: Vec<(usize, u32)> = channels
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, channel)| Some((idx, channel.search_score(query)?)))
+        .collect();
+    matches.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    matches.into_iter().map(|(idx, _)| idx).collect()
+}
+
+/// Score `text` for containing the letters of `needle` in order, ignoring
+/// case. Letters that follow each other or start a word score higher, so
+/// "gs" ranks "Groove Salad" above "Lounge Music".
+fn fuzzy_score(needle: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous: Option<usize> = None;
+    for c in needle.to_lowercase().chars() {
+        let found = pos + text[pos..].iter().position(|&t| t == c)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 4;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+/// Split comma separated tags, dropping empty ones.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// One way to reach a station, e.g. a mirror or a bitrate variant.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamEndpoint {
+    pub uri: String,
+    /// Bitrate in kbps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitrate: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codec: Option<String>,
+}
+
+impl StreamEndpoint {
+    /// An endpoint without bitrate or codec labels.
+    pub fn new(uri: impl Into<String>) -> Self {
+        Self {
+            uri: uri.into(),
+            bitrate: None,
+            codec: None,
+        }
+    }
+
+    /// Short label such as "192 kbps AAC", if known.
+    pub fn label(&self) -> Option<String> {
+        match (self.bitrate, &self.codec) {
+            (Some(bitrate), Some(codec)) => Some(format!("{} kbps {}", bitrate, codec)),
+            (Some(bitrate), None) => Some(format!("{} kbps", bitrate)),
+            (None, Some(codec)) => Some(codec.clone()),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Accepts a bare URI string, a single endpoint table, or a list of either.
+fn deserialize_streams<'de, D>(deserializer: D) -> Result<Vec<StreamEndpoint>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum EndpointRepr {
+        Uri(String),
+        Endpoint(StreamEndpoint),
+    }
+
+    impl From<EndpointRepr> for StreamEndpoint {
+        fn from(repr: EndpointRepr) -> Self {
+            match repr {
+                EndpointRepr::Uri(uri) => StreamEndpoint::new(uri),
+                EndpointRepr::Endpoint(endpoint) => endpoint,
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(EndpointRepr),
+        Many(Vec<EndpointRepr>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(endpoint) => vec![endpoint.into()],
+        OneOrMany::Many(endpoints) => endpoints.into_iter().map(Into::into).collect(),
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ChannelList {
+    /// Channels outside of any group. Files without groups only have these.
+    #[serde(default)]
+    pub channels: Vec<Channel>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<ChannelGroup>,
+}
+
+/// A named, ordered set of channels, e.g. "Jazz".
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChannelGroup {
+    pub name: String,
+    #[serde(default)]
     pub channels: Vec<Channel>,
 }
 
+impl ChannelList {
+    /// All channels in one list, ungrouped ones first and then group by
+    /// group, with their `group` set, along with the group names in order.
+    pub fn flatten(self) -> (Vec<Channel>, Vec<String>) {
+        let mut channels = self.channels;
+        for channel in &mut channels {
+            channel.group = None;
+        }
+        let mut names: Vec<String> = Vec::new();
+        for group in self.groups {
+            if !names.contains(&group.name) {
+                names.push(group.name.clone());
+            }
+            channels.extend(group.channels.into_iter().map(|channel| Channel {
+                group: Some(group.name.clone()),
+                ..channel
+            }));
+        }
+        sort_by_group(&mut channels, &names);
+        (channels, names)
+    }
+
+    /// The inverse of `flatten`. Groups without channels are left out.
+    pub fn from_flat(channels: &[Channel], groups: &[String]) -> Self {
+        let in_group = |name: Option<&str>| {
+            channels
+                .iter()
+                .filter(|channel| channel.group.as_deref() == name)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let mut names: Vec<&String> = groups.iter().collect();
+        for name in channels.iter().filter_map(|channel| channel.group.as_ref()) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        Self {
+            channels: in_group(None),
+            groups: names
+                .into_iter()
+                .map(|name| ChannelGroup {
+                    name: name.clone(),
+                    channels: in_group(Some(name)),
+                })
+                .filter(|group| !group.channels.is_empty())
+                .collect(),
+        }
+    }
+}
+
+/// Position of a group in the list: ungrouped channels come first, then the
+/// groups in order, then any group missing from `groups`.
+pub fn group_position(group: Option<&str>, groups: &[String]) -> usize {
+    match group {
+        None => 0,
+        Some(name) => groups.iter().position(|g| g == name).unwrap_or(groups.len()) + 1,
+    }
+}
+
+/// Order channels group by group, keeping their order within each group.
+pub fn sort_by_group(channels: &mut [Channel], groups: &[String]) {
+    channels.sort_by_key(|channel| group_position(channel.group.as_deref(), groups));
+}
+
+/// The index just past the last channel of `group`, where a channel added
+/// to it goes when channels are ordered group by group.
+pub fn group_end(channels: &[Channel], groups: &[String], group: Option<&str>) -> usize {
+    let position = group_position(group, groups);
+    channels
+        .iter()
+        .filter(|channel| group_position(channel.group.as_deref(), groups) <= position)
+        .count()
+}
+
 #[derive(Error, Debug)]
 pub enum ChannelError {
     #[error("Failed to read config file: {0}")]
@@ -29,12 +292,19 @@ pub enum ChannelError {
 }
 
 /// Returns the config directory path
-fn config_dir() -> PathBuf {
+pub(crate) fn config_dir() -> PathBuf {
     dirs::config_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join("cosmic-ext-applet-radio")
 }
 
+/// Where exports go: the downloads directory, or home if there is none.
+pub fn export_directory() -> PathBuf {
+    dirs::download_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(std::env::temp_dir)
+}
+
 /// Returns the full path to the channels.toml file
 fn channels_file_path() -> PathBuf {
     config_dir().join("channels.toml")
@@ -81,6 +351,45 @@ pub fn save_channels(list: &ChannelList) -> Result<(), ChannelError> {
     Ok(())
 }
 
+/// An id for a new channel called `name`, unique among `channels`.
+///
+/// Returns `None` if the name has no characters usable in an id.
+pub fn unique_id(name: &str, channels: &[Channel]) -> Option<String> {
+    let base = name.trim().to_lowercase()
+        .replace(' ', "-")
+        .replace(|c: char| !c.is_alphanumeric() && c != '-', "");
+    if base.is_empty() {
+        return None;
+    }
+    let taken = |id: &str| channels.iter().any(|c| c.id == id);
+    if !taken(&base) {
+        return Some(base);
+    }
+    (2..).map(|n| format!("{}-{}", base, n)).find(|id| !taken(id))
+}
+
+/// Move the channel at `from` to position `to`, shifting the ones in between.
+pub fn move_channel(channels: &mut [Channel], from: usize, to: usize) {
+    if from < to {
+        channels[from..=to].rotate_left(1);
+    } else {
+        channels[to..=from].rotate_right(1);
+    }
+}
+
+/// Where the channel at `idx` ends up after `move_channel(_, from, to)`.
+pub fn moved_index(idx: usize, from: usize, to: usize) -> usize {
+    if idx == from {
+        to
+    } else if from < idx && idx <= to {
+        idx - 1
+    } else if to <= idx && idx < from {
+        idx + 1
+    } else {
+        idx
+    }
+}
+
 /// Get the path to the channels file (for error messages)
 pub fn get_channels_file_path() -> PathBuf {
     channels_file_path()
@@ -97,16 +406,31 @@ mod tests {
                 Channel {
                     id: "fip-radio".to_string(),
                     name: "FIP Radio".to_string(),
-                    uri: "http://icecast.radiofrance.fr/fip-midfi.mp3".to_string(),
+                    streams: vec![
+                        StreamEndpoint {
+                            uri: "http://icecast.radiofrance.fr/fip-hifi.aac".to_string(),
+                            bitrate: Some(192),
+                            codec: Some("AAC".to_string()),
+                        },
+                        StreamEndpoint::new("http://icecast.radiofrance.fr/fip-midfi.mp3"),
+                    ],
                     favourite: true,
+                    homepage: Some("https://www.radiofrance.fr/fip".to_string()),
+                    tags: parse_tags("jazz, eclectic,"),
+                    country: Some("France".to_string()),
+                    language: Some("French".to_string()),
+                    notes: Some("Eclectic mix, no ads".to_string()),
+                    ..Default::default()
                 },
                 Channel {
                     id: "groove-salad".to_string(),
                     name: "Groove Salad".to_string(),
-                    uri: "https://somafm.com/groovesalad256.pls".to_string(),
+                    streams: vec![StreamEndpoint::new("https://somafm.com/groovesalad256.pls")],
                     favourite: false,
+                    ..Default::default()
                 },
             ],
+            groups: Vec::new(),
         };
 
         let toml_str = toml::to_string_pretty(&list).unwrap();
@@ -114,6 +438,179 @@ mod tests {
 
         assert_eq!(parsed.channels.len(), 2);
         assert_eq!(parsed.channels[0].name, "FIP Radio");
+        assert_eq!(parsed.channels[0].streams, list.channels[0].streams);
         assert_eq!(parsed.channels[1].favourite, false);
+        assert_eq!(parsed.channels[0].tags, list.channels[0].tags);
+        assert_eq!(parsed.channels[0].homepage, list.channels[0].homepage);
+        assert_eq!(parsed.channels[0].notes, list.channels[0].notes);
+        assert!(!toml_str.contains("favicon"));
+        assert_eq!(
+            parsed.channels[0].details_line().as_deref(),
+            Some("France · French · 192 kbps AAC · jazz, eclectic")
+        );
+        assert_eq!(parsed.channels[1].details_line(), None);
+    }
+
+    #[test]
+    fn test_unique_id() {
+        let mut channels: Vec<Channel> = ["fip-radio", "groove-salad"]
+            .iter()
+            .map(|id| Channel {
+                id: id.to_string(),
+                ..Default::default()
+            })
+            .collect();
+
+        assert_eq!(unique_id("FIP Radio", &channels).as_deref(), Some("fip-radio-2"));
+        assert_eq!(unique_id("Radio Nova!", &channels).as_deref(), Some("radio-nova"));
+        assert_eq!(unique_id("!!", &channels), None);
+
+        channels.push(Channel {
+            id: "fip-radio-2".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(unique_id("FIP Radio", &channels).as_deref(), Some("fip-radio-3"));
+    }
+
+    #[test]
+    fn test_move_channel() {
+        let mut channels: Vec<Channel> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|id| Channel {
+                id: id.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let ids = |channels: &[Channel]| channels.iter().map(|c| c.id.clone()).collect::<Vec<_>>().join("");
+
+        move_channel(&mut channels, 0, 2);
+        assert_eq!(ids(&channels), "bcad");
+        assert_eq!([0, 1, 2, 3].map(|idx| moved_index(idx, 0, 2)), [2, 0, 1, 3]);
+
+        move_channel(&mut channels, 3, 1);
+        assert_eq!(ids(&channels), "bdca");
+        assert_eq!([0, 1, 2, 3].map(|idx| moved_index(idx, 3, 1)), [0, 2, 3, 1]);
+
+        move_channel(&mut channels, 2, 2);
+        assert_eq!(ids(&channels), "bdca");
+        assert_eq!(moved_index(2, 2, 2), 2);
+    }
+
+    #[test]
+    fn test_search() {
+        let channel = |id: &str, name: &str, tags: &[&str], uri: &str| Channel {
+            id: id.to_string(),
+            name: name.to_string(),
+            streams: vec![StreamEndpoint::new(uri)],
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        };
+        let channels = vec![
+            channel("lounge", "Lounge Music", &["chill"], "https://example.com/lounge.mp3"),
+            channel("groove-salad", "Groove Salad", &["ambient"], "https://ice2.somafm.com/groovesalad-256-mp3"),
+            channel("fip", "FIP", &["jazz", "eclectic"], "http://icecast.radiofrance.fr/fip-hifi.aac"),
+        ];
+
+        assert_eq!(search(&channels, "gs"), vec![1, 0]);
+        assert_eq!(search(&channels, "GROOVE"), vec![1]);
+        assert_eq!(search(&channels, "jaz"), vec![2]);
+        assert_eq!(search(&channels, "somafm"), vec![1]);
+        assert_eq!(search(&channels, "fip jazz"), vec![2]);
+        assert_eq!(search(&channels, "fip ambient"), Vec::<usize>::new());
+        assert_eq!(search(&channels, "  "), vec![0, 1, 2]);
+        assert_eq!(fuzzy_score("ab", "ba"), None);
+    }
+
+    #[test]
+    fn test_groups() {
+        let toml_str = r#"
+            [[channels]]
+            id = "fip-radio"
+            name = "FIP Radio"
+            uri = "http://icecast.radiofrance.fr/fip-midfi.mp3"
+            favourite = false
+
+            [[groups]]
+            name = "Jazz"
+
+            [[groups.channels]]
+            id = "tsf-jazz"
+            name = "TSF Jazz"
+            uri = "http://tsfjazz.ice.infomaniak.ch/tsfjazz-high.mp3"
+            favourite = true
+
+            [[groups]]
+            name = "News"
+
+            [[groups.channels]]
+            id = "bbc-world-service"
+            name = "BBC World Service"
+            uri = "http://stream.live.vc.bbcmedia.co.uk/bbc_world_service"
+            favourite = false
+        "#;
+
+        let (mut channels, groups) = toml::from_str::<ChannelList>(toml_str).unwrap().flatten();
+        assert_eq!(groups, vec!["Jazz", "News"]);
+        let ids: Vec<&str> = channels.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["fip-radio", "tsf-jazz", "bbc-world-service"]);
+        assert_eq!(channels[0].group, None);
+        assert_eq!(channels[2].group.as_deref(), Some("News"));
+
+        assert_eq!(group_end(&channels, &groups, None), 1);
+        assert_eq!(group_end(&channels, &groups, Some("Jazz")), 2);
+        assert_eq!(group_end(&channels, &groups, Some("Rock")), 3);
+
+        // Moving the only jazz station to the news drops the empty group
+        channels[1].group = Some("News".to_string());
+        let list = ChannelList::from_flat(&channels, &groups);
+        assert_eq!(list.channels.len(), 1);
+        assert_eq!(list.groups.len(), 1);
+        assert_eq!(list.groups[0].channels.len(), 2);
+
+        let toml_str = toml::to_string_pretty(&list).unwrap();
+        let (reloaded, groups) = toml::from_str::<ChannelList>(&toml_str).unwrap().flatten();
+        assert_eq!(groups, vec!["News"]);
+        assert_eq!(reloaded[1].id, "tsf-jazz");
+        assert_eq!(reloaded[1].group.as_deref(), Some("News"));
+
+        // Files without groups are one implicit group
+        let ungrouped = ChannelList {
+            channels: reloaded.clone(),
+            groups: Vec::new(),
+        };
+        let (channels, groups) = ungrouped.flatten();
+        assert!(groups.is_empty());
+        assert!(channels.iter().all(|c| c.group.is_none()));
+        assert!(!toml::to_string_pretty(&ChannelList::from_flat(&channels, &groups)).unwrap().contains("groups"));
+    }
+
+    #[test]
+    fn test_legacy_uri_loads_as_single_stream() {
+        let toml_str = r#"
+            [[channels]]
+            id = "fip-radio"
+            name = "FIP Radio"
+            uri = "http://icecast.radiofrance.fr/fip-midfi.mp3"
+            favourite = true
+
+            [[channels]]
+            id = "mirrors"
+            name = "Mirrors"
+            streams = ["http://a.example.com/live", { uri = "http://b.example.com/live", bitrate = 64 }]
+            favourite = false
+        "#;
+
+        let parsed: ChannelList = toml::from_str(toml_str).unwrap();
+
+        assert_eq!(
+            parsed.channels[0].streams,
+            vec![StreamEndpoint::new("http://icecast.radiofrance.fr/fip-midfi.mp3")]
+        );
+        assert_eq!(parsed.channels[1].uris(), vec!["http://a.example.com/live", "http://b.example.com/live"]);
+        assert_eq!(parsed.channels[1].streams[1].bitrate, Some(64));
+        assert!(parsed.channels[0].tags.is_empty());
+        assert_eq!(parsed.channels[0].country, None);
+        assert_eq!(parsed.channels[0].notes, None);
+        assert!(default_channels().channels[0].streams.len() > 1);
     }
 }
@@ -2,8 +2,46 @@
 
 use cosmic::cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
 
-#[derive(Debug, Default, Clone, CosmicConfigEntry, Eq, PartialEq)]
+#[derive(Debug, Clone, CosmicConfigEntry, Eq, PartialEq)]
 #[version = 1]
 pub struct Config {
     demo: String,
+    /// Playback volume in percent (0-100).
+    pub volume: u8,
+    /// Whether playback is muted.
+    pub muted: bool,
+    /// Directory recordings are saved to, empty for `~/Music/Radio`.
+    pub recording_dir: String,
+    /// Whether recordings start a new file on every track change.
+    pub split_recordings: bool,
+    /// Whether to show a desktop notification when the track changes.
+    pub track_notifications: bool,
+    /// Radio Browser compatible directory to search, empty for the public one.
+    pub radio_browser_url: String,
+    /// Names of the station groups shown collapsed.
+    pub collapsed_groups: Vec<String>,
+    /// Global shortcut for play/pause, e.g. "Super+Alt+P", empty for none.
+    pub play_pause_shortcut: String,
+    /// Global shortcut for switching to the next station.
+    pub next_station_shortcut: String,
+    /// Global shortcut for switching to the previous station.
+    pub previous_station_shortcut: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            demo: String::new(),
+            volume: 100,
+            muted: false,
+            recording_dir: String::new(),
+            split_recordings: false,
+            track_notifications: false,
+            radio_browser_url: String::new(),
+            collapsed_groups: Vec::new(),
+            play_pause_shortcut: String::new(),
+            next_station_shortcut: String::new(),
+            previous_station_shortcut: String::new(),
+        }
+    }
 }
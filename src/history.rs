@@ -0,0 +1,242 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Log of played tracks, appended to `history.jsonl` next to `channels.toml`
+//! with one JSON object per line.
+
+use crate::channels::{self, Channel};
+use crate::metadata::{self, NowPlaying};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// A song heard on a station, as kept in the history and the liked tracks.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StationTrack {
+    pub station_id: String,
+    /// Station name at the time, kept for stations removed since.
+    pub station_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    pub title: String,
+}
+
+impl StationTrack {
+    /// The song playing on `channel`, if its title is known.
+    pub fn now_playing(channel: &Channel, now_playing: &NowPlaying) -> Option<Self> {
+        Some(Self {
+            station_id: channel.id.clone(),
+            station_name: channel.name.clone(),
+            artist: now_playing.artist.clone(),
+            title: now_playing.title.clone()?,
+        })
+    }
+
+    /// The song in "Artist - Title" form.
+    pub fn track_line(&self) -> String {
+        metadata::track_line(self.artist.as_deref(), &self.title)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub played_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub track: StationTrack,
+}
+
+impl HistoryEntry {
+    /// Whether the entry matches every word of `query`, ignoring case.
+    pub fn matches(&self, query: &str) -> bool {
+        let track = &self.track;
+        let haystack = format!(
+            "{} {} {}",
+            track.artist.as_deref().unwrap_or_default(),
+            track.title,
+            track.station_name
+        )
+        .to_lowercase();
+        query
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| haystack.contains(word))
+    }
+}
+
+/// File formats the history can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum HistoryError {
+    #[error("Failed to access history file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to serialize JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Returns the full path to the history.jsonl file
+fn history_file_path() -> PathBuf {
+    channels::config_dir().join("history.jsonl")
+}
+
+/// Load the history, oldest first, or nothing if it doesn't exist yet.
+pub fn load_history() -> Result<Vec<HistoryEntry>, HistoryError> {
+    read_entries(&history_file_path())
+}
+
+/// Append a played track to the history file
+pub fn append_entry(entry: &HistoryEntry) -> Result<(), HistoryError> {
+    fs::create_dir_all(channels::config_dir())?;
+    write_entry(&history_file_path(), entry)
+}
+
+fn read_entries(path: &Path) -> Result<Vec<HistoryEntry>, HistoryError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)?;
+    // A line cut short by a crash shouldn't lose the whole history
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                tracing::warn!("Skipping invalid history line: {}", e);
+                None
+            }
+        })
+        .collect())
+}
+
+fn write_entry(path: &Path, entry: &HistoryEntry) -> Result<(), HistoryError> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Render entries as CSV with local timestamps.
+pub fn to_csv<'a>(entries: impl IntoIterator<Item = &'a HistoryEntry>) -> String {
+    let mut csv = String::from("played_at,station_id,station_name,artist,title\n");
+    for entry in entries {
+        let fields = [
+            entry.played_at.with_timezone(&Local).to_rfc3339(),
+            entry.track.station_id.clone(),
+            entry.track.station_name.clone(),
+            entry.track.artist.clone().unwrap_or_default(),
+            entry.track.title.clone(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote a CSV field if it contains separators, quotes or line breaks.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Write entries to a new file in `directory`, returning its path.
+pub fn export<'a>(
+    entries: impl IntoIterator<Item = &'a HistoryEntry>,
+    format: ExportFormat,
+    directory: &Path,
+) -> Result<PathBuf, HistoryError> {
+    let content = match format {
+        ExportFormat::Csv => to_csv(entries),
+        ExportFormat::Json => serde_json::to_string_pretty(&entries.into_iter().collect::<Vec<_>>())?,
+    };
+    fs::create_dir_all(directory)?;
+    let path = directory.join(format!(
+        "radio-history-{}.{}",
+        Local::now().format("%Y-%m-%d-%H%M%S"),
+        format.extension()
+    ));
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn entry(minute: u32, artist: Option<&str>, title: &str) -> HistoryEntry {
+        HistoryEntry {
+            played_at: Utc.with_ymd_and_hms(2024, 3, 1, 14, minute, 0).unwrap(),
+            track: StationTrack {
+                station_id: "groove-salad".to_string(),
+                station_name: "SomaFM Groove Salad".to_string(),
+                artist: artist.map(str::to_string),
+                title: title.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_history_file_round_trip() {
+        let directory = std::env::temp_dir().join(format!("radio-history-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("history.jsonl");
+        let _ = fs::remove_file(&path);
+
+        assert!(read_entries(&path).unwrap().is_empty());
+
+        let entries = vec![entry(1, Some("Boards of Canada"), "Dayvan Cowboy"), entry(6, None, "Station ID")];
+        for entry in &entries {
+            write_entry(&path, entry).unwrap();
+        }
+        // A truncated last line is skipped
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "{\"played_at\":").unwrap();
+        assert_eq!(read_entries(&path).unwrap(), entries);
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("{\"played_at\":\"2024-03-01T14:01:00Z\",\"station_id\":\"groove-salad\""));
+        assert!(!content.lines().nth(1).unwrap().contains("artist"));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_search_and_csv_export() {
+        let entries = vec![
+            entry(1, Some("Boards of Canada"), "Dayvan Cowboy"),
+            entry(6, Some("Tycho"), "Awake, \"Live\""),
+        ];
+
+        assert!(entries[0].matches("boards cowboy"));
+        assert!(entries[0].matches("groove"));
+        assert!(entries[0].matches(""));
+        assert!(!entries[0].matches("boards tycho"));
+        assert_eq!(entries[1].track.track_line(), "Tycho - Awake, \"Live\"");
+
+        let csv = to_csv(&entries);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "played_at,station_id,station_name,artist,title");
+        assert!(lines[1].ends_with(",groove-salad,SomaFM Groove Salad,Boards of Canada,Dayvan Cowboy"));
+        assert!(lines[2].ends_with(",Tycho,\"Awake, \"\"Live\"\"\""));
+    }
+}
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Tracks the user liked, stored in `liked_tracks.toml` next to `channels.toml`.
+
+use crate::channels;
+use crate::history::StationTrack;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Web search the "search on the web" action opens, followed by the query.
+const WEB_SEARCH_URL: &str = "https://duckduckgo.com/";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LikedTrack {
+    pub liked_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub track: StationTrack,
+}
+
+impl LikedTrack {
+    /// Whether this is the song `artist` - `title`, ignoring the station.
+    pub fn is_song(&self, artist: Option<&str>, title: &str) -> bool {
+        self.track.artist.as_deref() == artist && self.track.title == title
+    }
+
+    /// URL of a web search for the song.
+    pub fn search_url(&self) -> String {
+        let mut url = url::Url::parse(WEB_SEARCH_URL).expect("valid search URL");
+        url.query_pairs_mut().append_pair("q", &self.track.track_line());
+        url.into()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct LikedList {
+    #[serde(default)]
+    pub tracks: Vec<LikedTrack>,
+}
+
+#[derive(Error, Debug)]
+pub enum LikedError {
+    #[error("Failed to access liked tracks file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse TOML: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("Failed to serialize TOML: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+/// Returns the full path to the liked_tracks.toml file
+fn liked_file_path() -> PathBuf {
+    channels::config_dir().join("liked_tracks.toml")
+}
+
+/// Load liked tracks from the config file, or none if it doesn't exist yet.
+pub fn load_liked() -> Result<LikedList, LikedError> {
+    let path = liked_file_path();
+    if !path.exists() {
+        return Ok(LikedList::default());
+    }
+    let content = fs::read_to_string(&path)?;
+    Ok(toml::from_str(&content)?)
+}
+
+/// Save liked tracks to the config file
+pub fn save_liked(list: &LikedList) -> Result<(), LikedError> {
+    fs::create_dir_all(channels::config_dir())?;
+    let content = toml::to_string_pretty(list)?;
+    fs::write(liked_file_path(), content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_liked_track() {
+        let track = LikedTrack {
+            liked_at: Utc.with_ymd_and_hms(2024, 3, 1, 14, 5, 0).unwrap(),
+            track: StationTrack {
+                station_id: "groove-salad".to_string(),
+                station_name: "SomaFM Groove Salad".to_string(),
+                artist: Some("Boards of Canada".to_string()),
+                title: "Roygbiv & more".to_string(),
+            },
+        };
+
+        assert!(track.is_song(Some("Boards of Canada"), "Roygbiv & more"));
+        assert!(!track.is_song(None, "Roygbiv & more"));
+        assert_eq!(
+            track.search_url(),
+            "https://duckduckgo.com/?q=Boards+of+Canada+-+Roygbiv+%26+more"
+        );
+
+        let list = LikedList { tracks: vec![track] };
+        let toml_str = toml::to_string_pretty(&list).unwrap();
+        assert!(toml_str.contains("liked_at = \"2024-03-01T14:05:00Z\""));
+        assert!(toml_str.contains("station_id = \"groove-salad\""));
+        assert_eq!(toml::from_str::<LikedList>(&toml_str).unwrap(), list);
+    }
+}
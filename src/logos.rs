@@ -0,0 +1,342 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Station logos: where to find them, and an on-disk cache of downloaded ones.
+
+use crate::channels::Channel;
+use md5::{Digest, Md5};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+use url::Url;
+
+/// Largest logo that will be downloaded or shown.
+const MAX_LOGO_BYTES: u64 = 512 * 1024;
+
+/// Size the cache is pruned back to, oldest logos first.
+const MAX_CACHE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Age after which a cached logo is downloaded again.
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Background colours of the initials badges.
+const BADGE_COLORS: [(u8, u8, u8); 8] = [
+    (0x1c, 0x71, 0xd8),
+    (0x26, 0xa2, 0x69),
+    (0xe5, 0xa5, 0x0a),
+    (0xc6, 0x46, 0x00),
+    (0xc0, 0x1c, 0x28),
+    (0x81, 0x3d, 0x9c),
+    (0x86, 0x5e, 0x3c),
+    (0x3d, 0x84, 0x8c),
+];
+
+#[derive(Error, Debug)]
+pub enum LogoError {
+    #[error("Failed to access logo: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to download logo: {0}")]
+    Download(#[from] reqwest::Error),
+    #[error("Logo is larger than {} bytes", MAX_LOGO_BYTES)]
+    TooLarge,
+    #[error("Not a supported image")]
+    NotAnImage,
+}
+
+/// Where the logo of a station comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LogoSource {
+    Remote(String),
+    Local(PathBuf),
+}
+
+impl LogoSource {
+    /// The configured logo of `channel`, a URL or a local file, or else
+    /// the favicon of its homepage.
+    pub fn for_channel(channel: &Channel) -> Option<Self> {
+        if let Some(logo) = channel.favicon.as_deref().map(str::trim).filter(|logo| !logo.is_empty()) {
+            return Self::parse(logo);
+        }
+        let homepage = Url::parse(channel.homepage.as_deref()?.trim()).ok()?;
+        if !matches!(homepage.scheme(), "http" | "https") {
+            return None;
+        }
+        Some(Self::Remote(homepage.join("/favicon.ico").ok()?.to_string()))
+    }
+
+    fn parse(logo: &str) -> Option<Self> {
+        if logo.starts_with("http://") || logo.starts_with("https://") {
+            return Some(Self::Remote(logo.to_string()));
+        }
+        if logo.starts_with("file://") {
+            return Url::parse(logo).ok()?.to_file_path().ok().map(Self::Local);
+        }
+        let path = match logo.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir()?.join(rest),
+            None => PathBuf::from(logo),
+        };
+        path.is_absolute().then_some(Self::Local(path))
+    }
+
+    /// Identifies the logo, e.g. to remember it was loaded.
+    pub fn key(&self) -> String {
+        match self {
+            Self::Remote(url) => url.clone(),
+            Self::Local(path) => path.display().to_string(),
+        }
+    }
+}
+
+/// Whether a logo setting can be used, for validating forms.
+pub fn is_valid_logo(logo: &str) -> bool {
+    LogoSource::parse(logo.trim()).is_some()
+}
+
+/// Where downloaded logos are kept.
+pub fn cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("cosmic-ext-applet-radio")
+        .join("logos")
+}
+
+/// Up to two letters standing for the station, e.g. "GS" for "Groove Salad".
+pub fn initials(name: &str) -> String {
+    let words: Vec<&str> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    let letters: String = match words.as_slice() {
+        [] => String::new(),
+        [word] => word.chars().take(2).collect(),
+        [first, second, ..] => first.chars().take(1).chain(second.chars().take(1)).collect(),
+    };
+    letters.to_uppercase()
+}
+
+/// The badge colour of a station, the same on every run.
+pub fn badge_color(name: &str) -> (u8, u8, u8) {
+    let hash = name.bytes().fold(0u32, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte.into()));
+    BADGE_COLORS[hash as usize % BADGE_COLORS.len()]
+}
+
+/// Get a logo as a local file, downloading it into `cache_dir` unless a
+/// fresh copy is cached there.
+pub async fn fetch(source: &LogoSource, cache_dir: &Path) -> Result<PathBuf, LogoError> {
+    let url = match source {
+        LogoSource::Local(path) => {
+            if fs::metadata(path)?.len() > MAX_LOGO_BYTES {
+                return Err(LogoError::TooLarge);
+            }
+            return Ok(path.clone());
+        }
+        LogoSource::Remote(url) => url,
+    };
+
+    let stem = cache_stem(url);
+    let cached = find_cached(cache_dir, &stem);
+    if let Some((path, modified)) = &cached
+        && is_fresh(*modified, SystemTime::now())
+    {
+        return Ok(path.clone());
+    }
+
+    match download(url).await {
+        Ok(image) => {
+            let extension = image_extension(&image).ok_or(LogoError::NotAnImage)?;
+            fs::create_dir_all(cache_dir)?;
+            if let Some((path, _)) = &cached {
+                let _ = fs::remove_file(path);
+            }
+            let path = cache_dir.join(format!("{}.{}", stem, extension));
+            fs::write(&path, image)?;
+            if let Err(e) = prune(cache_dir, MAX_CACHE_BYTES) {
+                tracing::warn!("Failed to prune logo cache: {}", e);
+            }
+            Ok(path)
+        }
+        // An outdated logo is better than none
+        Err(e) => match cached {
+            Some((path, _)) => {
+                tracing::warn!("Using outdated logo, refresh failed: {}", e);
+                Ok(path)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+async fn download(url: &str) -> Result<Vec<u8>, LogoError> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    if response.content_length().is_some_and(|length| length > MAX_LOGO_BYTES) {
+        return Err(LogoError::TooLarge);
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        body.extend_from_slice(&chunk);
+        if body.len() as u64 > MAX_LOGO_BYTES {
+            return Err(LogoError::TooLarge);
+        }
+    }
+    Ok(body)
+}
+
+/// Cached logos are named after a hash of their URL, plus the image extension.
+fn cache_stem(url: &str) -> String {
+    let mut hasher = Md5::new();
+    hasher.update(url.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn find_cached(cache_dir: &Path, stem: &str) -> Option<(PathBuf, SystemTime)> {
+    fs::read_dir(cache_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.file_stem().is_some_and(|s| s == stem))
+        .and_then(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((path, modified))
+        })
+}
+
+fn is_fresh(modified: SystemTime, now: SystemTime) -> bool {
+    now.duration_since(modified).map_or(true, |age| age < MAX_AGE)
+}
+
+/// The file extension for image data, or `None` if it isn't an image we can show.
+fn image_extension(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if data.starts_with(b"\xff\xd8\xff") {
+        Some("jpg")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        Some("webp")
+    } else if data.starts_with(b"\x00\x00\x01\x00") {
+        Some("ico")
+    } else if data.starts_with(b"BM") {
+        Some("bmp")
+    } else {
+        let start = String::from_utf8_lossy(&data[..data.len().min(1024)]).to_lowercase();
+        start.contains("<svg").then_some("svg")
+    }
+}
+
+/// Delete the least recently downloaded logos until the cache is at most `max_bytes`.
+fn prune(cache_dir: &Path, max_bytes: u64) -> std::io::Result<()> {
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = fs::read_dir(cache_dir)?
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|metadata| metadata.is_file())?;
+            Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+        })
+        .collect();
+    files.sort();
+
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    for (_, size, path) in files {
+        if total <= max_bytes {
+            break;
+        }
+        fs::remove_file(path)?;
+        total -= size;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{respond, TestServer};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[test]
+    fn test_sources_and_badges() {
+        let mut channel = Channel {
+            name: "SomaFM Groove Salad".to_string(),
+            homepage: Some("https://somafm.com/groovesalad/".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            LogoSource::for_channel(&channel),
+            Some(LogoSource::Remote("https://somafm.com/favicon.ico".to_string()))
+        );
+
+        channel.favicon = Some("file:///usr/share/icons/radio.png".to_string());
+        assert_eq!(
+            LogoSource::for_channel(&channel),
+            Some(LogoSource::Local(PathBuf::from("/usr/share/icons/radio.png")))
+        );
+        channel.favicon = Some("logo.png".to_string());
+        assert_eq!(LogoSource::for_channel(&channel), None);
+        assert!(!is_valid_logo("logo.png"));
+        assert!(is_valid_logo("/usr/share/icons/radio.png"));
+
+        assert_eq!(initials("SomaFM Groove Salad"), "SG");
+        assert_eq!(initials("FIP"), "FI");
+        assert_eq!(initials("  ö1 — Ö1 "), "ÖÖ");
+        assert_eq!(initials("!!"), "");
+        assert_eq!(badge_color("FIP"), badge_color("FIP"));
+
+        assert_eq!(image_extension(PNG), Some("png"));
+        assert_eq!(image_extension(b"<?xml version=\"1.0\"?><svg></svg>"), Some("svg"));
+        assert_eq!(image_extension(b"<!DOCTYPE html><html>"), None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_caches_and_prunes() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let server = TestServer::spawn(move |request, stream| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            match request.path.as_str() {
+                "/logo.png" => respond(stream, "200 OK", "image/png", PNG),
+                "/page.png" => respond(stream, "200 OK", "text/html", b"<html>Not found</html>"),
+                _ => respond(stream, "404 Not Found", "text/plain", b""),
+            }
+        });
+        let cache = std::env::temp_dir().join(format!("radio-logos-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&cache);
+
+        // Downloaded once, then served from the cache
+        let source = LogoSource::Remote(server.url("/logo.png"));
+        let path = fetch(&source, &cache).await.unwrap();
+        assert_eq!(path.extension().unwrap(), "png");
+        assert_eq!(fs::read(&path).unwrap(), PNG);
+        assert_eq!(fetch(&source, &cache).await.unwrap(), path);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // Expired logos are downloaded again
+        let expired = SystemTime::now() - MAX_AGE - Duration::from_secs(60);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(expired).unwrap();
+        assert_eq!(fetch(&source, &cache).await.unwrap(), path);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        assert!(matches!(
+            fetch(&LogoSource::Remote(server.url("/page.png")), &cache).await,
+            Err(LogoError::NotAnImage)
+        ));
+        assert!(fetch(&LogoSource::Remote(server.url("/missing.ico")), &cache).await.is_err());
+
+        // Local files are used in place
+        assert_eq!(fetch(&LogoSource::Local(path.clone()), &cache).await.unwrap(), path);
+
+        // Pruning drops the oldest logos first
+        let old = cache.join("old.png");
+        fs::write(&old, PNG).unwrap();
+        fs::File::options().write(true).open(&old).unwrap().set_modified(expired).unwrap();
+        prune(&cache, PNG.len() as u64).unwrap();
+        assert!(!old.exists());
+        assert!(path.exists());
+
+        fs::remove_dir_all(&cache).unwrap();
+    }
+}
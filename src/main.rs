@@ -1,10 +1,29 @@
 // SPDX-License-Identifier: MPL-2.0
 
+mod alarms;
 mod app;
 mod channels;
 mod config;
+mod history;
 mod i18n;
+mod liked;
+mod logos;
+mod metadata;
+mod mpris;
+mod notifications;
 mod player;
+mod playlist;
+mod radio_browser;
+mod recorder;
+mod recording_schedule;
+mod scrobbler;
+mod shortcuts;
+mod sleep_timer;
+mod station_lists;
+#[cfg(test)]
+mod test_bus;
+#[cfg(test)]
+mod test_server;
 
 fn main() -> cosmic::iced::Result {
     // Initialize GStreamer
@@ -0,0 +1,141 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use gstreamer::tags;
+
+/// Track and stream information extracted from GStreamer tags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NowPlaying {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// Station name as announced by the stream (`icy-name`).
+    pub organization: Option<String>,
+    pub genre: Option<String>,
+    /// Bitrate in bits per second.
+    pub bitrate: Option<u32>,
+    pub codec: Option<String>,
+}
+
+impl NowPlaying {
+    /// Merge a tag list into the current state.
+    ///
+    /// Streams send tags piecemeal, so values missing from `tags` are kept.
+    /// A new title always replaces the artist, since a stale artist would be
+    /// shown next to the wrong song.
+    pub fn update_from_tags(&mut self, tags: &gstreamer::TagListRef) {
+        if let Some(title) = tags.get::<tags::Title>() {
+            let (artist, title) = split_stream_title(title.get());
+            self.title = title;
+            self.artist = artist;
+        }
+        if let Some(artist) = tags.get::<tags::Artist>() {
+            self.artist = non_empty(artist.get());
+        }
+        if let Some(album) = tags.get::<tags::Album>() {
+            self.album = non_empty(album.get());
+        }
+        if let Some(organization) = tags.get::<tags::Organization>() {
+            self.organization = non_empty(organization.get());
+        }
+        if let Some(genre) = tags.get::<tags::Genre>() {
+            self.genre = non_empty(genre.get());
+        }
+        if let Some(bitrate) = tags
+            .get::<tags::Bitrate>()
+            .or_else(|| tags.get::<tags::NominalBitrate>())
+        {
+            let bitrate = bitrate.get();
+            if bitrate > 0 {
+                self.bitrate = Some(bitrate);
+            }
+        }
+        if let Some(codec) = tags
+            .get::<tags::AudioCodec>()
+            .or_else(|| tags.get::<tags::Codec>())
+        {
+            self.codec = non_empty(codec.get());
+        }
+    }
+
+    /// The song in "Artist - Title" form, if a title is known.
+    pub fn track_line(&self) -> Option<String> {
+        let title = self.title.as_deref()?;
+        Some(track_line(self.artist.as_deref(), title))
+    }
+
+    /// Secondary stream details such as genre, bitrate and codec.
+    pub fn details_line(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(album) = &self.album {
+            parts.push(album.clone());
+        }
+        if let Some(genre) = &self.genre {
+            parts.push(genre.clone());
+        }
+        if let Some(bitrate) = self.bitrate {
+            parts.push(format!("{} kbps", bitrate / 1000));
+        }
+        if let Some(codec) = &self.codec {
+            parts.push(codec.clone());
+        }
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join(" · "))
+        }
+    }
+}
+
+/// A song in "Artist - Title" form, or just the title.
+pub fn track_line(artist: Option<&str>, title: &str) -> String {
+    match artist {
+        Some(artist) => format!("{} - {}", artist, title),
+        None => title.to_string(),
+    }
+}
+
+/// Split an Icecast `StreamTitle` of the form "Artist - Title".
+///
+/// Returns `(artist, title)`. Titles without a separator are returned whole.
+pub fn split_stream_title(stream_title: &str) -> (Option<String>, Option<String>) {
+    let stream_title = stream_title.trim();
+    match stream_title.split_once(" - ") {
+        Some((artist, title)) if !artist.trim().is_empty() && !title.trim().is_empty() => {
+            (non_empty(artist), non_empty(title))
+        }
+        _ => (None, non_empty(stream_title)),
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_stream_title() {
+        assert_eq!(
+            split_stream_title("Boards of Canada - Roygbiv"),
+            (Some("Boards of Canada".to_string()), Some("Roygbiv".to_string()))
+        );
+        // Only the first separator splits, the rest belongs to the title
+        assert_eq!(
+            split_stream_title("Air - Moon Safari - Remastered"),
+            (Some("Air".to_string()), Some("Moon Safari - Remastered".to_string()))
+        );
+        assert_eq!(
+            split_stream_title("Station ID"),
+            (None, Some("Station ID".to_string()))
+        );
+        assert_eq!(split_stream_title(" - "), (None, Some("-".to_string())));
+        assert_eq!(split_stream_title(""), (None, None));
+    }
+}
@@ -0,0 +1,409 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! MPRIS2 media player service, so media keys, the COSMIC media widget and
+//! `playerctl` can control the applet.
+
+use crate::metadata::NowPlaying;
+use std::collections::HashMap;
+use tokio::sync::mpsc::UnboundedSender;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+use zbus::{connection, interface, Connection};
+
+/// Well-known bus name of the service.
+pub const BUS_NAME: &str = "org.mpris.MediaPlayer2.cosmic_ext_applet_radio";
+
+/// Object path mandated by the MPRIS specification.
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+
+/// Requests received from MPRIS clients.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Play,
+    Pause,
+    PlayPause,
+    Stop,
+    Next,
+    Previous,
+    /// Set the volume, from 0.0 to 1.0.
+    SetVolume(f64),
+}
+
+/// Playback status as defined by MPRIS.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlaybackStatus {
+    Playing,
+    Paused,
+    #[default]
+    Stopped,
+}
+
+impl PlaybackStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            PlaybackStatus::Playing => "Playing",
+            PlaybackStatus::Paused => "Paused",
+            PlaybackStatus::Stopped => "Stopped",
+        }
+    }
+}
+
+/// Player state published over MPRIS.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct State {
+    pub status: PlaybackStatus,
+    /// Volume from 0.0 to 1.0.
+    pub volume: f64,
+    /// Index of the current channel, used to build the track id.
+    pub channel_idx: Option<usize>,
+    /// Name of the current channel.
+    pub station: Option<String>,
+    /// Stream URI of the current channel.
+    pub uri: Option<String>,
+    pub now_playing: NowPlaying,
+    /// Whether there are channels to switch to with Next and Previous.
+    pub can_switch: bool,
+}
+
+impl State {
+    /// Build the `Metadata` property map.
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        let mut map = HashMap::new();
+
+        let track_id = match self.channel_idx {
+            Some(idx) => format!("/com/github/maxattwell/CosmicExtAppletRadio/Station/{}", idx),
+            None => "/org/mpris/MediaPlayer2/TrackList/NoTrack".to_string(),
+        };
+        if let Ok(path) = ObjectPath::try_from(track_id) {
+            insert(&mut map, "mpris:trackid", path);
+        }
+
+        let Some(station) = &self.station else {
+            return map;
+        };
+
+        // Fall back to the station name so widgets always have something to show
+        let title = self.now_playing.title.clone().unwrap_or_else(|| station.clone());
+        insert(&mut map, "xesam:title", title);
+        if let Some(artist) = &self.now_playing.artist {
+            insert(&mut map, "xesam:artist", vec![artist.clone()]);
+        }
+        let album = self.now_playing.album.clone().unwrap_or_else(|| station.clone());
+        insert(&mut map, "xesam:album", album);
+        if let Some(genre) = &self.now_playing.genre {
+            insert(&mut map, "xesam:genre", vec![genre.clone()]);
+        }
+        if let Some(uri) = &self.uri {
+            insert(&mut map, "xesam:url", uri.clone());
+        }
+
+        map
+    }
+}
+
+fn insert<'a>(map: &mut HashMap<String, OwnedValue>, key: &str, value: impl Into<Value<'a>>) {
+    if let Ok(value) = OwnedValue::try_from(value.into()) {
+        map.insert(key.to_string(), value);
+    }
+}
+
+/// The `org.mpris.MediaPlayer2` interface.
+struct Root;
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) {}
+
+    fn quit(&self) {}
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> String {
+        "Internet Radio".to_string()
+    }
+
+    #[zbus(property)]
+    fn desktop_entry(&self) -> String {
+        "com.github.maxattwell.cosmic-ext-applet-radio".to_string()
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        vec!["http".to_string(), "https".to_string()]
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// The `org.mpris.MediaPlayer2.Player` interface.
+struct Player {
+    state: State,
+    commands: UnboundedSender<Command>,
+}
+
+impl Player {
+    fn send(&self, command: Command) {
+        if let Err(e) = self.commands.send(command) {
+            tracing::error!("Failed to forward MPRIS command: {}", e);
+        }
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn next(&self) {
+        self.send(Command::Next);
+    }
+
+    fn previous(&self) {
+        self.send(Command::Previous);
+    }
+
+    fn pause(&self) {
+        self.send(Command::Pause);
+    }
+
+    fn play_pause(&self) {
+        self.send(Command::PlayPause);
+    }
+
+    fn stop(&self) {
+        self.send(Command::Stop);
+    }
+
+    fn play(&self) {
+        self.send(Command::Play);
+    }
+
+    /// Live streams cannot seek.
+    fn seek(&self, _offset: i64) {}
+
+    /// Live streams cannot seek.
+    fn set_position(&self, _track_id: ObjectPath<'_>, _position: i64) {}
+
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        self.state.status.as_str().to_string()
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        self.state.metadata()
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.state.volume
+    }
+
+    #[zbus(property)]
+    fn set_volume(&mut self, volume: f64) {
+        self.send(Command::SetVolume(volume.clamp(0.0, 1.0)));
+    }
+
+    #[zbus(property)]
+    fn position(&self) -> i64 {
+        0
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        self.state.can_switch
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        self.state.can_switch
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        self.state.can_switch || self.state.station.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        self.state.station.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// A running MPRIS service.
+pub struct Server {
+    connection: Connection,
+}
+
+impl Server {
+    /// Register the service on the session bus.
+    ///
+    /// Commands from clients are sent to `commands`.
+    pub async fn start(commands: UnboundedSender<Command>) -> zbus::Result<Self> {
+        Self::start_on(connection::Builder::session()?, commands).await
+    }
+
+    /// Register the service on the bus of the given connection builder.
+    pub async fn start_on(
+        builder: connection::Builder<'_>,
+        commands: UnboundedSender<Command>,
+    ) -> zbus::Result<Self> {
+        let player = Player {
+            state: State::default(),
+            commands,
+        };
+
+        let connection = builder
+            .name(BUS_NAME)?
+            .serve_at(OBJECT_PATH, Root)?
+            .serve_at(OBJECT_PATH, player)?
+            .build()
+            .await?;
+
+        Ok(Self { connection })
+    }
+
+    /// Publish a new player state, emitting `PropertiesChanged` for what changed.
+    pub async fn publish(&self, state: State) -> zbus::Result<()> {
+        let iface_ref = self
+            .connection
+            .object_server()
+            .interface::<_, Player>(OBJECT_PATH)
+            .await?;
+        let mut iface = iface_ref.get_mut().await;
+        if iface.state == state {
+            return Ok(());
+        }
+
+        let old = std::mem::replace(&mut iface.state, state);
+        let emitter: &SignalEmitter<'_> = iface_ref.signal_emitter();
+
+        if old.status != iface.state.status {
+            iface.playback_status_changed(emitter).await?;
+        }
+        if old.volume != iface.state.volume {
+            iface.volume_changed(emitter).await?;
+        }
+        if old.metadata() != iface.state.metadata() {
+            iface.metadata_changed(emitter).await?;
+        }
+        if old.can_switch != iface.state.can_switch {
+            iface.can_go_next_changed(emitter).await?;
+            iface.can_go_previous_changed(emitter).await?;
+        }
+        if old.station.is_some() != iface.state.station.is_some() {
+            iface.can_play_changed(emitter).await?;
+            iface.can_pause_changed(emitter).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+    use crate::test_bus::PrivateBus;
+
+    #[tokio::test]
+    async fn test_mpris_on_private_bus() {
+        let Some(bus) = PrivateBus::spawn() else {
+            eprintln!("dbus-daemon not available, skipping");
+            return;
+        };
+
+        let (commands_tx, mut commands_rx) = mpsc::unbounded_channel();
+        let server = Server::start_on(
+            connection::Builder::address(bus.address.as_str()).unwrap(),
+            commands_tx,
+        )
+        .await
+        .unwrap();
+
+        let client = connection::Builder::address(bus.address.as_str())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let proxy: zbus::Proxy<'_> = zbus::proxy::Builder::new(&client)
+            .destination(BUS_NAME)
+            .unwrap()
+            .path(OBJECT_PATH)
+            .unwrap()
+            .interface("org.mpris.MediaPlayer2.Player")
+            .unwrap()
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await
+            .unwrap();
+
+        // Method calls are forwarded as commands
+        let _: () = proxy.call("Next", &()).await.unwrap();
+        assert_eq!(commands_rx.recv().await, Some(Command::Next));
+        proxy.set_property("Volume", 0.25).await.unwrap();
+        assert_eq!(commands_rx.recv().await, Some(Command::SetVolume(0.25)));
+
+        // Published state is visible to clients
+        let mut state = State {
+            status: PlaybackStatus::Playing,
+            volume: 0.5,
+            channel_idx: Some(0),
+            station: Some("FIP Radio".to_string()),
+            can_switch: true,
+            ..Default::default()
+        };
+        state.now_playing.artist = Some("Air".to_string());
+        state.now_playing.title = Some("La femme d'argent".to_string());
+        server.publish(state).await.unwrap();
+
+        let status: String = proxy.get_property("PlaybackStatus").await.unwrap();
+        assert_eq!(status, "Playing");
+        let volume: f64 = proxy.get_property("Volume").await.unwrap();
+        assert_eq!(volume, 0.5);
+        let metadata: HashMap<String, OwnedValue> = proxy.get_property("Metadata").await.unwrap();
+        let title: String = metadata["xesam:title"].try_clone().unwrap().try_into().unwrap();
+        assert_eq!(title, "La femme d'argent");
+        let artists: Vec<String> = metadata["xesam:artist"].try_clone().unwrap().try_into().unwrap();
+        assert_eq!(artists, vec!["Air".to_string()]);
+    }
+}
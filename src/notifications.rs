@@ -0,0 +1,308 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Desktop notifications on track changes through `org.freedesktop.Notifications`.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedReceiver;
+use zbus::zvariant::Value;
+use zbus::{connection, Connection};
+
+/// Minimum time between two notifications. Titles arriving faster are
+/// coalesced into one showing the latest.
+pub const MIN_INTERVAL: Duration = Duration::from_secs(20);
+
+const BUS_NAME: &str = "org.freedesktop.Notifications";
+const OBJECT_PATH: &str = "/org/freedesktop/Notifications";
+
+/// How long notifications stay on screen, in milliseconds.
+const EXPIRE_TIMEOUT: i32 = 5000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// Station name.
+    pub summary: String,
+    /// The song in "Artist - Title" form.
+    pub body: String,
+}
+
+/// Spaces out notifications by a minimum interval.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    min_interval: Duration,
+    last_shown: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            last_shown: None,
+        }
+    }
+
+    /// When the next notification may be shown, or `None` if it may be shown at `now`.
+    pub fn wait_until(&self, now: Instant) -> Option<Instant> {
+        self.last_shown
+            .map(|last| last + self.min_interval)
+            .filter(|allowed| *allowed > now)
+    }
+
+    /// A notification was shown at `now`.
+    pub fn shown(&mut self, now: Instant) {
+        self.last_shown = Some(now);
+    }
+}
+
+/// Client of the notification daemon.
+pub struct Notifier {
+    connection: Connection,
+    /// Id of the last notification, replaced by the next one instead of stacking.
+    last_id: u32,
+    /// Whether the daemon renders markup in the body, so it must be escaped.
+    markup: bool,
+}
+
+impl Notifier {
+    /// Connect to the session bus.
+    pub async fn connect() -> zbus::Result<Self> {
+        Self::connect_on(connection::Builder::session()?).await
+    }
+
+    /// Connect to the bus of the given connection builder.
+    pub async fn connect_on(builder: connection::Builder<'_>) -> zbus::Result<Self> {
+        let connection = builder.build().await?;
+        let reply = connection
+            .call_method(Some(BUS_NAME), OBJECT_PATH, Some(BUS_NAME), "GetCapabilities", &())
+            .await?;
+        let capabilities: Vec<String> = reply.body().deserialize()?;
+        Ok(Self {
+            connection,
+            last_id: 0,
+            markup: capabilities.iter().any(|capability| capability == "body-markup"),
+        })
+    }
+
+    /// Show `notification`, replacing the previous one.
+    pub async fn show(&mut self, notification: &Notification) -> zbus::Result<()> {
+        // The summary is always plain text, only the body may be markup
+        let body = if self.markup {
+            escape_markup(&notification.body)
+        } else {
+            notification.body.clone()
+        };
+        let hints: HashMap<&str, Value<'_>> = HashMap::from([
+            ("category", Value::from("x-gnome.music")),
+            ("transient", Value::from(true)),
+        ]);
+        let reply = self
+            .connection
+            .call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(BUS_NAME),
+                "Notify",
+                &(
+                    "Internet Radio",
+                    self.last_id,
+                    "audio-x-generic",
+                    notification.summary.as_str(),
+                    body,
+                    Vec::<&str>::new(),
+                    hints,
+                    EXPIRE_TIMEOUT,
+                ),
+            )
+            .await?;
+        self.last_id = reply.body().deserialize()?;
+        Ok(())
+    }
+}
+
+/// Show notifications as they arrive, at most one per `min_interval`.
+///
+/// Runs until the sender is dropped.
+pub async fn run(
+    mut notifier: Notifier,
+    mut notifications: UnboundedReceiver<Notification>,
+    min_interval: Duration,
+) {
+    let mut limiter = RateLimiter::new(min_interval);
+    while let Some(mut notification) = notifications.recv().await {
+        // Wait out the interval, keeping only the latest title
+        if let Some(deadline) = limiter.wait_until(Instant::now()) {
+            let sleep = tokio::time::sleep_until(deadline.into());
+            tokio::pin!(sleep);
+            loop {
+                tokio::select! {
+                    _ = &mut sleep => break,
+                    next = notifications.recv() => match next {
+                        Some(next) => notification = next,
+                        None => return,
+                    },
+                }
+            }
+        }
+
+        limiter.shown(Instant::now());
+        if let Err(e) = notifier.show(&notification).await {
+            tracing::warn!("Failed to show notification: {}", e);
+        }
+    }
+}
+
+/// Escape text for daemons that render markup.
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bus::PrivateBus;
+    use tokio::sync::mpsc;
+    use zbus::interface;
+
+    /// Records `(replaces_id, summary, body)` of every call, like a notification daemon.
+    struct StubDaemon {
+        calls: mpsc::UnboundedSender<(u32, String, String)>,
+        next_id: u32,
+        markup: bool,
+    }
+
+    #[interface(name = "org.freedesktop.Notifications")]
+    impl StubDaemon {
+        fn get_capabilities(&self) -> Vec<String> {
+            let mut capabilities = vec!["body".to_string()];
+            if self.markup {
+                capabilities.push("body-markup".to_string());
+            }
+            capabilities
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &mut self,
+            _app_name: String,
+            replaces_id: u32,
+            _app_icon: String,
+            summary: String,
+            body: String,
+            _actions: Vec<String>,
+            _hints: HashMap<String, zbus::zvariant::OwnedValue>,
+            _expire_timeout: i32,
+        ) -> u32 {
+            let _ = self.calls.send((replaces_id, summary, body));
+            if replaces_id != 0 {
+                return replaces_id;
+            }
+            self.next_id += 1;
+            self.next_id
+        }
+    }
+
+    fn notification(body: &str) -> Notification {
+        Notification {
+            summary: "SomaFM Groove Salad".to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    /// Serve a stub daemon on `bus`, returning its connection and calls.
+    async fn stub_daemon(
+        bus: &PrivateBus,
+        markup: bool,
+    ) -> (Connection, mpsc::UnboundedReceiver<(u32, String, String)>) {
+        let (calls_tx, calls_rx) = mpsc::unbounded_channel();
+        let daemon = connection::Builder::address(bus.address.as_str())
+            .unwrap()
+            .name(BUS_NAME)
+            .unwrap()
+            .serve_at(OBJECT_PATH, StubDaemon { calls: calls_tx, next_id: 0, markup })
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        (daemon, calls_rx)
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(Duration::from_secs(20));
+        assert_eq!(limiter.wait_until(start), None);
+
+        limiter.shown(start);
+        let allowed = start + Duration::from_secs(20);
+        assert_eq!(limiter.wait_until(start + Duration::from_secs(5)), Some(allowed));
+        assert_eq!(limiter.wait_until(allowed), None);
+    }
+
+    #[tokio::test]
+    async fn test_notifications_on_private_bus() {
+        let Some(bus) = PrivateBus::spawn() else {
+            eprintln!("dbus-daemon not available, skipping");
+            return;
+        };
+
+        let (_daemon, mut calls_rx) = stub_daemon(&bus, true).await;
+
+        let notifier = Notifier::connect_on(connection::Builder::address(bus.address.as_str()).unwrap())
+            .await
+            .unwrap();
+        let (notifications_tx, notifications_rx) = mpsc::unbounded_channel();
+        let interval = Duration::from_millis(300);
+        let runner = tokio::spawn(run(notifier, notifications_rx, interval));
+
+        // The first title is shown right away
+        notifications_tx.send(notification("Tycho - Awake")).unwrap();
+        let first = calls_rx.recv().await.unwrap();
+        assert_eq!(first, (0, "SomaFM Groove Salad".to_string(), "Tycho - Awake".to_string()));
+        let shown_at = Instant::now();
+
+        // A burst within the interval is shown once, with the latest title,
+        // replacing the previous notification
+        notifications_tx.send(notification("Station ID")).unwrap();
+        notifications_tx.send(notification("Boards of Canada - Olson & <Friends>")).unwrap();
+        let second = calls_rx.recv().await.unwrap();
+        assert!(shown_at.elapsed() >= interval - Duration::from_millis(50));
+        assert_eq!(second.0, 1);
+        assert_eq!(second.2, "Boards of Canada - Olson &amp; &lt;Friends&gt;");
+
+        drop(notifications_tx);
+        runner.await.unwrap();
+        assert!(calls_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_body_is_escaped_only_for_markup_daemons() {
+        let Some(bus) = PrivateBus::spawn() else {
+            eprintln!("dbus-daemon not available, skipping");
+            return;
+        };
+        let station = Notification {
+            summary: "Drum & Bass <24/7>".to_string(),
+            body: "Olson & <Friends>".to_string(),
+        };
+
+        let (daemon, mut calls_rx) = stub_daemon(&bus, false).await;
+        let mut notifier = Notifier::connect_on(connection::Builder::address(bus.address.as_str()).unwrap())
+            .await
+            .unwrap();
+        notifier.show(&station).await.unwrap();
+        let (_, summary, body) = calls_rx.recv().await.unwrap();
+        assert_eq!((summary.as_str(), body.as_str()), ("Drum & Bass <24/7>", "Olson & <Friends>"));
+        drop(daemon);
+
+        let (_daemon, mut calls_rx) = stub_daemon(&bus, true).await;
+        let mut notifier = Notifier::connect_on(connection::Builder::address(bus.address.as_str()).unwrap())
+            .await
+            .unwrap();
+        notifier.show(&station).await.unwrap();
+        let (_, summary, body) = calls_rx.recv().await.unwrap();
+        assert_eq!(summary, "Drum & Bass <24/7>");
+        assert_eq!(body, "Olson &amp; &lt;Friends&gt;");
+    }
+}
@@ -1,60 +1,258 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use gstreamer::prelude::*;
-use gstreamer::{Pipeline, State};
+use crate::recorder::{IcyDemuxer, StreamItem, TappedStream, extension_for_content_type};
+use gstreamer::{Element, ElementFactory, EventView, Pad, PadProbeData, PadProbeReturn, PadProbeType, Pipeline, State};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use thiserror::Error;
+use tokio::sync::mpsc::{self, UnboundedSender};
 
 #[derive(Debug, Error)]
 pub enum PlayerError {
     #[error("Failed to create element: {0}")]
     CreateElement(#[from] gstreamer::glib::BoolError),
-    #[error("Element is not a pipeline")]
-    NotAPipeline,
+    #[error("Failed to link elements")]
+    Link,
     #[error("State change failed")]
     StateChange,
+    #[error("No stream to play")]
+    NoStream,
 }
 
+/// Size of the ring buffer that keeps downloading live audio while paused.
+///
+/// About half an hour of a 128 kbps stream. Once full, the oldest audio is
+/// dropped so the connection is never stalled.
+const TIMESHIFT_BUFFER_BYTES: u32 = 32 * 1024 * 1024;
+
+/// Playback this close to the live stream counts as live.
+///
+/// Servers send a few seconds of audio ahead when connecting, which stays
+/// in the buffer.
+pub const LIVE_MARGIN: Duration = Duration::from_secs(5);
+
 /// A wrapper around a GStreamer pipeline for audio playback.
+///
+/// The stream is split off for recording as received, then parsed but not
+/// decoded before it goes through the timeshift buffer:
+///
+/// ```text
+/// urisourcebin ! tee ! parsebin ! queue (timeshift) ! decodebin ! audioconvert ! audioresample ! volume ! sink
+///                tee ! fakesink (tap)
+/// ```
+///
+/// Pausing only blocks the part after the buffer, so the source keeps
+/// downloading into it.
 pub struct Player {
     pipeline: Pipeline,
+    /// Receives the stream of every source.
+    tee: Element,
+    /// Branch of the tee feeding the parser.
+    tee_parse: Pad,
+    /// Ring buffer of compressed audio between the source and the decoder.
+    timeshift: Element,
+    volume: Element,
+    /// Source and parser of the current stream, replaced for every stream.
+    source: Option<(Element, Element)>,
+    /// Set by [`Player::jump_to_live`] until the buffer has been skipped.
+    skip_to_live: Arc<AtomicBool>,
+    tap: StreamTap,
+    /// Stream URIs to try in order for the current channel.
+    candidates: Vec<String>,
+    /// Index of the candidate currently playing.
+    candidate_idx: usize,
 }
 
 impl Player {
-    /// Create a new Player instance.
-    ///
-    /// This initializes a `playbin3` pipeline.
+    /// Create a new Player instance playing to the default audio output.
     pub fn new() -> Result<Self, PlayerError> {
-        // Create a playbin3 element
-        let playbin = gstreamer::ElementFactory::make("playbin3")
-            .build()
-            .map_err(PlayerError::CreateElement)?;
+        let sink = ElementFactory::make("autoaudiosink").build()?;
+        Self::with_audio_sink(sink)
+    }
+
+    fn with_audio_sink(sink: Element) -> Result<Self, PlayerError> {
+        let pipeline = Pipeline::new();
+        let tee = ElementFactory::make("tee")
+            .property("allow-not-linked", true)
+            .build()?;
+        // Never prerolls, the tap drops everything before it
+        let tap_sink = ElementFactory::make("fakesink")
+            .property("sync", false)
+            .property("async", false)
+            .build()?;
+        let timeshift = ElementFactory::make("queue")
+            .name("timeshift")
+            .property("max-size-bytes", TIMESHIFT_BUFFER_BYTES)
+            .property("max-size-buffers", 0u32)
+            .property("max-size-time", 0u64)
+            .property_from_str("leaky", "downstream")
+            .build()?;
+        let decoder = ElementFactory::make("decodebin").build()?;
+        let convert = ElementFactory::make("audioconvert").build()?;
+        let resample = ElementFactory::make("audioresample").build()?;
+        let volume = ElementFactory::make("volume").build()?;
+
+        pipeline.add_many([&tee, &tap_sink, &timeshift, &decoder, &convert, &resample, &volume, &sink])?;
+        Element::link_many([&tee, &tap_sink])?;
+        Element::link_many([&timeshift, &decoder])?;
+        Element::link_many([&convert, &resample, &volume, &sink])?;
+        let tee_parse = tee.request_pad_simple("src_%u").expect("tee has no src pads");
+
+        let convert_sink = convert.static_pad("sink").expect("audioconvert has no sink pad");
+        decoder.connect_pad_added(move |_, pad| {
+            link_audio_pad(pad, &convert_sink);
+        });
 
-        let pipeline = playbin
-            .downcast::<Pipeline>()
-            .map_err(|_| PlayerError::NotAPipeline)?;
+        let skip_to_live = Arc::new(AtomicBool::new(false));
+        add_skip_probe(&timeshift, skip_to_live.clone());
 
-        Ok(Self { pipeline })
+        let tap = StreamTap::default();
+        let tap_src = tap_sink
+            .static_pad("sink")
+            .and_then(|pad| pad.peer())
+            .expect("tap is not linked");
+        tap.add_probe(&tap_src);
+
+        Ok(Self {
+            pipeline,
+            tee,
+            tee_parse,
+            timeshift,
+            volume,
+            source: None,
+            skip_to_live,
+            tap,
+            candidates: Vec::new(),
+            candidate_idx: 0,
+        })
     }
 
     /// Start playback of the given URI.
-    pub fn play(&self, uri: &str) -> Result<(), PlayerError> {
+    pub fn play(&mut self, uri: &str) -> Result<(), PlayerError> {
         self.pipeline
             .set_state(State::Null)
             .map_err(|_| PlayerError::StateChange)?;
-        self.pipeline.set_property("uri", uri);
+        if let Some((source, parser)) = self.source.take() {
+            self.pipeline.remove_many([&source, &parser])?;
+        }
+        self.skip_to_live.store(false, Ordering::SeqCst);
+        self.timeshift_src().set_offset(0);
+
+        let source = ElementFactory::make("urisourcebin")
+            .property("uri", uri)
+            .build()?;
+        let parser = ElementFactory::make("parsebin").build()?;
+        self.pipeline.add_many([&source, &parser])?;
+
+        let tee_sink = self.tee.static_pad("sink").expect("tee has no sink pad");
+        source.connect_pad_added(move |_, pad| {
+            if tee_sink.is_linked() {
+                return;
+            }
+            if let Err(e) = pad.link(&tee_sink) {
+                tracing::warn!("Failed to link stream source: {:?}", e);
+            }
+        });
+        let parser_sink = parser.static_pad("sink").expect("parsebin has no sink pad");
+        self.tee_parse
+            .link(&parser_sink)
+            .map_err(|_| PlayerError::Link)?;
+        let timeshift_sink = self.timeshift.static_pad("sink").expect("queue has no sink pad");
+        parser.connect_pad_added(move |_, pad| {
+            link_audio_pad(pad, &timeshift_sink);
+        });
+        self.source = Some((source, parser));
+
+        self.pipeline
+            .set_state(State::Playing)
+            .map_err(|_| PlayerError::StateChange)?;
+        Ok(())
+    }
+
+    /// Start playback of the first of several stream URIs.
+    ///
+    /// The others are kept for [`Player::play_next_candidate`].
+    pub fn play_candidates(&mut self, uris: Vec<String>) -> Result<(), PlayerError> {
+        let first = uris.first().ok_or(PlayerError::NoStream)?.clone();
+        self.candidates = uris;
+        self.candidate_idx = 0;
+        self.play(&first)
+    }
+
+    /// Fail over to the next stream URI.
+    ///
+    /// Returns `false` if all candidates have been tried.
+    pub fn play_next_candidate(&mut self) -> Result<bool, PlayerError> {
+        let Some(uri) = self.candidates.get(self.candidate_idx + 1).cloned() else {
+            return Ok(false);
+        };
+        self.candidate_idx += 1;
+        tracing::info!("Failing over to {}", uri);
+        self.play(&uri)?;
+        Ok(true)
+    }
+
+    /// The stream URI currently playing, if any.
+    pub fn current_uri(&self) -> Option<&str> {
+        self.candidates.get(self.candidate_idx).map(String::as_str)
+    }
+
+    /// Pause playback while the stream keeps downloading into the
+    /// timeshift buffer.
+    pub fn pause(&self) -> Result<(), PlayerError> {
+        self.pipeline
+            .set_state(State::Paused)
+            .map_err(|_| PlayerError::StateChange)?;
+        Ok(())
+    }
+
+    /// Resume playback from where it was paused.
+    pub fn resume(&self) -> Result<(), PlayerError> {
         self.pipeline
             .set_state(State::Playing)
             .map_err(|_| PlayerError::StateChange)?;
         Ok(())
     }
 
-    /// Stop playback.
-    pub fn stop(&self) -> Result<(), PlayerError> {
+    /// How far playback is behind the live stream, i.e. how much audio is
+    /// waiting in the timeshift buffer.
+    pub fn behind_live(&self) -> Duration {
+        buffered_time(&self.timeshift)
+    }
+
+    /// Skip the buffered audio and continue with the live stream, resuming
+    /// if paused. The connection is kept.
+    pub fn jump_to_live(&self) -> Result<(), PlayerError> {
+        if self.current_uri().is_none() {
+            return Err(PlayerError::NoStream);
+        }
+        self.skip_to_live.store(true, Ordering::SeqCst);
+        self.resume()
+    }
+
+    /// Stop playback and forget the stream URIs of the channel, so a later
+    /// failure can't fail over to them.
+    pub fn stop(&mut self) -> Result<(), PlayerError> {
+        self.candidates.clear();
+        self.candidate_idx = 0;
         self.pipeline
             .set_state(State::Null)
             .map_err(|_| PlayerError::StateChange)?;
         Ok(())
     }
+
+    /// Set the playback volume, from 0.0 (silent) to 1.0 (full volume).
+    pub fn set_volume(&self, volume: f64) {
+        self.volume.set_property("volume", volume.clamp(0.0, 1.0));
+    }
+
+    /// Mute or unmute playback without changing the volume.
+    pub fn set_muted(&self, muted: bool) {
+        self.volume.set_property("mute", muted);
+    }
+
     pub fn bus(&self) -> gstreamer::Bus {
         self.pipeline.bus().expect("Pipeline has no bus")
     }
@@ -62,6 +260,16 @@ impl Player {
     pub fn pipeline(&self) -> &Pipeline {
         &self.pipeline
     }
+
+    /// Handle for recording the stream as received, kept across
+    /// reconnects and channel changes.
+    pub fn tap(&self) -> StreamTap {
+        self.tap.clone()
+    }
+
+    fn timeshift_src(&self) -> Pad {
+        self.timeshift.static_pad("src").expect("queue has no src pad")
+    }
 }
 
 impl Drop for Player {
@@ -69,3 +277,505 @@ impl Drop for Player {
         let _ = self.pipeline.set_state(State::Null);
     }
 }
+
+/// Link `pad` to `sink` if it carries audio and `sink` is still free.
+fn link_audio_pad(pad: &Pad, sink: &Pad) {
+    let is_audio = pad
+        .current_caps()
+        .and_then(|caps| caps.structure(0).map(|s| s.name().starts_with("audio/")))
+        .unwrap_or(false);
+    if !is_audio || sink.is_linked() {
+        return;
+    }
+    if let Err(e) = pad.link(sink) {
+        tracing::warn!("Failed to link audio stream: {:?}", e);
+    }
+}
+
+/// Hands the stream the player receives to a recorder, so recordings are
+/// of the same connection that is heard.
+#[derive(Debug, Clone, Default)]
+pub struct StreamTap(Arc<Mutex<TapState>>);
+
+#[derive(Debug)]
+struct TapState {
+    /// Undoes the ICY metadata interleaving of the current connection,
+    /// even while nothing is attached, to stay in step with it.
+    demuxer: IcyDemuxer,
+    has_metadata: bool,
+    extension: &'static str,
+    sender: Option<UnboundedSender<StreamItem>>,
+}
+
+impl Default for TapState {
+    fn default() -> Self {
+        Self {
+            demuxer: IcyDemuxer::new(None),
+            has_metadata: false,
+            extension: "audio",
+            sender: None,
+        }
+    }
+}
+
+impl StreamTap {
+    /// Start receiving the stream from here on. Only one receiver is
+    /// attached at a time, attaching another ends the previous stream.
+    pub fn attach(&self) -> TappedStream {
+        let (sender, items) = mpsc::unbounded_channel();
+        let mut state = self.0.lock().unwrap();
+        state.sender = Some(sender);
+        TappedStream {
+            extension: state.extension,
+            has_metadata: state.has_metadata,
+            items,
+        }
+    }
+
+    /// Pass what goes through `pad` to the attached receiver and drop it.
+    fn add_probe(&self, pad: &Pad) {
+        let tap = self.0.clone();
+        pad.add_probe(PadProbeType::BUFFER | PadProbeType::EVENT_DOWNSTREAM, move |_, info| {
+            let mut state = tap.lock().unwrap();
+            match &info.data {
+                Some(PadProbeData::Buffer(buffer)) => {
+                    let Ok(map) = buffer.map_readable() else {
+                        return PadProbeReturn::Drop;
+                    };
+                    let items = state.demuxer.push(&map);
+                    let detached = state
+                        .sender
+                        .as_ref()
+                        .is_some_and(|sender| items.into_iter().any(|item| sender.send(item).is_err()));
+                    if detached {
+                        state.sender = None;
+                    }
+                    PadProbeReturn::Drop
+                }
+                Some(PadProbeData::Event(event)) => {
+                    match event.view() {
+                        // A new connection
+                        EventView::Caps(caps) => {
+                            let Some(structure) = caps.caps().structure(0) else {
+                                return PadProbeReturn::Ok;
+                            };
+                            let metaint = Some(structure)
+                                .filter(|s| s.name().as_str() == "application/x-icy")
+                                .and_then(|s| s.get::<i32>("metadata-interval").ok())
+                                .and_then(|metaint| usize::try_from(metaint).ok())
+                                .filter(|metaint| *metaint > 0);
+                            state.demuxer = IcyDemuxer::new(metaint);
+                            state.has_metadata = metaint.is_some();
+                            // Sources without HTTP headers may name the content type here
+                            let extension = extension_for_content_type(structure.name());
+                            if extension != "audio" {
+                                state.extension = extension;
+                            }
+                        }
+                        // Response headers of HTTP sources
+                        EventView::CustomDownstreamSticky(custom) => {
+                            let content_type = custom
+                                .structure()
+                                .filter(|s| s.name().as_str() == "http-headers")
+                                .and_then(|s| s.get::<gstreamer::Structure>("response-headers").ok())
+                                .and_then(|headers| {
+                                    headers
+                                        .iter()
+                                        .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+                                        .and_then(|(_, value)| value.get::<String>().ok())
+                                });
+                            if let Some(content_type) = content_type {
+                                state.extension = extension_for_content_type(&content_type);
+                            }
+                        }
+                        _ => {}
+                    }
+                    PadProbeReturn::Ok
+                }
+                _ => PadProbeReturn::Ok,
+            }
+        });
+    }
+}
+
+/// Duration of the audio waiting in `queue`.
+fn buffered_time(queue: &Element) -> Duration {
+    Duration::from_nanos(queue.property::<u64>("current-level-time"))
+}
+
+/// While `skip` is set, drop what comes out of the timeshift `queue` until
+/// it is empty.
+///
+/// The live audio after the skipped part is timestamped later than the
+/// pipeline expects, so the skipped duration is taken off the running time
+/// of everything that follows.
+fn add_skip_probe(queue: &Element, skip: Arc<AtomicBool>) {
+    let queue_weak = queue.downgrade();
+    let skipped = AtomicU64::new(0);
+    let src = queue.static_pad("src").expect("queue has no src pad");
+    src.add_probe(PadProbeType::BUFFER, move |pad, info| {
+        if !skip.load(Ordering::SeqCst) {
+            return PadProbeReturn::Ok;
+        }
+        let Some(queue) = queue_weak.upgrade() else {
+            return PadProbeReturn::Ok;
+        };
+        if let Some(PadProbeData::Buffer(buffer)) = &info.data {
+            let duration = buffer.duration().map_or(0, |d| d.nseconds());
+            skipped.fetch_add(duration, Ordering::SeqCst);
+        }
+        if queue.property::<u32>("current-level-buffers") == 0 {
+            skip.store(false, Ordering::SeqCst);
+            let skipped = skipped.swap(0, Ordering::SeqCst) as i64;
+            pad.set_offset(pad.offset() - skipped);
+        }
+        PadProbeReturn::Drop
+    });
+}
+
+/// How to reconnect after a stream error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Number of attempts before giving up.
+    pub max_attempts: u32,
+    /// Delay before the first attempt, doubled for each following attempt.
+    pub initial_delay: Duration,
+    /// Upper bound for the delay between attempts.
+    pub max_delay: Duration,
+    /// How long a stream must play before a later error starts a fresh series of attempts.
+    pub stable_after: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            stable_after: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before the given attempt (starting at 1), or `None` once the policy gives up.
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        if attempt == 0 || attempt > self.max_attempts {
+            return None;
+        }
+        let factor = 2u32.saturating_pow(attempt - 1);
+        Some(self.initial_delay.saturating_mul(factor).min(self.max_delay))
+    }
+}
+
+/// Tracks reconnect attempts for the current stream.
+#[derive(Debug, Clone, Default)]
+pub struct Reconnector {
+    policy: ReconnectPolicy,
+    attempt: u32,
+    playing_since: Option<Instant>,
+}
+
+impl Reconnector {
+    pub fn new(policy: ReconnectPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// The stream started playing at `now`.
+    pub fn connected(&mut self, now: Instant) {
+        self.playing_since.get_or_insert(now);
+    }
+
+    /// The stream failed at `now`.
+    ///
+    /// Returns the delay before the next attempt, or `None` to give up.
+    pub fn failed(&mut self, now: Instant) -> Option<Duration> {
+        // A stream that played for a while is a new failure, not a retry
+        if let Some(since) = self.playing_since.take() {
+            if now.duration_since(since) >= self.policy.stable_after {
+                self.attempt = 0;
+            }
+        }
+        self.attempt += 1;
+        self.policy.delay(self.attempt)
+    }
+
+    /// Forget all previous attempts, e.g. when the user picks a station.
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+        self.playing_since = None;
+    }
+
+    /// The current attempt, starting at 1.
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    pub fn policy(&self) -> &ReconnectPolicy {
+        &self.policy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::TestServer;
+    use std::io::Write;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_reconnect_backoff() {
+        let policy = ReconnectPolicy {
+            max_attempts: 4,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+            stable_after: Duration::from_secs(30),
+        };
+        let mut reconnector = Reconnector::new(policy);
+        let start = Instant::now();
+
+        assert_eq!(reconnector.failed(start), Some(Duration::from_secs(1)));
+        assert_eq!(reconnector.failed(start), Some(Duration::from_secs(2)));
+        assert_eq!(reconnector.failed(start), Some(Duration::from_secs(4)));
+        assert_eq!(reconnector.failed(start), Some(Duration::from_secs(5)));
+        assert_eq!(reconnector.failed(start), None);
+
+        // Playing only briefly does not reset the attempts
+        let mut reconnector = Reconnector::new(policy);
+        reconnector.failed(start);
+        reconnector.connected(start);
+        assert_eq!(reconnector.failed(start + Duration::from_secs(1)), Some(Duration::from_secs(2)));
+
+        // Playing long enough does
+        reconnector.connected(start);
+        assert_eq!(reconnector.failed(start + Duration::from_secs(60)), Some(Duration::from_secs(1)));
+        assert_eq!(reconnector.attempt(), 1);
+    }
+
+    /// Play `uri` until the bus reports the stream dropped, telling
+    /// `reconnector` when it started playing at the virtual time `now`.
+    fn play_until_dropped(player: &mut Player, uri: &str, reconnector: &mut Reconnector, now: Instant) -> bool {
+        player.play(uri).unwrap();
+        let bus = player.bus();
+        let dropped = loop {
+            let Some(message) = bus.timed_pop(gstreamer::ClockTime::from_seconds(10)) else {
+                break false;
+            };
+            match message.view() {
+                gstreamer::MessageView::StateChanged(change)
+                    if change.src() == Some(player.pipeline().upcast_ref::<gstreamer::Object>())
+                        && change.current() == State::Playing =>
+                {
+                    reconnector.connected(now);
+                }
+                gstreamer::MessageView::Error(_) | gstreamer::MessageView::Eos(_) => break true,
+                _ => {}
+            }
+        };
+        player.stop().unwrap();
+        dropped
+    }
+
+    #[test]
+    fn test_dropped_connection_is_retried_then_given_up() {
+        if gstreamer::init().is_err() {
+            eprintln!("GStreamer not available, skipping");
+            return;
+        }
+        let Ok(mut player) = Player::new() else {
+            eprintln!("Playback elements not available, skipping");
+            return;
+        };
+
+        // Send the start of a stream, then cut the connection, every time
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        let server = TestServer::spawn(move |_, stream| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let _ = stream.write_all(b"HTTP/1.0 200 OK\r\nContent-Type: audio/mpeg\r\n\r\n");
+            let _ = stream.write_all(&[0u8; 4096]);
+        });
+        let uri = server.url("/stream.mp3");
+
+        // Reconnect like the app does, on a virtual clock so the test
+        // doesn't wait out the delays
+        let policy = ReconnectPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            stable_after: Duration::from_secs(30),
+        };
+        let mut reconnector = Reconnector::new(policy);
+        let mut now = Instant::now();
+        let mut delays = Vec::new();
+        loop {
+            assert!(
+                play_until_dropped(&mut player, &uri, &mut reconnector, now),
+                "dropped stream was not reported"
+            );
+            now += Duration::from_secs(1);
+            match reconnector.failed(now) {
+                Some(delay) => {
+                    delays.push(delay);
+                    now += delay;
+                }
+                None => break,
+            }
+        }
+
+        assert_eq!(delays, [1, 2, 4].map(Duration::from_secs));
+        assert_eq!(reconnector.attempt(), 4);
+        assert_eq!(connections.load(Ordering::SeqCst), 4);
+
+        // A station picked again starts a fresh series of attempts
+        reconnector.reset();
+        assert!(play_until_dropped(&mut player, &uri, &mut reconnector, now));
+        assert_eq!(reconnector.failed(now), Some(Duration::from_secs(1)));
+    }
+
+    /// A silent MPEG-1 Layer III frame: 128 kbps, 44.1 kHz, mono.
+    fn silent_mp3_frame() -> Vec<u8> {
+        let mut frame = vec![0u8; 417];
+        frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0xC0]);
+        frame
+    }
+
+    /// Wait for the pipeline to reach `state`, or fail on an error.
+    fn wait_for_state(player: &Player, state: State) {
+        let bus = player.bus();
+        loop {
+            let message = bus
+                .timed_pop(gstreamer::ClockTime::from_seconds(10))
+                .expect("timed out waiting for the pipeline");
+            match message.view() {
+                gstreamer::MessageView::StateChanged(change)
+                    if change.src() == Some(player.pipeline().upcast_ref::<gstreamer::Object>())
+                        && change.current() == state =>
+                {
+                    return;
+                }
+                gstreamer::MessageView::Error(err) => panic!("playback failed: {}", err.error()),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_paused_stream_keeps_downloading() {
+        if gstreamer::init().is_err() {
+            eprintln!("GStreamer not available, skipping");
+            return;
+        }
+        let have_decoder = ["mpg123audiodec", "avdec_mp3"]
+            .iter()
+            .any(|name| gstreamer::ElementFactory::find(name).is_some());
+        if gstreamer::ElementFactory::find("mpegaudioparse").is_none() || !have_decoder {
+            eprintln!("No MP3 support, skipping");
+            return;
+        }
+        let sink = gstreamer::ElementFactory::make("fakesink")
+            .property("sync", true)
+            .build()
+            .unwrap();
+        let mut player = Player::with_audio_sink(sink).unwrap();
+
+        // A live stream, sent no faster than it plays
+        let frame_duration = Duration::from_nanos(1_000_000_000 * 1152 / 44100);
+        let server = TestServer::spawn(move |_, stream| {
+            let _ = stream.write_all(b"HTTP/1.0 200 OK\r\nContent-Type: audio/mpeg\r\n\r\n");
+            let frame = silent_mp3_frame();
+            while stream.write_all(&frame).is_ok() {
+                std::thread::sleep(frame_duration);
+            }
+        });
+
+        player.play_candidates(vec![server.url("/stream.mp3")]).unwrap();
+        wait_for_state(&player, State::Playing);
+        std::thread::sleep(Duration::from_secs(1));
+        assert!(player.behind_live() < Duration::from_secs(1));
+
+        // Audio keeps arriving into the buffer while paused
+        player.pause().unwrap();
+        std::thread::sleep(Duration::from_secs(3));
+        let behind = player.behind_live();
+        assert!(behind >= Duration::from_secs(2), "only {:?} buffered while paused", behind);
+
+        // and plays from there after resuming
+        player.resume().unwrap();
+        wait_for_state(&player, State::Playing);
+        std::thread::sleep(Duration::from_secs(1));
+        assert!(player.behind_live() >= Duration::from_secs(2));
+
+        // Jumping to live skips the buffer on the same connection
+        player.jump_to_live().unwrap();
+        std::thread::sleep(Duration::from_secs(1));
+        assert!(player.behind_live() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_tap_receives_the_playing_stream() {
+        if gstreamer::init().is_err() {
+            eprintln!("GStreamer not available, skipping");
+            return;
+        }
+        let have_decoder = ["mpg123audiodec", "avdec_mp3"]
+            .iter()
+            .any(|name| gstreamer::ElementFactory::find(name).is_some());
+        if gstreamer::ElementFactory::find("mpegaudioparse").is_none() || !have_decoder {
+            eprintln!("No MP3 support, skipping");
+            return;
+        }
+        let sink = gstreamer::ElementFactory::make("fakesink")
+            .property("sync", true)
+            .build()
+            .unwrap();
+        let mut player = Player::with_audio_sink(sink).unwrap();
+
+        // An Icecast-like stream with a title after every frame
+        let frame_duration = Duration::from_nanos(1_000_000_000 * 1152 / 44100);
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        let server = TestServer::spawn(move |request, stream| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(request.header("icy-metadata"), Some("1"));
+            let _ = stream.write_all(b"HTTP/1.0 200 OK\r\nContent-Type: audio/mpeg\r\nicy-metaint: 417\r\n\r\n");
+            let mut metadata = b"StreamTitle='Artist - Title';".to_vec();
+            metadata.resize(32, 0);
+            let frame = silent_mp3_frame();
+            loop {
+                let mut chunk = frame.clone();
+                chunk.push(2);
+                chunk.extend_from_slice(&metadata);
+                if stream.write_all(&chunk).is_err() {
+                    break;
+                }
+                std::thread::sleep(frame_duration);
+            }
+        });
+
+        player.play_candidates(vec![server.url("/stream.mp3")]).unwrap();
+        wait_for_state(&player, State::Playing);
+        let mut tapped = player.tap().attach();
+        assert_eq!(tapped.extension, "mp3");
+        assert!(tapped.has_metadata);
+
+        let mut audio = Vec::new();
+        let mut titles = Vec::new();
+        while titles.len() < 3 {
+            match tapped.items.blocking_recv().expect("tap ended") {
+                StreamItem::Audio(bytes) => audio.extend(bytes),
+                StreamItem::Metadata(title) => titles.push(title),
+            }
+        }
+        // Whole frames without the metadata, from the one connection played
+        assert!(audio.len() >= 2 * 417);
+        assert!(audio.windows(4).any(|bytes| bytes == &silent_mp3_frame()[..4]));
+        assert!(!audio.windows(11).any(|bytes| bytes == b"StreamTitle"));
+        assert!(titles.iter().all(|title| title.as_deref() == Some("Artist - Title")));
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+}
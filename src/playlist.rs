@@ -0,0 +1,465 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Resolves playlist URLs (.pls, .m3u, .m3u8, .xspf, .asx) to stream URLs.
+
+use std::collections::HashMap;
+use std::time::Duration;
+use thiserror::Error;
+use url::Url;
+
+/// Largest playlist that will be downloaded.
+const MAX_PLAYLIST_BYTES: usize = 256 * 1024;
+
+/// How many levels of playlists pointing at other playlists are followed.
+const MAX_DEPTH: usize = 3;
+
+#[derive(Error, Debug)]
+pub enum PlaylistError {
+    #[error("Failed to download playlist: {0}")]
+    Download(#[from] reqwest::Error),
+    #[error("Failed to parse playlist: {0}")]
+    Parse(#[from] roxmltree::Error),
+    #[error("Playlist is larger than {} bytes", MAX_PLAYLIST_BYTES)]
+    TooLarge,
+    #[error("Playlist contains no streams")]
+    Empty,
+}
+
+/// Supported playlist formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    Pls,
+    /// M3U and M3U8, including extended M3U.
+    M3u,
+    Xspf,
+    Asx,
+}
+
+impl PlaylistFormat {
+    /// Guess the format from the file extension of a URL or path.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        let (_, extension) = file_name.rsplit_once('.')?;
+        match extension.to_lowercase().as_str() {
+            "pls" => Some(Self::Pls),
+            "m3u" | "m3u8" => Some(Self::M3u),
+            "xspf" => Some(Self::Xspf),
+            "asx" | "wax" | "wvx" => Some(Self::Asx),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from a `Content-Type` header value.
+    fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+        match mime.as_str() {
+            "audio/x-scpls" | "audio/scpls" => Some(Self::Pls),
+            "audio/x-mpegurl" | "audio/mpegurl" | "application/x-mpegurl"
+            | "application/vnd.apple.mpegurl" => Some(Self::M3u),
+            "application/xspf+xml" => Some(Self::Xspf),
+            "video/x-ms-asf" | "audio/x-ms-wax" | "video/x-ms-wvx" => Some(Self::Asx),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from the start of the content.
+    pub fn sniff(content: &str) -> Option<Self> {
+        let start: String = content.trim_start().chars().take(512).collect::<String>().to_lowercase();
+        if start.starts_with("[playlist]") {
+            Some(Self::Pls)
+        } else if start.starts_with("#extm3u") {
+            Some(Self::M3u)
+        } else if start.contains("<asx") {
+            Some(Self::Asx)
+        } else if start.contains("<playlist") {
+            Some(Self::Xspf)
+        } else {
+            None
+        }
+    }
+}
+
+/// One entry of a playlist, as written in the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistEntry {
+    /// URL of the entry, possibly relative.
+    pub location: String,
+    /// Title given by the playlist, if any.
+    pub title: Option<String>,
+}
+
+impl PlaylistEntry {
+    fn new(location: impl Into<String>, title: Option<&str>) -> Self {
+        Self {
+            location: location.into(),
+            title: title.map(str::trim).filter(|title| !title.is_empty()).map(str::to_string),
+        }
+    }
+}
+
+/// Parse playlist content into its entries, in order.
+pub fn parse_entries(format: PlaylistFormat, content: &str) -> Result<Vec<PlaylistEntry>, PlaylistError> {
+    Ok(match format {
+        PlaylistFormat::Pls => parse_pls(content),
+        PlaylistFormat::M3u => parse_m3u(content),
+        PlaylistFormat::Xspf => parse_xspf(content)?,
+        PlaylistFormat::Asx => parse_asx(content),
+    })
+}
+
+/// Parse playlist content into an ordered list of stream URLs.
+///
+/// Relative entries are resolved against `base`, the URL of the playlist.
+pub fn parse(
+    format: PlaylistFormat,
+    content: &str,
+    base: Option<&Url>,
+) -> Result<Vec<String>, PlaylistError> {
+    let mut streams: Vec<String> = Vec::new();
+    for PlaylistEntry { location: entry, .. } in parse_entries(format, content)? {
+        let resolved = match (Url::parse(&entry), base) {
+            (Ok(url), _) => url.to_string(),
+            (Err(_), Some(base)) => match base.join(&entry) {
+                Ok(url) => url.to_string(),
+                Err(_) => continue,
+            },
+            (Err(_), None) => continue,
+        };
+        if !streams.contains(&resolved) {
+            streams.push(resolved);
+        }
+    }
+
+    Ok(streams)
+}
+
+/// Whether M3U content is an HLS playlist, which GStreamer plays directly.
+fn is_hls(content: &str) -> bool {
+    content.lines().any(|line| line.trim_start().starts_with("#EXT-X-"))
+}
+
+fn parse_pls(content: &str) -> Vec<PlaylistEntry> {
+    let mut files: Vec<(u32, String)> = Vec::new();
+    let mut titles: HashMap<u32, String> = HashMap::new();
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim().to_string();
+        if let Some(number) = key.strip_prefix("file").and_then(|n| n.parse().ok()) {
+            if !value.is_empty() {
+                files.push((number, value));
+            }
+        } else if let Some(number) = key.strip_prefix("title").and_then(|n| n.parse().ok()) {
+            titles.insert(number, value);
+        }
+    }
+    files.sort_by_key(|(number, _)| *number);
+    files
+        .into_iter()
+        .map(|(number, file)| PlaylistEntry::new(file, titles.get(&number).map(String::as_str)))
+        .collect()
+}
+
+fn parse_m3u(content: &str) -> Vec<PlaylistEntry> {
+    let mut entries = Vec::new();
+    let mut title = None;
+    for line in content.lines().map(|line| line.trim().trim_start_matches('\u{feff}')) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            title = extinf_title(info);
+        } else if !line.is_empty() && !line.starts_with('#') {
+            entries.push(PlaylistEntry::new(line, title.take()));
+        }
+    }
+    entries
+}
+
+/// The title of an `#EXTINF:` line: whatever follows the first comma
+/// outside of quoted attributes, e.g. `-1 tvg-logo="a,b.png",FIP`.
+fn extinf_title(info: &str) -> Option<&str> {
+    let mut quoted = false;
+    for (i, c) in info.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => return Some(&info[i + 1..]),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_xspf(content: &str) -> Result<Vec<PlaylistEntry>, PlaylistError> {
+    let document = roxmltree::Document::parse(content)?;
+    Ok(document
+        .descendants()
+        .filter(|node| node.has_tag_name("track"))
+        .flat_map(|track| {
+            let title = track
+                .children()
+                .find(|node| node.has_tag_name("title"))
+                .and_then(|node| node.text());
+            track
+                .children()
+                .filter(|node| node.has_tag_name("location"))
+                .filter_map(|node| node.text())
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .map(move |text| PlaylistEntry::new(text, title))
+                .collect::<Vec<_>>()
+        })
+        .collect())
+}
+
+/// ASX files are often not well-formed XML, so `<ref href="...">` is scanned for directly.
+fn parse_asx(content: &str) -> Vec<PlaylistEntry> {
+    // ASCII only, so byte offsets found in `lower` are valid in `content`
+    let lower = content.to_ascii_lowercase();
+    let mut entries = Vec::new();
+    let mut offset = 0;
+
+    while let Some(start) = lower[offset..].find("<ref") {
+        let tag_start = offset + start;
+        let Some(tag_len) = lower[tag_start..].find('>') else {
+            break;
+        };
+        let tag_end = tag_start + tag_len;
+        offset = tag_end;
+
+        let Some(href) = lower[tag_start..tag_end].find("href") else {
+            continue;
+        };
+        let rest = &content[tag_start + href + 4..tag_end];
+        let Some(rest) = rest.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            continue;
+        };
+        if let Some((value, _)) = rest[1..].split_once(quote) {
+            entries.push(PlaylistEntry::new(value.trim().replace("&amp;", "&"), None));
+        }
+    }
+
+    entries
+}
+
+/// Resolve a channel URL to the stream URLs to try, in order.
+///
+/// URLs without a playlist extension are returned unchanged. Playlists are
+/// downloaded and parsed, following nested playlists.
+pub async fn resolve(url: &str) -> Result<Vec<String>, PlaylistError> {
+    if PlaylistFormat::from_url(url).is_none() {
+        return Ok(vec![url.to_string()]);
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let streams = resolve_with(&client, url, 0).await?;
+    if streams.is_empty() {
+        return Err(PlaylistError::Empty);
+    }
+    Ok(streams)
+}
+
+/// Resolve several channel URLs in order and concatenate their streams.
+///
+/// URLs that fail to resolve are skipped unless all of them fail.
+pub async fn resolve_all(urls: &[String]) -> Result<Vec<String>, PlaylistError> {
+    let mut streams: Vec<String> = Vec::new();
+    let mut last_error = None;
+
+    for url in urls {
+        match resolve(url).await {
+            Ok(resolved) => {
+                for stream in resolved {
+                    if !streams.contains(&stream) {
+                        streams.push(stream);
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to resolve {}: {}", url, e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    match last_error {
+        Some(e) if streams.is_empty() => Err(e),
+        _ if streams.is_empty() => Err(PlaylistError::Empty),
+        _ => Ok(streams),
+    }
+}
+
+async fn resolve_with(
+    client: &reqwest::Client,
+    url: &str,
+    depth: usize,
+) -> Result<Vec<String>, PlaylistError> {
+    let Some(url_format) = PlaylistFormat::from_url(url) else {
+        return Ok(vec![url.to_string()]);
+    };
+    if depth >= MAX_DEPTH {
+        tracing::warn!("Not following deeply nested playlist {}", url);
+        return Ok(Vec::new());
+    }
+
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+
+    // Some servers serve the stream itself under a playlist name
+    if content_type.starts_with("audio/") && PlaylistFormat::from_content_type(&content_type).is_none() {
+        return Ok(vec![url.to_string()]);
+    }
+
+    let base = response.url().clone();
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        body.extend_from_slice(&chunk);
+        if body.len() > MAX_PLAYLIST_BYTES {
+            return Err(PlaylistError::TooLarge);
+        }
+    }
+    let content = String::from_utf8_lossy(&body);
+
+    let format = PlaylistFormat::sniff(&content)
+        .or_else(|| PlaylistFormat::from_content_type(&content_type))
+        .unwrap_or(url_format);
+    if format == PlaylistFormat::M3u && is_hls(&content) {
+        return Ok(vec![url.to_string()]);
+    }
+
+    let mut streams: Vec<String> = Vec::new();
+    for entry in parse(format, &content, Some(&base))? {
+        match Box::pin(resolve_with(client, &entry, depth + 1)).await {
+            Ok(nested) => {
+                for stream in nested {
+                    if !streams.contains(&stream) {
+                        streams.push(stream);
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Skipping playlist entry {}: {}", entry, e),
+        }
+    }
+
+    Ok(streams)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{respond, TestServer};
+
+    const PLS: &str = include_str!("../tests/fixtures/groovesalad.pls");
+    const M3U: &str = include_str!("../tests/fixtures/fip.m3u");
+    const XSPF: &str = include_str!("../tests/fixtures/station.xspf");
+    const ASX: &str = include_str!("../tests/fixtures/station.asx");
+    const TURKISH_ASX: &str = include_str!("../tests/fixtures/turkish.asx");
+
+    #[test]
+    fn test_parse_formats() {
+        assert_eq!(
+            parse(PlaylistFormat::Pls, PLS, None).unwrap(),
+            vec![
+                "http://ice1.somafm.com/groovesalad-256-mp3",
+                "http://ice2.somafm.com/groovesalad-256-mp3",
+                "http://ice4.somafm.com/groovesalad-256-mp3",
+            ]
+        );
+
+        let base = Url::parse("http://example.com/lists/fip.m3u").unwrap();
+        assert_eq!(
+            parse(PlaylistFormat::M3u, M3U, Some(&base)).unwrap(),
+            vec![
+                "http://icecast.radiofrance.fr/fip-hifi.aac",
+                "http://example.com/lists/fip-midfi.mp3",
+            ]
+        );
+
+        assert_eq!(
+            parse(PlaylistFormat::Xspf, XSPF, None).unwrap(),
+            vec!["http://stream.example.com/live.ogg", "http://backup.example.com/live.ogg"]
+        );
+
+        assert_eq!(
+            parse(PlaylistFormat::Asx, ASX, None).unwrap(),
+            vec!["http://stream.example.com/live?type=.mp3&id=1", "mms://stream.example.com/live"]
+        );
+        // Lower case "İ" and "Ⱥ" take a different number of bytes
+        assert_eq!(parse(PlaylistFormat::Asx, TURKISH_ASX, None).unwrap(), vec!["http://a.example.com/b"]);
+    }
+
+    #[test]
+    fn test_parse_entry_titles() {
+        let pls = parse_entries(PlaylistFormat::Pls, PLS).unwrap();
+        assert_eq!(pls[0].location, "http://ice1.somafm.com/groovesalad-256-mp3");
+        assert!(pls[0].title.as_deref().unwrap().starts_with("SomaFM: Groove Salad (#1)"));
+
+        let m3u = parse_entries(PlaylistFormat::M3u, M3U).unwrap();
+        assert_eq!(m3u[0], PlaylistEntry::new("http://icecast.radiofrance.fr/fip-hifi.aac", Some("FIP (high quality)")));
+        assert_eq!(m3u[1].title.as_deref(), Some("FIP (relative mirror)"));
+        let attributes = "#EXTM3U\n#EXTINF:-1 tvg-logo=\"a,b.png\",Radio 1\nhttp://a.example.com/\nhttp://b.example.com/\n";
+        let entries = parse_entries(PlaylistFormat::M3u, attributes).unwrap();
+        assert_eq!(entries[0].title.as_deref(), Some("Radio 1"));
+        assert_eq!(entries[1].title, None);
+
+        let xspf = parse_entries(PlaylistFormat::Xspf, XSPF).unwrap();
+        assert_eq!(xspf[0].title.as_deref(), Some("Example Station"));
+        assert_eq!(xspf[1].title, None);
+    }
+
+    #[test]
+    fn test_format_detection() {
+        assert_eq!(PlaylistFormat::from_url("https://somafm.com/groovesalad256.pls"), Some(PlaylistFormat::Pls));
+        assert_eq!(PlaylistFormat::from_url("http://example.com/live.M3U8?token=1"), Some(PlaylistFormat::M3u));
+        assert_eq!(PlaylistFormat::from_url("http://icecast.radiofrance.fr/fip-midfi.mp3"), None);
+        assert_eq!(PlaylistFormat::from_url("http://stream.example.com/live"), None);
+        assert_eq!(PlaylistFormat::sniff(PLS), Some(PlaylistFormat::Pls));
+        assert_eq!(PlaylistFormat::sniff(XSPF), Some(PlaylistFormat::Xspf));
+        assert_eq!(PlaylistFormat::sniff(ASX), Some(PlaylistFormat::Asx));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_over_http() {
+        let server = TestServer::spawn(|request, stream| match request.path.as_str() {
+            "/outer.m3u" => respond(stream, "200 OK", "audio/x-mpegurl", b"#EXTM3U\ninner.pls\nhttp://direct.example.com/a.mp3\n"),
+            "/inner.pls" => respond(stream, "200 OK", "audio/x-scpls", PLS.as_bytes()),
+            "/hls.m3u8" => respond(stream, "200 OK", "application/vnd.apple.mpegurl", b"#EXTM3U\n#EXT-X-TARGETDURATION:10\nseg1.aac\n"),
+            _ => respond(stream, "404 Not Found", "text/plain", b""),
+        });
+
+        // Nested playlists are expanded in place, keeping the order
+        assert_eq!(
+            resolve(&server.url("/outer.m3u")).await.unwrap(),
+            vec![
+                "http://ice1.somafm.com/groovesalad-256-mp3",
+                "http://ice2.somafm.com/groovesalad-256-mp3",
+                "http://ice4.somafm.com/groovesalad-256-mp3",
+                "http://direct.example.com/a.mp3",
+            ]
+        );
+
+        // HLS is left to GStreamer
+        let hls = server.url("/hls.m3u8");
+        assert_eq!(resolve(&hls).await.unwrap(), vec![hls.clone()]);
+
+        // Non-playlist URLs are not fetched at all
+        assert_eq!(resolve("http://127.0.0.1:1/live").await.unwrap(), vec!["http://127.0.0.1:1/live"]);
+
+        assert!(resolve(&server.url("/missing.pls")).await.is_err());
+
+        // Failing endpoints are skipped in favour of the next one
+        let endpoints = vec![server.url("/missing.pls"), server.url("/inner.pls")];
+        assert_eq!(resolve_all(&endpoints).await.unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,273 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Station search in a Radio Browser (<https://www.radio-browser.info>)
+//! compatible directory.
+
+use crate::channels::{self, Channel, StreamEndpoint};
+use serde::{Deserialize, Deserializer};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Directory used unless the config names another server.
+pub const DEFAULT_BASE_URL: &str = "https://all.api.radio-browser.info";
+
+/// Most stations returned by one search.
+const SEARCH_LIMIT: u32 = 50;
+
+#[derive(Debug, Error)]
+pub enum BrowseError {
+    #[error("Invalid directory URL: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+    #[error("Request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Invalid response: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Search filters, empty ones are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub name: String,
+    pub tag: String,
+    /// Country name, or a two letter country code.
+    pub country: String,
+    pub language: String,
+    pub codec: String,
+}
+
+impl SearchQuery {
+    pub fn is_empty(&self) -> bool {
+        [&self.name, &self.tag, &self.country, &self.language, &self.codec]
+            .iter()
+            .all(|value| value.trim().is_empty())
+    }
+}
+
+/// A station as listed by the directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Station {
+    #[serde(rename = "stationuuid")]
+    pub uuid: String,
+    pub name: String,
+    pub url: String,
+    /// The stream behind `url` if that is a playlist, empty if unknown.
+    pub url_resolved: String,
+    pub homepage: String,
+    pub favicon: String,
+    #[serde(deserialize_with = "deserialize_tags")]
+    pub tags: Vec<String>,
+    pub country: String,
+    #[serde(rename = "countrycode")]
+    pub country_code: String,
+    pub language: String,
+    pub codec: String,
+    /// Bitrate in kbps, 0 if unknown.
+    pub bitrate: u32,
+}
+
+impl Station {
+    /// The URL to play, preferring the resolved stream.
+    pub fn stream_url(&self) -> &str {
+        if self.url_resolved.trim().is_empty() {
+            self.url.trim()
+        } else {
+            self.url_resolved.trim()
+        }
+    }
+
+    /// Country, codec and bitrate in one line, e.g. "France · AAC 192 kbps".
+    pub fn details_line(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.country.is_empty() {
+            parts.push(self.country.clone());
+        }
+        let stream = StreamEndpoint {
+            uri: String::new(),
+            bitrate: (self.bitrate > 0).then_some(self.bitrate),
+            codec: non_empty(&self.codec),
+        };
+        if let Some(label) = stream.label() {
+            parts.push(label);
+        }
+        if !self.tags.is_empty() {
+            parts.push(self.tags.iter().take(3).cloned().collect::<Vec<_>>().join(", "));
+        }
+        parts.join(" · ")
+    }
+
+    /// A channel for this station with the given id.
+    pub fn to_channel(&self, id: String) -> Channel {
+        Channel {
+            id,
+            name: self.name.trim().to_string(),
+            streams: vec![StreamEndpoint {
+                uri: self.stream_url().to_string(),
+                bitrate: (self.bitrate > 0).then_some(self.bitrate),
+                codec: non_empty(&self.codec),
+            }],
+            favourite: false,
+            homepage: non_empty(&self.homepage),
+            favicon: non_empty(&self.favicon),
+            tags: self.tags.clone(),
+            country: non_empty(&self.country),
+            language: non_empty(&self.language).map(|language| capitalize(&language)),
+            ..Default::default()
+        }
+    }
+}
+
+/// Languages are listed in lower case, e.g. "french".
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Tags come as one comma separated string.
+fn deserialize_tags<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let tags = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+    Ok(channels::parse_tags(&tags))
+}
+
+/// Search the directory at `base_url`, most popular stations first.
+pub async fn search(base_url: &str, query: &SearchQuery) -> Result<Vec<Station>, BrowseError> {
+    let mut url = url::Url::parse(&format!("{}/json/stations/search", base_url.trim_end_matches('/')))?;
+    {
+        let mut params = url.query_pairs_mut();
+        let filters = [
+            ("name", &query.name),
+            ("tag", &query.tag),
+            ("language", &query.language),
+            ("codec", &query.codec),
+        ];
+        for (key, value) in filters {
+            if !value.trim().is_empty() {
+                params.append_pair(key, value.trim());
+            }
+        }
+        match query.country.trim() {
+            "" => {}
+            code if code.len() == 2 => {
+                params.append_pair("countrycode", &code.to_uppercase());
+            }
+            country => {
+                params.append_pair("country", country);
+            }
+        }
+        params
+            .append_pair("hidebroken", "true")
+            .append_pair("order", "clickcount")
+            .append_pair("reverse", "true")
+            .append_pair("limit", &SEARCH_LIMIT.to_string());
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .user_agent(concat!("cosmic-ext-applet-radio/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let body = client.get(url).send().await?.error_for_status()?.text().await?;
+    let stations: Vec<Station> = serde_json::from_str(&body)?;
+    Ok(stations
+        .into_iter()
+        .filter(|station| !station.name.trim().is_empty() && !station.stream_url().is_empty())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{respond, TestServer};
+    use std::sync::{Arc, Mutex};
+
+    const FIXTURE: &str = r#"[
+        {
+            "stationuuid": "96062a7b-0601-11e8-ae97-52543be04c81",
+            "name": "FIP",
+            "url": "http://icecast.radiofrance.fr/fip-hifi.aac",
+            "url_resolved": "",
+            "homepage": "https://www.radiofrance.fr/fip",
+            "favicon": "https://www.radiofrance.fr/fip.png",
+            "tags": "jazz,eclectic, world ,",
+            "country": "France",
+            "countrycode": "FR",
+            "language": "french",
+            "codec": "AAC",
+            "bitrate": 192,
+            "votes": 4000
+        },
+        {
+            "stationuuid": "960c8ba7-0601-11e8-ae97-52543be04c81",
+            "name": "SomaFM Groove Salad",
+            "url": "https://somafm.com/groovesalad256.pls",
+            "url_resolved": "https://ice2.somafm.com/groovesalad-256-mp3",
+            "homepage": "",
+            "favicon": "",
+            "tags": null,
+            "codec": "MP3",
+            "bitrate": 0
+        },
+        { "stationuuid": "broken", "name": "", "url": "" }
+    ]"#;
+
+    #[tokio::test]
+    async fn test_search_against_fixture_server() {
+        let paths = Arc::new(Mutex::new(Vec::new()));
+        let recorded = paths.clone();
+        let server = TestServer::spawn(move |request, stream| {
+            recorded.lock().unwrap().push(request.path.clone());
+            respond(stream, "200 OK", "application/json", FIXTURE.as_bytes());
+        });
+
+        let query = SearchQuery {
+            name: "fip".to_string(),
+            tag: "jazz".to_string(),
+            country: "fr".to_string(),
+            ..Default::default()
+        };
+        assert!(!query.is_empty());
+        let stations = search(&server.url("/"), &query).await.unwrap();
+
+        let path = paths.lock().unwrap()[0].clone();
+        assert!(path.starts_with("/json/stations/search?name=fip&tag=jazz&countrycode=FR&hidebroken=true"));
+        assert!(!path.contains("language"));
+
+        assert_eq!(stations.len(), 2);
+        assert_eq!(stations[0].tags, vec!["jazz", "eclectic", "world"]);
+        assert_eq!(stations[0].details_line(), "France · 192 kbps AAC · jazz, eclectic, world");
+        assert_eq!(stations[1].stream_url(), "https://ice2.somafm.com/groovesalad-256-mp3");
+        assert!(stations[1].tags.is_empty());
+
+        let channel = stations[0].to_channel("fip".to_string());
+        assert_eq!(channel.name, "FIP");
+        assert_eq!(channel.primary_uri(), "http://icecast.radiofrance.fr/fip-hifi.aac");
+        assert_eq!(channel.streams[0].label().as_deref(), Some("192 kbps AAC"));
+        assert_eq!(channel.homepage.as_deref(), Some("https://www.radiofrance.fr/fip"));
+        assert_eq!(channel.favicon.as_deref(), Some("https://www.radiofrance.fr/fip.png"));
+        assert_eq!(channel.country.as_deref(), Some("France"));
+        assert_eq!(channel.language.as_deref(), Some("French"));
+        assert_eq!(stations[1].to_channel("groove-salad".to_string()).homepage, None);
+    }
+
+    #[tokio::test]
+    async fn test_search_errors() {
+        let server = TestServer::spawn(|_, stream| {
+            respond(stream, "500 Internal Server Error", "text/plain", b"oops");
+        });
+        let query = SearchQuery::default();
+        assert!(matches!(search(&server.url(""), &query).await, Err(BrowseError::Http(_))));
+
+        let server = TestServer::spawn(|_, stream| {
+            respond(stream, "200 OK", "text/html", b"<html>");
+        });
+        assert!(matches!(search(&server.url(""), &query).await, Err(BrowseError::Json(_))));
+        assert!(matches!(search("not a url", &query).await, Err(BrowseError::InvalidUrl(_))));
+    }
+}
@@ -0,0 +1,487 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Records a stream to disk without re-encoding, optionally starting a new
+//! file whenever the ICY stream title changes.
+//!
+//! The playing stream is recorded from what the player receives, see
+//! [`record_tapped`]. Scheduled recordings of stations that aren't playing
+//! open their own connection with [`record`].
+
+use chrono::Local;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+#[derive(Error, Debug)]
+pub enum RecorderError {
+    #[error("Failed to connect to stream: {0}")]
+    Connect(#[from] reqwest::Error),
+    #[error("Failed to write recording: {0}")]
+    Write(#[from] std::io::Error),
+}
+
+/// Where and how to record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingOptions {
+    /// Directory the files are written to, created if missing.
+    pub directory: PathBuf,
+    /// Station name, used in file names.
+    pub station: String,
+    /// Start a new file, named after the track, on every title change.
+    pub split_per_track: bool,
+}
+
+/// Progress reported while recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A new file was started.
+    FileStarted(PathBuf),
+}
+
+/// A stream tapped from the player.
+#[derive(Debug)]
+pub struct TappedStream {
+    /// File extension for the stream's content type.
+    pub extension: &'static str,
+    /// Whether the stream carries ICY metadata to split on.
+    pub has_metadata: bool,
+    /// Audio and metadata in stream order, until the player lets go.
+    pub items: UnboundedReceiver<StreamItem>,
+}
+
+/// The default recording directory: `~/Music/Radio`.
+pub fn default_directory() -> PathBuf {
+    dirs::audio_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("Radio")
+}
+
+/// Record `url` until the stream ends or the future is dropped.
+///
+/// New files are announced through `events`.
+pub async fn record(
+    url: &str,
+    options: &RecordingOptions,
+    events: UnboundedSender<Event>,
+) -> Result<(), RecorderError> {
+    let client = reqwest::Client::new();
+    let mut response = client
+        .get(url)
+        .header("Icy-MetaData", "1")
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let metaint = response
+        .headers()
+        .get("icy-metaint")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|metaint| *metaint > 0);
+    let extension = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(extension_for_content_type)
+        .unwrap_or("audio");
+
+    tokio::fs::create_dir_all(&options.directory).await?;
+
+    let mut demuxer = IcyDemuxer::new(metaint);
+    let mut writer = TrackWriter::new(options, extension, events, metaint.is_some());
+    while let Some(chunk) = response.chunk().await? {
+        for item in demuxer.push(&chunk) {
+            writer.item(item).await?;
+        }
+    }
+    writer.finish().await
+}
+
+/// Record a stream tapped from the player until the player lets go of it
+/// or the future is dropped.
+///
+/// New files are announced through `events`.
+pub async fn record_tapped(
+    mut stream: TappedStream,
+    options: &RecordingOptions,
+    events: UnboundedSender<Event>,
+) -> Result<(), RecorderError> {
+    tokio::fs::create_dir_all(&options.directory).await?;
+
+    let mut writer = TrackWriter::new(options, stream.extension, events, stream.has_metadata);
+    while let Some(item) = stream.items.recv().await {
+        writer.item(item).await?;
+    }
+    writer.finish().await
+}
+
+/// Writes audio to the current file and rotates files on title changes.
+struct TrackWriter<'a> {
+    options: &'a RecordingOptions,
+    extension: &'static str,
+    events: UnboundedSender<Event>,
+    file: Option<File>,
+    title: Option<String>,
+    /// Audio held back until the first title is known, when splitting.
+    pending: Option<Vec<u8>>,
+}
+
+impl<'a> TrackWriter<'a> {
+    fn new(
+        options: &'a RecordingOptions,
+        extension: &'static str,
+        events: UnboundedSender<Event>,
+        has_metadata: bool,
+    ) -> Self {
+        Self {
+            options,
+            extension,
+            events,
+            file: None,
+            title: None,
+            // Without metadata there is nothing to split on
+            pending: if options.split_per_track && has_metadata {
+                Some(Vec::new())
+            } else {
+                None
+            },
+        }
+    }
+
+    async fn item(&mut self, item: StreamItem) -> Result<(), RecorderError> {
+        match item {
+            StreamItem::Audio(audio) => self.write(&audio).await,
+            StreamItem::Metadata(title) => self.metadata(title).await,
+        }
+    }
+
+    async fn write(&mut self, audio: &[u8]) -> Result<(), RecorderError> {
+        if let Some(pending) = &mut self.pending {
+            pending.extend_from_slice(audio);
+            return Ok(());
+        }
+        if self.file.is_none() {
+            self.open(None).await?;
+        }
+        if let Some(file) = &mut self.file {
+            file.write_all(audio).await?;
+        }
+        Ok(())
+    }
+
+    /// Handle a metadata block, `None` when it carried no title.
+    async fn metadata(&mut self, title: Option<String>) -> Result<(), RecorderError> {
+        if !self.options.split_per_track {
+            return Ok(());
+        }
+
+        if let Some(pending) = self.pending.take() {
+            // First metadata block: name the first file after it
+            self.title = title;
+            self.open(self.title.clone()).await?;
+            return self.write(&pending).await;
+        }
+
+        if title.is_some() && title != self.title {
+            self.title = title;
+            self.open(self.title.clone()).await?;
+        }
+        Ok(())
+    }
+
+    /// Close the current file and start a new one.
+    async fn open(&mut self, title: Option<String>) -> Result<(), RecorderError> {
+        if let Some(mut file) = self.file.take() {
+            file.flush().await?;
+        }
+
+        let name = match title {
+            Some(title) => format!("{} - {}", self.options.station, title),
+            None => format!(
+                "{} {}",
+                self.options.station,
+                Local::now().format("%Y-%m-%d %H-%M-%S")
+            ),
+        };
+        let path = unique_path(&self.options.directory, &sanitize_file_name(&name), self.extension);
+        tracing::info!("Recording to {}", path.display());
+
+        self.file = Some(File::create(&path).await?);
+        let _ = self.events.send(Event::FileStarted(path));
+        Ok(())
+    }
+
+    async fn finish(mut self) -> Result<(), RecorderError> {
+        // The stream ended before the first metadata block
+        if let Some(pending) = self.pending.take().filter(|pending| !pending.is_empty()) {
+            self.open(None).await?;
+            self.write(&pending).await?;
+        }
+        if let Some(mut file) = self.file.take() {
+            file.flush().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Part of a stream, as separated by [`IcyDemuxer::push`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum StreamItem {
+    Audio(Vec<u8>),
+    /// A metadata block and the stream title it carried, if any.
+    Metadata(Option<String>),
+}
+
+/// Separates audio from the metadata blocks interleaved every `icy-metaint` bytes.
+#[derive(Debug)]
+pub struct IcyDemuxer {
+    metaint: Option<usize>,
+    /// Audio bytes left before the next metadata block.
+    audio_left: usize,
+    /// The metadata block being read, with its total length.
+    metadata: Option<(usize, Vec<u8>)>,
+}
+
+impl IcyDemuxer {
+    pub fn new(metaint: Option<usize>) -> Self {
+        Self {
+            metaint,
+            audio_left: metaint.unwrap_or(0),
+            metadata: None,
+        }
+    }
+
+    pub fn push(&mut self, mut data: &[u8]) -> Vec<StreamItem> {
+        let Some(metaint) = self.metaint else {
+            return vec![StreamItem::Audio(data.to_vec())];
+        };
+
+        let mut items = Vec::new();
+        while !data.is_empty() {
+            if let Some((length, block)) = &mut self.metadata {
+                let take = (*length - block.len()).min(data.len());
+                block.extend_from_slice(&data[..take]);
+                data = &data[take..];
+                if block.len() == *length {
+                    items.push(StreamItem::Metadata(parse_stream_title(block)));
+                    self.metadata = None;
+                    self.audio_left = metaint;
+                }
+            } else if self.audio_left == 0 {
+                // Length byte, in units of 16 bytes
+                let length = usize::from(data[0]) * 16;
+                data = &data[1..];
+                if length == 0 {
+                    items.push(StreamItem::Metadata(None));
+                    self.audio_left = metaint;
+                } else {
+                    self.metadata = Some((length, Vec::with_capacity(length)));
+                }
+            } else {
+                let take = self.audio_left.min(data.len());
+                match items.last_mut() {
+                    Some(StreamItem::Audio(audio)) => audio.extend_from_slice(&data[..take]),
+                    _ => items.push(StreamItem::Audio(data[..take].to_vec())),
+                }
+                data = &data[take..];
+                self.audio_left -= take;
+            }
+        }
+        items
+    }
+}
+
+/// Extract `StreamTitle` from an ICY metadata block.
+fn parse_stream_title(block: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(block);
+    let text = text.trim_end_matches('\0');
+    let start = text.find("StreamTitle='")? + "StreamTitle='".len();
+    let rest = &text[start..];
+    // Titles may contain quotes, so look for the closing `';`
+    let end = rest.find("';").unwrap_or(rest.len());
+    let title = rest[..end].trim_end_matches('\'').trim();
+    if title.is_empty() {
+        None
+    } else {
+        Some(title.to_string())
+    }
+}
+
+/// File extension for a stream's `Content-Type`.
+pub fn extension_for_content_type(content_type: &str) -> &'static str {
+    match content_type.split(';').next().unwrap_or("").trim() {
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/aac" | "audio/aacp" | "audio/x-aac" => "aac",
+        "audio/ogg" | "application/ogg" => "ogg",
+        "audio/opus" => "opus",
+        "audio/flac" | "audio/x-flac" => "flac",
+        _ => "audio",
+    }
+}
+
+/// Replace characters that are not allowed or awkward in file names.
+fn sanitize_file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let name = name.trim().trim_start_matches('.');
+    // Stay well below the usual 255 byte limit
+    let name: String = name.chars().take(120).collect();
+    if name.is_empty() {
+        "recording".to_string()
+    } else {
+        name
+    }
+}
+
+/// `directory/name.extension`, numbered if that file already exists.
+fn unique_path(directory: &Path, name: &str, extension: &str) -> PathBuf {
+    let mut path = directory.join(format!("{}.{}", name, extension));
+    let mut counter = 2;
+    while path.exists() {
+        path = directory.join(format!("{} ({}).{}", name, counter, extension));
+        counter += 1;
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::TestServer;
+    use std::io::Write;
+    use tokio::sync::mpsc;
+
+    const METAINT: usize = 8;
+
+    /// An ICY metadata block for `title`, padded to 16 bytes.
+    fn metadata_block(title: Option<&str>) -> Vec<u8> {
+        let Some(title) = title else {
+            return vec![0];
+        };
+        let mut text = format!("StreamTitle='{}';", title).into_bytes();
+        text.resize(text.len().div_ceil(16) * 16, 0);
+        let mut block = vec![(text.len() / 16) as u8];
+        block.extend(text);
+        block
+    }
+
+    #[test]
+    fn test_demuxer_splits_audio_and_metadata() {
+        let mut stream = b"AAAAAAAA".to_vec();
+        stream.extend(metadata_block(Some("It's - Quoted")));
+        stream.extend(b"BBBBBBBB");
+        stream.extend(metadata_block(None));
+        stream.extend(b"CC");
+
+        // Feed in small chunks to cross block boundaries
+        let mut demuxer = IcyDemuxer::new(Some(METAINT));
+        let items: Vec<StreamItem> = stream.chunks(5).flat_map(|chunk| demuxer.push(chunk)).collect();
+
+        let audio: Vec<u8> = items
+            .iter()
+            .filter_map(|item| match item {
+                StreamItem::Audio(audio) => Some(audio.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        assert_eq!(audio, b"AAAAAAAABBBBBBBBCC");
+
+        let titles: Vec<&Option<String>> = items
+            .iter()
+            .filter_map(|item| match item {
+                StreamItem::Metadata(title) => Some(title),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(titles, vec![&Some("It's - Quoted".to_string()), &None]);
+    }
+
+    #[tokio::test]
+    async fn test_record_splits_per_track() {
+        // An Icecast-like server sending two tracks, then ending the stream
+        let server = TestServer::spawn(|request, stream| {
+            assert_eq!(request.header("icy-metadata"), Some("1"));
+            let _ = write!(
+                stream,
+                "HTTP/1.0 200 OK\r\nContent-Type: audio/mpeg\r\nicy-metaint: {}\r\n\r\n",
+                METAINT
+            );
+            let _ = stream.write_all(b"11111111");
+            let _ = stream.write_all(&metadata_block(Some("Artist - First")));
+            let _ = stream.write_all(b"11111111");
+            let _ = stream.write_all(&metadata_block(None));
+            let _ = stream.write_all(b"22222222");
+            let _ = stream.write_all(&metadata_block(Some("Artist - Second/Part")));
+            let _ = stream.write_all(b"2222");
+        });
+
+        let directory = std::env::temp_dir().join(format!("radio-recorder-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let options = RecordingOptions {
+            directory: directory.clone(),
+            station: "Test FM".to_string(),
+            split_per_track: true,
+        };
+
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        record(&server.url("/live"), &options, events_tx).await.unwrap();
+
+        let first = directory.join("Test FM - Artist - First.mp3");
+        let second = directory.join("Test FM - Artist - Second_Part.mp3");
+        assert_eq!(events_rx.recv().await, Some(Event::FileStarted(first.clone())));
+        assert_eq!(events_rx.recv().await, Some(Event::FileStarted(second.clone())));
+
+        // Split points fall exactly on the title changes
+        assert_eq!(std::fs::read(&first).unwrap(), b"111111111111111122222222");
+        assert_eq!(std::fs::read(&second).unwrap(), b"2222");
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_record_tapped_until_released() {
+        let directory = std::env::temp_dir().join(format!("radio-tap-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let options = RecordingOptions {
+            directory: directory.clone(),
+            station: "Test FM".to_string(),
+            split_per_track: true,
+        };
+
+        // Tapped in the middle of a track, audio waits for its title
+        let (items_tx, items) = mpsc::unbounded_channel();
+        for item in [
+            StreamItem::Audio(b"1111".to_vec()),
+            StreamItem::Metadata(Some("Artist - First".to_string())),
+            StreamItem::Audio(b"1111".to_vec()),
+            StreamItem::Metadata(Some("Artist - Second".to_string())),
+            StreamItem::Audio(b"2222".to_vec()),
+        ] {
+            items_tx.send(item).unwrap();
+        }
+        drop(items_tx);
+
+        let stream = TappedStream {
+            extension: "aac",
+            has_metadata: true,
+            items,
+        };
+        let (events_tx, _events_rx) = mpsc::unbounded_channel();
+        record_tapped(stream, &options, events_tx).await.unwrap();
+
+        assert_eq!(std::fs::read(directory.join("Test FM - Artist - First.aac")).unwrap(), b"11111111");
+        assert_eq!(std::fs::read(directory.join("Test FM - Artist - Second.aac")).unwrap(), b"2222");
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,255 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Recordings of programmes scheduled between a start and end time, stored
+//! in `scheduled_recordings.toml` next to `channels.toml`.
+
+use crate::alarms::hour_minute;
+use crate::channels;
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use thiserror::Error;
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScheduledRecording {
+    /// Name of the programme, used for the file names.
+    pub title: String,
+    /// Id of the channel to record.
+    pub channel_id: String,
+    /// Day of a one-off recording. Recordings without a date repeat on `weekdays`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    #[serde(default)]
+    pub weekdays: Vec<Weekday>,
+    /// Local start time, written as "HH:MM".
+    #[serde(with = "hour_minute")]
+    pub start: NaiveTime,
+    /// Local end time. An end before the start ends on the next day.
+    #[serde(with = "hour_minute")]
+    pub end: NaiveTime,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+impl ScheduledRecording {
+    /// Whether a recording starts on `date`.
+    fn starts_on(&self, date: NaiveDate) -> bool {
+        match self.date {
+            Some(day) => day == date,
+            None => self.weekdays.contains(&date.weekday()),
+        }
+    }
+
+    /// Start and end of the occurrence running at `now`, if any.
+    pub fn occurrence_at(&self, now: NaiveDateTime) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        // An occurrence crossing midnight started the day before
+        [now.date().checked_sub_days(Days::new(1)), Some(now.date())]
+            .into_iter()
+            .flatten()
+            .filter(|date| self.starts_on(*date))
+            .map(|date| {
+                let start = date.and_time(self.start);
+                let end_date = if self.end > self.start { date } else { date + Days::new(1) };
+                (start, end_date.and_time(self.end))
+            })
+            .find(|(start, end)| *start <= now && now < *end)
+    }
+
+    /// When it runs, e.g. "Tue 21:00–22:00" or "2024-06-04 21:00–22:00".
+    pub fn when_label(&self) -> String {
+        let days = match self.date {
+            Some(date) => date.to_string(),
+            None => crate::alarms::WEEKDAYS.iter()
+                .filter(|day| self.weekdays.contains(day))
+                .map(|day| day.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        format!("{} {}–{}", days, self.start.format("%H:%M"), self.end.format("%H:%M"))
+    }
+}
+
+/// A channel that should be recording at the time of [`due`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DueRecording {
+    pub channel_id: String,
+    /// Title of the earliest started programme on this channel.
+    pub title: String,
+    /// When the last overlapping programme on this channel ends.
+    pub end: NaiveDateTime,
+}
+
+/// Channels to record at `now`.
+///
+/// Overlapping programmes on the same channel share one recording that
+/// lasts until the later end. Programmes on different channels are
+/// recorded side by side.
+pub fn due(schedule: &[ScheduledRecording], now: NaiveDateTime) -> Vec<DueRecording> {
+    let mut running: Vec<(NaiveDateTime, &ScheduledRecording, NaiveDateTime)> = schedule
+        .iter()
+        .filter(|recording| recording.enabled)
+        .filter_map(|recording| {
+            let (start, end) = recording.occurrence_at(now)?;
+            Some((start, recording, end))
+        })
+        .collect();
+    running.sort_by_key(|(start, _, _)| *start);
+
+    let mut due: Vec<DueRecording> = Vec::new();
+    for (_, recording, end) in running {
+        match due.iter_mut().find(|due| due.channel_id == recording.channel_id) {
+            Some(due) => due.end = due.end.max(end),
+            None => due.push(DueRecording {
+                channel_id: recording.channel_id.clone(),
+                title: recording.title.clone(),
+                end,
+            }),
+        }
+    }
+    due
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct Schedule {
+    #[serde(default)]
+    pub recordings: Vec<ScheduledRecording>,
+}
+
+#[derive(Error, Debug)]
+pub enum ScheduleError {
+    #[error("Failed to access schedule file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse TOML: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("Failed to serialize TOML: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+/// Returns the full path to the scheduled_recordings.toml file
+fn schedule_file_path() -> PathBuf {
+    channels::config_dir().join("scheduled_recordings.toml")
+}
+
+/// Load the schedule from the config file, or an empty one if it doesn't exist yet.
+pub fn load_schedule() -> Result<Schedule, ScheduleError> {
+    let path = schedule_file_path();
+    if !path.exists() {
+        return Ok(Schedule::default());
+    }
+    let content = fs::read_to_string(&path)?;
+    Ok(toml::from_str(&content)?)
+}
+
+/// Save the schedule to the config file
+pub fn save_schedule(schedule: &Schedule) -> Result<(), ScheduleError> {
+    fs::create_dir_all(channels::config_dir())?;
+    let content = toml::to_string_pretty(schedule)?;
+    fs::write(schedule_file_path(), content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-06-04 was a Tuesday.
+    fn tuesday_at(time: &str) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 6, 4).unwrap().and_time(time.parse().unwrap())
+    }
+
+    fn weekly(title: &str, channel_id: &str, weekdays: &[Weekday], start: &str, end: &str) -> ScheduledRecording {
+        ScheduledRecording {
+            title: title.to_string(),
+            channel_id: channel_id.to_string(),
+            date: None,
+            weekdays: weekdays.to_vec(),
+            start: start.parse().unwrap(),
+            end: end.parse().unwrap(),
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_occurrences() {
+        let jazz = weekly("FIP jazz show", "fip-radio", &[Weekday::Tue], "21:00", "22:00");
+        assert_eq!(jazz.occurrence_at(tuesday_at("20:59")), None);
+        assert_eq!(
+            jazz.occurrence_at(tuesday_at("21:30")),
+            Some((tuesday_at("21:00"), tuesday_at("22:00")))
+        );
+        assert_eq!(jazz.occurrence_at(tuesday_at("22:00")), None);
+        assert_eq!(jazz.occurrence_at(tuesday_at("21:30") + Days::new(1)), None);
+        assert_eq!(jazz.when_label(), "Tue 21:00–22:00");
+
+        // Crossing midnight keeps recording into Wednesday
+        let night = weekly("Night", "fip-radio", &[Weekday::Tue], "23:00", "01:00");
+        let wednesday = tuesday_at("00:30") + Days::new(1);
+        assert_eq!(
+            night.occurrence_at(wednesday),
+            Some((tuesday_at("23:00"), tuesday_at("01:00") + Days::new(1)))
+        );
+        assert_eq!(night.occurrence_at(tuesday_at("00:30")), None);
+
+        // One-off recordings only run on their date
+        let once = ScheduledRecording {
+            date: Some(NaiveDate::from_ymd_opt(2024, 6, 4).unwrap()),
+            ..weekly("Special", "fip-radio", &[], "10:00", "11:00")
+        };
+        assert!(once.occurrence_at(tuesday_at("10:15")).is_some());
+        assert!(once.occurrence_at(tuesday_at("10:15") + Days::new(7)).is_none());
+    }
+
+    #[test]
+    fn test_overlapping_recordings() {
+        let mut disabled = weekly("Off", "groove-salad", &[Weekday::Tue], "20:00", "23:00");
+        disabled.enabled = false;
+        let schedule = vec![
+            weekly("Late", "fip-radio", &[Weekday::Tue], "21:30", "23:00"),
+            weekly("FIP jazz show", "fip-radio", &[Weekday::Tue], "21:00", "22:00"),
+            weekly("Other station", "groove-salad", &[Weekday::Tue], "21:45", "22:15"),
+            disabled,
+        ];
+
+        // Same station: one recording until the later end, named after the first programme
+        assert_eq!(
+            due(&schedule, tuesday_at("21:50")),
+            vec![
+                DueRecording {
+                    channel_id: "fip-radio".to_string(),
+                    title: "FIP jazz show".to_string(),
+                    end: tuesday_at("23:00"),
+                },
+                DueRecording {
+                    channel_id: "groove-salad".to_string(),
+                    title: "Other station".to_string(),
+                    end: tuesday_at("22:15"),
+                },
+            ]
+        );
+        assert_eq!(due(&schedule, tuesday_at("22:30")).len(), 1);
+        assert!(due(&schedule, tuesday_at("23:00")).is_empty());
+    }
+
+    #[test]
+    fn test_schedule_serialization() {
+        let schedule = Schedule {
+            recordings: vec![weekly("FIP jazz show", "fip-radio", &[Weekday::Tue], "21:00", "22:00")],
+        };
+        let toml_str = toml::to_string_pretty(&schedule).unwrap();
+        assert!(toml_str.contains("start = \"21:00\""));
+        assert!(!toml_str.contains("date"));
+        assert_eq!(toml::from_str::<Schedule>(&toml_str).unwrap(), schedule);
+
+        let loaded: Schedule = toml::from_str(
+            "[[recordings]]\ntitle = \"Special\"\nchannel_id = \"fip-radio\"\ndate = \"2024-06-04\"\nstart = \"10:00\"\nend = \"11:00\"\n",
+        )
+        .unwrap();
+        assert_eq!(loaded.recordings[0].date, NaiveDate::from_ymd_opt(2024, 6, 4));
+        assert!(loaded.recordings[0].enabled);
+    }
+}
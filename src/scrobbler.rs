@@ -0,0 +1,750 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Scrobbling of played tracks to ListenBrainz or Last.fm.
+//!
+//! Settings live in `scrobbling.toml` next to `channels.toml`. Listens that
+//! couldn't be submitted, e.g. while offline, wait in `scrobble_queue.json`.
+
+use crate::channels;
+use crate::metadata::NowPlaying;
+use chrono::{DateTime, TimeDelta, Utc};
+use md5::{Digest, Md5};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Name reported to the scrobbling service.
+const CLIENT_NAME: &str = "cosmic-ext-applet-radio";
+
+fn default_min_play_seconds() -> u64 {
+    90
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Service {
+    #[default]
+    ListenBrainz,
+    LastFm,
+}
+
+impl Service {
+    pub const ALL: [Service; 2] = [Service::ListenBrainz, Service::LastFm];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Service::ListenBrainz => "ListenBrainz",
+            Service::LastFm => "Last.fm",
+        }
+    }
+
+    /// API root used unless the settings name another endpoint.
+    pub fn default_endpoint(self) -> &'static str {
+        match self {
+            Service::ListenBrainz => "https://api.listenbrainz.org",
+            Service::LastFm => "https://ws.audioscrobbler.com/2.0/",
+        }
+    }
+
+    /// Most listens the service accepts in one request.
+    pub fn max_batch(self) -> usize {
+        match self {
+            Service::ListenBrainz => 100,
+            Service::LastFm => 50,
+        }
+    }
+}
+
+/// Stream titles matching `pattern` on `station_id`, or on every station
+/// if unset, aren't scrobbled. Used for ads and station IDs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Filter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub station_id: Option<String>,
+    pub pattern: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScrobbleSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub service: Service,
+    /// API root overriding the default of the service.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// ListenBrainz user token.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub token: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub api_key: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub api_secret: String,
+    /// Last.fm session key, obtained with [`lastfm_session`].
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub session_key: String,
+    /// How long a track must play before it is scrobbled.
+    #[serde(default = "default_min_play_seconds")]
+    pub min_play_seconds: u64,
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+}
+
+impl Default for ScrobbleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            service: Service::default(),
+            endpoint: None,
+            token: String::new(),
+            api_key: String::new(),
+            api_secret: String::new(),
+            session_key: String::new(),
+            min_play_seconds: default_min_play_seconds(),
+            filters: Vec::new(),
+        }
+    }
+}
+
+impl ScrobbleSettings {
+    pub fn endpoint(&self) -> &str {
+        self.endpoint.as_deref().unwrap_or(self.service.default_endpoint())
+    }
+
+    /// Whether the credentials for the selected service are filled in.
+    pub fn has_credentials(&self) -> bool {
+        match self.service {
+            Service::ListenBrainz => !self.token.is_empty(),
+            Service::LastFm => {
+                !self.api_key.is_empty() && !self.api_secret.is_empty() && !self.session_key.is_empty()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Track {
+    pub artist: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+}
+
+/// A track that was played long enough to be scrobbled.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Listen {
+    #[serde(flatten)]
+    pub track: Track,
+    /// When the track started playing.
+    pub listened_at: DateTime<Utc>,
+}
+
+#[derive(Error, Debug)]
+pub enum ScrobbleError {
+    #[error("Failed to access scrobbling file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse TOML: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("Failed to serialize TOML: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid filter: {0}")]
+    Filter(#[from] regex::Error),
+    #[error("Request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Rejected by the server ({status}): {message}")]
+    Rejected { status: u16, message: String },
+    #[error("Rejected by Last.fm (error {code}): {message}")]
+    LastFm { code: u64, message: String },
+}
+
+/// Last.fm error codes after which the same call may succeed later: the
+/// service failing or rate limiting, and authentication problems the user
+/// can fix in the settings.
+const LASTFM_TEMPORARY_ERRORS: [u64; 10] = [4, 8, 9, 10, 11, 13, 14, 16, 26, 29];
+
+impl ScrobbleError {
+    /// Whether submitting the same listens again can't succeed, e.g. because
+    /// the server considers them invalid.
+    pub fn is_permanent(&self) -> bool {
+        match self {
+            ScrobbleError::Rejected { status, .. } => {
+                (400..500).contains(status) && ![401, 403, 408, 429].contains(status)
+            }
+            ScrobbleError::LastFm { code, .. } => !LASTFM_TEMPORARY_ERRORS.contains(code),
+            _ => false,
+        }
+    }
+}
+
+/// Outcome of a failed submission that can be passed around as a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmitFailure {
+    pub message: String,
+    /// See [`ScrobbleError::is_permanent`].
+    pub permanent: bool,
+}
+
+impl From<ScrobbleError> for SubmitFailure {
+    fn from(error: ScrobbleError) -> Self {
+        Self {
+            permanent: error.is_permanent(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Decides what to scrobble as stream titles change.
+#[derive(Debug, Clone)]
+pub struct Scrobbler {
+    settings: ScrobbleSettings,
+    filters: Vec<(Option<String>, Regex)>,
+    /// The track playing and when it started.
+    playing: Option<(Track, DateTime<Utc>)>,
+}
+
+impl Scrobbler {
+    /// Fails if a filter isn't a valid regular expression.
+    pub fn new(settings: ScrobbleSettings) -> Result<Self, ScrobbleError> {
+        let filters = settings
+            .filters
+            .iter()
+            .map(|filter| Ok((filter.station_id.clone(), Regex::new(&filter.pattern)?)))
+            .collect::<Result<_, ScrobbleError>>()?;
+        Ok(Self {
+            settings,
+            filters,
+            playing: None,
+        })
+    }
+
+    pub fn settings(&self) -> &ScrobbleSettings {
+        &self.settings
+    }
+
+    /// Whether scrobbling is switched on and set up.
+    pub fn is_active(&self) -> bool {
+        self.settings.enabled && self.settings.has_credentials()
+    }
+
+    /// The stream title on `station_id` changed at `now`, or playback stopped
+    /// if `station_id` is `None`.
+    ///
+    /// Returns the listen of the previous track if it played long enough,
+    /// and the new track to announce as playing now. Tracks without an
+    /// artist or matching a filter are neither.
+    pub fn track_changed(
+        &mut self,
+        station_id: Option<&str>,
+        now_playing: &NowPlaying,
+        now: DateTime<Utc>,
+    ) -> (Option<Listen>, Option<Track>) {
+        let min_play = i64::try_from(self.settings.min_play_seconds)
+            .ok()
+            .and_then(TimeDelta::try_seconds)
+            .unwrap_or(TimeDelta::MAX);
+        let listen = self
+            .playing
+            .take()
+            .filter(|(_, started)| now - *started >= min_play)
+            .map(|(track, listened_at)| Listen { track, listened_at });
+
+        let track = station_id.and_then(|station_id| {
+            let track = Track {
+                artist: now_playing.artist.clone()?,
+                title: now_playing.title.clone()?,
+                album: now_playing.album.clone(),
+            };
+            let line = format!("{} - {}", track.artist, track.title);
+            let filtered = self.filters.iter().any(|(station, pattern)| {
+                station.as_deref().is_none_or(|station| station == station_id) && pattern.is_match(&line)
+            });
+            (!filtered).then_some(track)
+        });
+        if let Some(track) = &track {
+            self.playing = Some((track.clone(), now));
+        }
+
+        (listen, track)
+    }
+}
+
+/// Returns the full path to the scrobbling.toml file
+fn settings_file_path() -> PathBuf {
+    channels::config_dir().join("scrobbling.toml")
+}
+
+/// Returns the full path to the scrobble_queue.json file
+fn queue_file_path() -> PathBuf {
+    channels::config_dir().join("scrobble_queue.json")
+}
+
+/// Load the scrobbling settings, or defaults if there are none yet.
+pub fn load_settings() -> Result<ScrobbleSettings, ScrobbleError> {
+    let path = settings_file_path();
+    if !path.exists() {
+        return Ok(ScrobbleSettings::default());
+    }
+    let content = fs::read_to_string(&path)?;
+    Ok(toml::from_str(&content)?)
+}
+
+/// Save the scrobbling settings
+pub fn save_settings(settings: &ScrobbleSettings) -> Result<(), ScrobbleError> {
+    fs::create_dir_all(channels::config_dir())?;
+    write_settings(&settings_file_path(), settings)
+}
+
+/// Write the settings readable only by the user, as they hold the tokens.
+fn write_settings(path: &Path, settings: &ScrobbleSettings) -> Result<(), ScrobbleError> {
+    let content = toml::to_string_pretty(settings)?;
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    // The mode only applies to new files, so also tighten older ones
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+/// Load listens waiting to be submitted, oldest first.
+pub fn load_queue() -> Result<Vec<Listen>, ScrobbleError> {
+    read_queue(&queue_file_path())
+}
+
+/// Save listens waiting to be submitted
+pub fn save_queue(queue: &[Listen]) -> Result<(), ScrobbleError> {
+    fs::create_dir_all(channels::config_dir())?;
+    write_queue(&queue_file_path(), queue)
+}
+
+fn read_queue(path: &Path) -> Result<Vec<Listen>, ScrobbleError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn write_queue(path: &Path, queue: &[Listen]) -> Result<(), ScrobbleError> {
+    fs::write(path, serde_json::to_string_pretty(queue)?)?;
+    Ok(())
+}
+
+fn client() -> Result<reqwest::Client, ScrobbleError> {
+    Ok(reqwest::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .user_agent(concat!("cosmic-ext-applet-radio/", env!("CARGO_PKG_VERSION")))
+        .build()?)
+}
+
+/// Announce `track` as playing now.
+pub async fn now_playing(settings: &ScrobbleSettings, track: &Track) -> Result<(), ScrobbleError> {
+    match settings.service {
+        Service::ListenBrainz => {
+            let payload = serde_json::json!([{ "track_metadata": listenbrainz_metadata(track) }]);
+            listenbrainz_submit(settings, "playing_now", payload).await
+        }
+        Service::LastFm => {
+            let mut params = vec![
+                ("method", "track.updateNowPlaying".to_string()),
+                ("artist", track.artist.clone()),
+                ("track", track.title.clone()),
+            ];
+            if let Some(album) = &track.album {
+                params.push(("album", album.clone()));
+            }
+            lastfm_call(settings, params).await.map(|_| ())
+        }
+    }
+}
+
+/// Submit listens, at most [`Service::max_batch`] at a time.
+pub async fn submit(settings: &ScrobbleSettings, listens: &[Listen]) -> Result<(), ScrobbleError> {
+    match settings.service {
+        Service::ListenBrainz => {
+            let payload: Vec<serde_json::Value> = listens
+                .iter()
+                .map(|listen| {
+                    serde_json::json!({
+                        "listened_at": listen.listened_at.timestamp(),
+                        "track_metadata": listenbrainz_metadata(&listen.track),
+                    })
+                })
+                .collect();
+            let listen_type = if listens.len() == 1 { "single" } else { "import" };
+            listenbrainz_submit(settings, listen_type, payload.into()).await
+        }
+        Service::LastFm => {
+            let mut params = vec![("method".to_string(), "track.scrobble".to_string())];
+            for (i, listen) in listens.iter().enumerate() {
+                params.push((format!("artist[{}]", i), listen.track.artist.clone()));
+                params.push((format!("track[{}]", i), listen.track.title.clone()));
+                params.push((format!("timestamp[{}]", i), listen.listened_at.timestamp().to_string()));
+                if let Some(album) = &listen.track.album {
+                    params.push((format!("album[{}]", i), album.clone()));
+                }
+            }
+            lastfm_call(settings, params).await.map(|_| ())
+        }
+    }
+}
+
+/// Exchange a Last.fm username and password for a session key.
+pub async fn lastfm_session(
+    settings: &ScrobbleSettings,
+    username: &str,
+    password: &str,
+) -> Result<String, ScrobbleError> {
+    let params = vec![
+        ("method", "auth.getMobileSession".to_string()),
+        ("username", username.to_string()),
+        ("password", password.to_string()),
+    ];
+    let response = lastfm_call(settings, params).await?;
+    response["session"]["key"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| ScrobbleError::Rejected {
+            status: 200,
+            message: "No session key in response".to_string(),
+        })
+}
+
+fn listenbrainz_metadata(track: &Track) -> serde_json::Value {
+    let mut metadata = serde_json::json!({
+        "artist_name": track.artist,
+        "track_name": track.title,
+        "additional_info": {
+            "media_player": "COSMIC Internet Radio",
+            "submission_client": CLIENT_NAME,
+            "submission_client_version": env!("CARGO_PKG_VERSION"),
+        },
+    });
+    if let Some(album) = &track.album {
+        metadata["release_name"] = album.clone().into();
+    }
+    metadata
+}
+
+async fn listenbrainz_submit(
+    settings: &ScrobbleSettings,
+    listen_type: &str,
+    payload: serde_json::Value,
+) -> Result<(), ScrobbleError> {
+    let url = format!("{}/1/submit-listens", settings.endpoint().trim_end_matches('/'));
+    let body = serde_json::json!({ "listen_type": listen_type, "payload": payload });
+    let response = client()?
+        .post(url)
+        .header(reqwest::header::AUTHORIZATION, format!("Token {}", settings.token))
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let text = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<serde_json::Value>(&text)
+        .ok()
+        .and_then(|error| error["error"].as_str().map(str::to_string))
+        .unwrap_or(text);
+    Err(ScrobbleError::Rejected {
+        status: status.as_u16(),
+        message,
+    })
+}
+
+/// Call a signed Last.fm API method and return the JSON response.
+async fn lastfm_call<K: Into<String>>(
+    settings: &ScrobbleSettings,
+    params: Vec<(K, String)>,
+) -> Result<serde_json::Value, ScrobbleError> {
+    let mut params: Vec<(String, String)> = params.into_iter().map(|(key, value)| (key.into(), value)).collect();
+    params.push(("api_key".to_string(), settings.api_key.clone()));
+    if !settings.session_key.is_empty() {
+        params.push(("sk".to_string(), settings.session_key.clone()));
+    }
+    let signature = lastfm_signature(&params, &settings.api_secret);
+    params.push(("api_sig".to_string(), signature));
+    params.push(("format".to_string(), "json".to_string()));
+
+    let body = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(&params)
+        .finish();
+    let response = client()?
+        .post(settings.endpoint())
+        .header(reqwest::header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(body)
+        .send()
+        .await?;
+
+    let status = response.status().as_u16();
+    let json: serde_json::Value = serde_json::from_str(&response.text().await?).unwrap_or_default();
+    // Errors may come with a successful status, the code tells what went wrong
+    if let Some(code) = json["error"].as_u64() {
+        return Err(ScrobbleError::LastFm {
+            code,
+            message: json["message"].as_str().unwrap_or_default().to_string(),
+        });
+    }
+    if !(200..300).contains(&status) {
+        return Err(ScrobbleError::Rejected {
+            status,
+            message: "Unexpected response".to_string(),
+        });
+    }
+    Ok(json)
+}
+
+/// The `api_sig` of a Last.fm call: the MD5 of all parameters sorted by
+/// name, concatenated as name and value, followed by the secret.
+fn lastfm_signature(params: &[(String, String)], secret: &str) -> String {
+    let mut sorted: Vec<&(String, String)> = params.iter().filter(|(key, _)| key != "format").collect();
+    sorted.sort();
+    let mut hasher = Md5::new();
+    for (key, value) in sorted {
+        hasher.update(key.as_bytes());
+        hasher.update(value.as_bytes());
+    }
+    hasher.update(secret.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{respond, Request, TestServer};
+    use chrono::TimeZone;
+    use std::sync::{Arc, Mutex};
+
+    fn playing(artist: Option<&str>, title: &str) -> NowPlaying {
+        NowPlaying {
+            artist: artist.map(str::to_string),
+            title: Some(title.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn listen(artist: &str, title: &str, minute: u32) -> Listen {
+        Listen {
+            track: Track {
+                artist: artist.to_string(),
+                title: title.to_string(),
+                album: None,
+            },
+            listened_at: Utc.with_ymd_and_hms(2024, 3, 1, 14, minute, 0).unwrap(),
+        }
+    }
+
+    /// A server answering every request with `status` and `body`, recording the requests.
+    fn mock_server(status: &'static str, body: &'static str) -> (TestServer, Arc<Mutex<Vec<Request>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let server = TestServer::spawn(move |request, stream| {
+            recorded.lock().unwrap().push(request.clone());
+            respond(stream, status, "application/json", body.as_bytes());
+        });
+        (server, requests)
+    }
+
+    #[test]
+    fn test_scrobbler_threshold_and_filters() {
+        let settings = ScrobbleSettings {
+            enabled: true,
+            token: "token".to_string(),
+            min_play_seconds: 60,
+            filters: vec![
+                Filter {
+                    station_id: Some("groove-salad".to_string()),
+                    pattern: "(?i)^somafm".to_string(),
+                },
+                Filter {
+                    station_id: None,
+                    pattern: "Advert".to_string(),
+                },
+            ],
+            ..Default::default()
+        };
+        let mut scrobbler = Scrobbler::new(settings).unwrap();
+        assert!(scrobbler.is_active());
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 14, 0, 0).unwrap();
+        let at = |seconds| start + TimeDelta::seconds(seconds);
+
+        let (listen, track) = scrobbler.track_changed(Some("groove-salad"), &playing(Some("Tycho"), "Awake"), at(0));
+        assert_eq!(listen, None);
+        assert_eq!(track.unwrap().title, "Awake");
+
+        // Played long enough
+        let (listen, track) = scrobbler.track_changed(Some("groove-salad"), &playing(Some("Air"), "Remember"), at(200));
+        assert_eq!(listen.unwrap().listened_at, at(0));
+        assert!(track.is_some());
+
+        // Skipped after 30 seconds
+        let (listen, _) = scrobbler.track_changed(Some("groove-salad"), &playing(None, "Interlude"), at(230));
+        assert_eq!(listen, None);
+
+        // Filtered per station and everywhere, but not on other stations
+        let station_id = playing(Some("SomaFM"), "Groove Salad: a nicely chilled plate");
+        assert_eq!(scrobbler.track_changed(Some("groove-salad"), &station_id, at(300)).1, None);
+        assert!(scrobbler.track_changed(Some("fip"), &station_id, at(310)).1.is_some());
+        assert_eq!(scrobbler.track_changed(Some("fip"), &playing(Some("Advert"), "Buy now"), at(400)).1, None);
+
+        // Stopping ends the track
+        scrobbler.track_changed(Some("fip"), &playing(Some("Air"), "Alone in Kyoto"), at(500));
+        let (listen, track) = scrobbler.track_changed(None, &NowPlaying::default(), at(600));
+        assert_eq!(listen.unwrap().track.title, "Alone in Kyoto");
+        assert_eq!(track, None);
+
+        let invalid = ScrobbleSettings {
+            filters: vec![Filter { station_id: None, pattern: "(".to_string() }],
+            ..Default::default()
+        };
+        assert!(matches!(Scrobbler::new(invalid), Err(ScrobbleError::Filter(_))));
+    }
+
+    #[tokio::test]
+    async fn test_listenbrainz_against_mock_server() {
+        let (server, requests) = mock_server("200 OK", r#"{"status": "ok"}"#);
+        let settings = ScrobbleSettings {
+            token: "secret-token".to_string(),
+            endpoint: Some(server.url("/")),
+            ..Default::default()
+        };
+
+        submit(&settings, &[listen("Air", "Remember", 0), listen("Tycho", "Awake", 4)]).await.unwrap();
+        now_playing(&settings, &listen("Air", "Alone in Kyoto", 0).track).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0].path, "/1/submit-listens");
+        assert_eq!(requests[0].header("authorization"), Some("Token secret-token"));
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["listen_type"], "import");
+        assert_eq!(body["payload"][1]["listened_at"], 1709301840);
+        assert_eq!(body["payload"][1]["track_metadata"]["artist_name"], "Tycho");
+        assert_eq!(body["payload"][1]["track_metadata"]["track_name"], "Awake");
+
+        let body: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert_eq!(body["listen_type"], "playing_now");
+        assert!(body["payload"][0].get("listened_at").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lastfm_against_mock_server() {
+        let (server, requests) = mock_server("200 OK", r#"{"scrobbles": {}}"#);
+        let settings = ScrobbleSettings {
+            service: Service::LastFm,
+            endpoint: Some(server.url("/2.0/")),
+            api_key: "key".to_string(),
+            api_secret: "secret".to_string(),
+            session_key: "session".to_string(),
+            ..Default::default()
+        };
+
+        submit(&settings, &[listen("Air", "Remember", 0)]).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0].path, "/2.0/");
+        let params: Vec<(String, String)> = url::form_urlencoded::parse(&requests[0].body).into_owned().collect();
+        let param = |name: &str| params.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str());
+        assert_eq!(param("method"), Some("track.scrobble"));
+        assert_eq!(param("artist[0]"), Some("Air"));
+        assert_eq!(param("timestamp[0]"), Some("1709301600"));
+        assert_eq!(param("sk"), Some("session"));
+        // md5("api_keykeyartist[0]Airmethodtrack.scrobblesksessiontimestamp[0]1709301600track[0]Remembersecret")
+        assert_eq!(param("api_sig"), Some("2dfc6312beb9cf436567931f9ebc8302"));
+    }
+
+    #[tokio::test]
+    async fn test_lastfm_errors_are_judged_by_code() {
+        let responses = [
+            ("200 OK", r#"{"error": 11, "message": "Service Offline"}"#, false),
+            ("200 OK", r#"{"error": 16, "message": "Service temporarily unavailable"}"#, false),
+            ("429 Too Many Requests", r#"{"error": 29, "message": "Rate limit exceeded"}"#, false),
+            ("403 Forbidden", r#"{"error": 9, "message": "Invalid session key"}"#, false),
+            ("400 Bad Request", r#"{"error": 6, "message": "Invalid parameters"}"#, true),
+        ];
+        for (status, body, permanent) in responses {
+            let (server, _) = mock_server(status, body);
+            let settings = ScrobbleSettings {
+                service: Service::LastFm,
+                endpoint: Some(server.url("/2.0/")),
+                api_key: "key".to_string(),
+                api_secret: "secret".to_string(),
+                session_key: "session".to_string(),
+                ..Default::default()
+            };
+            let error = submit(&settings, &[listen("Air", "Remember", 0)]).await.unwrap_err();
+            assert!(matches!(error, ScrobbleError::LastFm { .. }), "{}", body);
+            assert_eq!(error.is_permanent(), permanent, "{}", body);
+        }
+
+        let (server, _) = mock_server("200 OK", r#"{"error": 11, "message": "Service Offline"}"#);
+        let settings = ScrobbleSettings {
+            service: Service::LastFm,
+            endpoint: Some(server.url("/2.0/")),
+            ..Default::default()
+        };
+        let error = submit(&settings, &[listen("Air", "Remember", 0)]).await.unwrap_err();
+        assert_eq!(error.to_string(), "Rejected by Last.fm (error 11): Service Offline");
+    }
+
+    #[tokio::test]
+    async fn test_failed_submissions() {
+        let (server, _) = mock_server("503 Service Unavailable", "");
+        let settings = ScrobbleSettings {
+            token: "token".to_string(),
+            endpoint: Some(server.url("")),
+            ..Default::default()
+        };
+        let error = submit(&settings, &[listen("Air", "Remember", 0)]).await.unwrap_err();
+        assert!(!error.is_permanent());
+
+        let (server, _) = mock_server("400 Bad Request", r#"{"code": 400, "error": "Invalid listen"}"#);
+        let settings = ScrobbleSettings {
+            endpoint: Some(server.url("")),
+            ..settings
+        };
+        let error = submit(&settings, &[listen("Air", "Remember", 0)]).await.unwrap_err();
+        assert!(error.is_permanent());
+        assert_eq!(error.to_string(), "Rejected by the server (400): Invalid listen");
+
+        // Queued listens survive restarts
+        let directory = std::env::temp_dir().join(format!("radio-scrobble-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("scrobble_queue.json");
+        let queue = vec![listen("Air", "Remember", 0), listen("Tycho", "Awake", 4)];
+        write_queue(&path, &queue).unwrap();
+        assert_eq!(read_queue(&path).unwrap(), queue);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_settings_are_private() {
+        let directory = std::env::temp_dir().join(format!("radio-scrobble-settings-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("scrobbling.toml");
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        write_settings(&path, &ScrobbleSettings::default()).unwrap();
+        assert_eq!(mode(&path), 0o600);
+
+        // Files saved before are tightened too
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        write_settings(&path, &ScrobbleSettings::default()).unwrap();
+        assert_eq!(mode(&path), 0o600);
+        assert!(toml::from_str::<ScrobbleSettings>(&fs::read_to_string(&path).unwrap()).is_ok());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,407 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Global keyboard shortcuts, registered as custom shortcuts in the COSMIC
+//! settings. Each one calls the applet's MPRIS service with `dbus-send`.
+
+use crate::mpris::BUS_NAME;
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Modifiers as named in the COSMIC shortcuts config.
+const MODIFIERS: [&str; 4] = ["Super", "Ctrl", "Alt", "Shift"];
+
+#[derive(Error, Debug)]
+pub enum ShortcutError {
+    #[error("Failed to access shortcuts: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid shortcut \"{0}\", expected e.g. Super+Alt+P")]
+    InvalidBinding(String),
+    #[error("Unexpected format of the COSMIC shortcuts file")]
+    UnknownFormat,
+}
+
+/// What a global shortcut does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShortcutAction {
+    PlayPause,
+    NextStation,
+    PreviousStation,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 3] = [
+        ShortcutAction::PlayPause,
+        ShortcutAction::NextStation,
+        ShortcutAction::PreviousStation,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ShortcutAction::PlayPause => "Play/Pause",
+            ShortcutAction::NextStation => "Next station",
+            ShortcutAction::PreviousStation => "Previous station",
+        }
+    }
+
+    /// Placeholder for the shortcut input.
+    pub fn example(self) -> &'static str {
+        match self {
+            ShortcutAction::PlayPause => "e.g., Super+Alt+P",
+            ShortcutAction::NextStation => "e.g., Super+Alt+Right",
+            ShortcutAction::PreviousStation => "e.g., Super+Alt+Left",
+        }
+    }
+
+    fn mpris_method(self) -> &'static str {
+        match self {
+            ShortcutAction::PlayPause => "PlayPause",
+            ShortcutAction::NextStation => "Next",
+            ShortcutAction::PreviousStation => "Previous",
+        }
+    }
+
+    /// The command the shortcut runs.
+    pub fn command(self) -> String {
+        format!(
+            "dbus-send --session --type=method_call --dest={} /org/mpris/MediaPlayer2 org.mpris.MediaPlayer2.Player.{}",
+            BUS_NAME,
+            self.mpris_method()
+        )
+    }
+}
+
+/// A key combination, e.g. "Super+Alt+P".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    /// Names from `MODIFIERS`, in that order.
+    pub modifiers: Vec<&'static str>,
+    /// Key name, e.g. "p", "F9" or "Right".
+    pub key: String,
+}
+
+impl Binding {
+    /// Parse a "+" separated binding, ignoring case of the modifiers.
+    /// Single letters are lower case in the shortcuts config.
+    pub fn parse(input: &str) -> Result<Self, ShortcutError> {
+        let invalid = || ShortcutError::InvalidBinding(input.trim().to_string());
+        let parts: Vec<&str> = input.split('+').map(str::trim).collect();
+        let (key, modifier_names) = parts.split_last().ok_or_else(invalid)?;
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(invalid());
+        }
+
+        let mut modifiers = Vec::new();
+        for name in modifier_names {
+            let modifier = match name.to_lowercase().as_str() {
+                "super" | "logo" | "meta" => "Super",
+                "ctrl" | "control" => "Ctrl",
+                "alt" => "Alt",
+                "shift" => "Shift",
+                _ => return Err(invalid()),
+            };
+            if !modifiers.contains(&modifier) {
+                modifiers.push(modifier);
+            }
+        }
+        modifiers.sort_by_key(|modifier| MODIFIERS.iter().position(|m| m == modifier));
+
+        let key = if key.chars().count() == 1 {
+            key.to_lowercase()
+        } else {
+            key.to_string()
+        };
+        Ok(Self { modifiers, key })
+    }
+
+    /// The binding as a key of the shortcuts config map.
+    fn to_ron(&self, description: &str) -> String {
+        format!(
+            "(modifiers: [{}], key: {}, description: Some({}))",
+            self.modifiers.join(", "),
+            ron_string(&self.key),
+            ron_string(description)
+        )
+    }
+}
+
+/// The file COSMIC keeps custom shortcuts in.
+fn custom_shortcuts_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("cosmic/com.system76.CosmicSettings.Shortcuts/v1/custom")
+}
+
+/// Replace the applet's custom shortcuts with `bindings`, leaving the
+/// other custom shortcuts as they are. An empty list removes them.
+pub fn register(bindings: &[(ShortcutAction, Binding)]) -> Result<(), ShortcutError> {
+    let path = custom_shortcuts_path();
+    let existing = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let Some(updated) = update_custom_shortcuts(&existing, bindings)? else {
+        return Ok(());
+    };
+
+    // cosmic-settings-daemon watches the file, so replace it in one step
+    // rather than letting it read a half-written one
+    let dir = path.parent().ok_or(ShortcutError::UnknownFormat)?;
+    fs::create_dir_all(dir)?;
+    let temp = dir.join(format!(".custom.{}.tmp", std::process::id()));
+    fs::write(&temp, updated)?;
+    if let Err(e) = fs::rename(&temp, &path) {
+        let _ = fs::remove_file(&temp);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Replace the applet's entries in the custom shortcuts map with
+/// `bindings`, keeping the other entries as they are written.
+///
+/// Returns `None` if the applet's entries already match `bindings`.
+fn update_custom_shortcuts(
+    content: &str,
+    bindings: &[(ShortcutAction, Binding)],
+) -> Result<Option<String>, ShortcutError> {
+    let entries = split_map_entries(content)?;
+    let ours: Vec<Range<usize>> = entries
+        .into_iter()
+        .filter(|range| content[range.clone()].contains(BUS_NAME))
+        .collect();
+    let wanted: Vec<String> = bindings
+        .iter()
+        .map(|(action, binding)| {
+            let description = format!("Radio: {}", action.name());
+            format!(
+                "{}: Spawn({})",
+                binding.to_ron(&description),
+                ron_string(&action.command())
+            )
+        })
+        .collect();
+
+    let current: Vec<String> = ours.iter().map(|range| normalize(&content[range.clone()])).collect();
+    if current == wanted.iter().map(|entry| normalize(entry)).collect::<Vec<_>>() {
+        return Ok(None);
+    }
+
+    // Cut out the applet's entries with their comma and line
+    let mut out = String::new();
+    let mut copied = 0;
+    for range in &ours {
+        let bytes = content.as_bytes();
+        let mut start = range.start;
+        while start > 0 && matches!(bytes[start - 1], b' ' | b'\t') {
+            start -= 1;
+        }
+        let mut end = range.end;
+        while end < bytes.len() && matches!(bytes[end], b' ' | b'\t') {
+            end += 1;
+        }
+        if bytes.get(end) == Some(&b',') {
+            end += 1;
+        }
+        while end < bytes.len() && matches!(bytes[end], b' ' | b'\t' | b'\r') {
+            end += 1;
+        }
+        if bytes.get(end) == Some(&b'\n') && (start == 0 || bytes[start - 1] == b'\n') {
+            end += 1;
+        }
+        out.push_str(&content[copied..start]);
+        copied = end;
+    }
+    out.push_str(&content[copied..]);
+
+    if wanted.is_empty() {
+        return Ok(Some(out));
+    }
+
+    // Append the new entries before the closing brace
+    let (head, tail) = match out.trim_end().strip_suffix('}') {
+        Some(head) => (head.trim_end().to_string(), out[out.trim_end().len()..].to_string()),
+        None => ("{".to_string(), String::new()),
+    };
+    let mut updated = head;
+    if !updated.ends_with('{') && !updated.ends_with(',') {
+        updated.push(',');
+    }
+    for entry in wanted {
+        updated.push_str(&format!("\n    {},", entry));
+    }
+    updated.push_str("\n}");
+    updated.push_str(&tail);
+    Ok(Some(updated))
+}
+
+/// An entry without whitespace and trailing commas, to compare entries
+/// regardless of how they are formatted.
+fn normalize(entry: &str) -> String {
+    let mut out = String::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in entry.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            out.push(c);
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            ')' | ']' | '}' => {
+                if out.ends_with(',') {
+                    out.pop();
+                }
+                out.push(c);
+            }
+            c if c.is_whitespace() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Byte ranges of the `key: value` entries of a RON map, trimmed. An
+/// empty file is an empty map.
+fn split_map_entries(content: &str) -> Result<Vec<Range<usize>>, ShortcutError> {
+    let trimmed = content.trim_start();
+    if trimmed.trim_end().is_empty() {
+        return Ok(Vec::new());
+    }
+    let offset = content.len() - trimmed.len() + 1;
+    let inner = trimmed
+        .trim_end()
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+        .ok_or(ShortcutError::UnknownFormat)?;
+
+    let mut entries = Vec::new();
+    let mut push = |start: usize, end: usize| {
+        let entry = &inner[start..end];
+        let leading = entry.len() - entry.trim_start().len();
+        if !entry.trim().is_empty() {
+            entries.push(offset + start + leading..offset + start + leading + entry.trim().len());
+        }
+    };
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.checked_sub(1).ok_or(ShortcutError::UnknownFormat)?,
+            ',' if depth == 0 => {
+                push(start, i);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 || in_string {
+        return Err(ShortcutError::UnknownFormat);
+    }
+    push(start, inner.len());
+    Ok(entries)
+}
+
+fn ron_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bindings() {
+        let binding = Binding::parse("alt + super+P").unwrap();
+        assert_eq!(binding.modifiers, vec!["Super", "Alt"]);
+        assert_eq!(binding.key, "p");
+        assert_eq!(Binding::parse("Ctrl+Shift+F9").unwrap().key, "F9");
+        assert_eq!(Binding::parse("XF86AudioNext").unwrap().modifiers, Vec::<&str>::new());
+
+        assert!(Binding::parse("").is_err());
+        assert!(Binding::parse("Super+").is_err());
+        assert!(Binding::parse("Hyper+P").is_err());
+    }
+
+    #[test]
+    fn test_update_custom_shortcuts() {
+        let existing = r#"{
+    (
+        modifiers: [
+            Super,
+        ],
+        key: "t",
+        description: Some("Terminal, (tabbed)"),
+    ): Spawn("cosmic-term"),
+}
+"#;
+        let entries = |content: &str| -> Vec<String> {
+            split_map_entries(content)
+                .unwrap()
+                .into_iter()
+                .map(|range| content[range].to_string())
+                .collect()
+        };
+        let terminal = entries(existing).remove(0);
+
+        let bindings = vec![(ShortcutAction::NextStation, Binding::parse("Super+Alt+Right").unwrap())];
+        let updated = update_custom_shortcuts(existing, &bindings).unwrap().unwrap();
+        // Other shortcuts keep their formatting
+        assert!(updated.starts_with(existing.trim_end().strip_suffix('}').unwrap()));
+        assert!(updated.ends_with("}\n"));
+        let added = entries(&updated);
+        assert_eq!(added.len(), 2);
+        assert_eq!(added[0], terminal);
+        assert!(added[1].starts_with(
+            "(modifiers: [Super, Alt], key: \"Right\", description: Some(\"Radio: Next station\")): Spawn(\"dbus-send"
+        ));
+        assert!(added[1].ends_with("org.mpris.MediaPlayer2.Player.Next\")"));
+
+        // Nothing changes if the applet's shortcuts are the same, however they are formatted
+        assert_eq!(update_custom_shortcuts(&updated, &bindings).unwrap(), None);
+        let reformatted = updated.replace("[Super, Alt]", "[\n            Super,\n            Alt,\n        ]");
+        assert_eq!(update_custom_shortcuts(&reformatted, &bindings).unwrap(), None);
+
+        // Registering again replaces the applet's shortcuts
+        let bindings = vec![(ShortcutAction::PlayPause, Binding::parse("Super+Alt+P").unwrap())];
+        let replaced = update_custom_shortcuts(&updated, &bindings).unwrap().unwrap();
+        let entries_replaced = entries(&replaced);
+        assert_eq!(entries_replaced.len(), 2);
+        assert_eq!(entries_replaced[0], terminal);
+        assert!(entries_replaced[1].contains("Player.PlayPause"));
+
+        // Removing them leaves the file as it was
+        assert_eq!(update_custom_shortcuts(&replaced, &[]).unwrap().as_deref(), Some(existing));
+
+        assert_eq!(update_custom_shortcuts("", &[]).unwrap(), None);
+        assert_eq!(
+            update_custom_shortcuts("{}", &bindings).unwrap().unwrap(),
+            format!("{{\n    {},\n}}", entries(&replaced)[1])
+        );
+        assert!(matches!(update_custom_shortcuts("[1, 2]", &[]), Err(ShortcutError::UnknownFormat)));
+    }
+}
@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Sleep timer that fades playback out and then stops it.
+
+use std::time::{Duration, Instant};
+
+/// How long before the deadline the volume starts fading out.
+pub const FADE_DURATION: Duration = Duration::from_secs(60);
+
+/// Timer lengths offered in the popup, in minutes.
+pub const PRESET_MINUTES: [u64; 3] = [15, 30, 60];
+
+/// When to stop playback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SleepTimer {
+    /// Stop at this point in time.
+    Until(Instant),
+    /// Stop once the track with this title has ended.
+    EndOfTrack { title: Option<String> },
+}
+
+impl SleepTimer {
+    /// A timer expiring `duration` after `now`.
+    pub fn after(duration: Duration, now: Instant) -> Self {
+        SleepTimer::Until(now + duration)
+    }
+
+    /// A timer expiring when the current track, `title`, ends.
+    pub fn end_of_track(title: Option<String>) -> Self {
+        SleepTimer::EndOfTrack { title }
+    }
+
+    /// Time left at `now`, if the timer has a deadline.
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        match self {
+            SleepTimer::Until(deadline) => Some(deadline.saturating_duration_since(now)),
+            SleepTimer::EndOfTrack { .. } => None,
+        }
+    }
+
+    /// Whether playback should stop at `now`.
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.remaining(now).is_some_and(|remaining| remaining.is_zero())
+    }
+
+    /// The track title changed to `title`.
+    ///
+    /// Returns whether playback should stop. A timer set before any title
+    /// was known waits for the end of the first track it sees.
+    pub fn track_changed(&mut self, title: Option<String>) -> bool {
+        let SleepTimer::EndOfTrack { title: current } = self else {
+            return false;
+        };
+        match current {
+            None => {
+                *current = title;
+                false
+            }
+            Some(current) => title.as_ref() != Some(current),
+        }
+    }
+
+    /// Factor to scale the volume by at `now`, from 1.0 down to 0.0 over the
+    /// last [`FADE_DURATION`].
+    pub fn volume_factor(&self, now: Instant) -> f64 {
+        match self.remaining(now) {
+            Some(remaining) if remaining < FADE_DURATION => {
+                remaining.as_secs_f64() / FADE_DURATION.as_secs_f64()
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_fades_out_then_expires() {
+        let start = Instant::now();
+        let timer = SleepTimer::after(Duration::from_secs(15 * 60), start);
+
+        assert_eq!(timer.remaining(start), Some(Duration::from_secs(15 * 60)));
+        assert_eq!(timer.volume_factor(start), 1.0);
+        assert!(!timer.is_expired(start));
+
+        // Full volume until the last minute
+        let fade_start = start + Duration::from_secs(14 * 60);
+        assert_eq!(timer.volume_factor(fade_start), 1.0);
+        assert_eq!(timer.volume_factor(fade_start + Duration::from_secs(15)), 0.75);
+        assert_eq!(timer.volume_factor(fade_start + Duration::from_secs(45)), 0.25);
+
+        let end = start + Duration::from_secs(15 * 60);
+        assert_eq!(timer.volume_factor(end), 0.0);
+        assert!(timer.is_expired(end));
+        assert!(timer.is_expired(end + Duration::from_secs(5)));
+        assert_eq!(timer.remaining(end + Duration::from_secs(5)), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_end_of_track_timer() {
+        let now = Instant::now();
+        let mut timer = SleepTimer::end_of_track(Some("Air - Alone in Kyoto".to_string()));
+
+        // No deadline, so no fading
+        assert_eq!(timer.remaining(now), None);
+        assert_eq!(timer.volume_factor(now), 1.0);
+        assert!(!timer.is_expired(now + Duration::from_secs(3600)));
+
+        assert!(!timer.track_changed(Some("Air - Alone in Kyoto".to_string())));
+        assert!(timer.clone().track_changed(None));
+        assert!(timer.track_changed(Some("Air - Cherry Blossom Girl".to_string())));
+
+        // Without a known title the first title is the track to finish
+        let mut timer = SleepTimer::end_of_track(None);
+        assert!(!timer.track_changed(None));
+        assert!(!timer.track_changed(Some("Station ID".to_string())));
+        assert!(timer.track_changed(Some("Air - Remember".to_string())));
+
+        // Timers with a deadline ignore track changes
+        let mut timer = SleepTimer::after(Duration::from_secs(60), now);
+        assert!(!timer.track_changed(Some("Air - Remember".to_string())));
+    }
+}
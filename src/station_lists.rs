@@ -0,0 +1,389 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Import and export of station lists as M3U, PLS, XSPF and OPML files.
+
+use crate::channels::{self, Channel, StreamEndpoint};
+use crate::playlist::{self, PlaylistError, PlaylistFormat};
+use chrono::Local;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use url::Url;
+
+/// File formats station lists can be imported from and exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListFormat {
+    M3u,
+    Pls,
+    Xspf,
+    Opml,
+}
+
+impl ListFormat {
+    pub const ALL: [ListFormat; 4] = [ListFormat::M3u, ListFormat::Pls, ListFormat::Xspf, ListFormat::Opml];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ListFormat::M3u => "M3U",
+            ListFormat::Pls => "PLS",
+            ListFormat::Xspf => "XSPF",
+            ListFormat::Opml => "OPML",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ListFormat::M3u => "m3u",
+            ListFormat::Pls => "pls",
+            ListFormat::Xspf => "xspf",
+            ListFormat::Opml => "opml",
+        }
+    }
+
+    /// Guess the format from the file extension, then from the content.
+    fn detect(path: &Path, content: &str) -> Option<Self> {
+        let by_extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+        match by_extension.as_deref() {
+            Some("m3u" | "m3u8") => return Some(ListFormat::M3u),
+            Some("pls") => return Some(ListFormat::Pls),
+            Some("xspf") => return Some(ListFormat::Xspf),
+            Some("opml") => return Some(ListFormat::Opml),
+            _ => {}
+        }
+
+        let start: String = content.trim_start().chars().take(512).collect::<String>().to_lowercase();
+        if start.contains("<opml") {
+            return Some(ListFormat::Opml);
+        }
+        match PlaylistFormat::sniff(content)? {
+            PlaylistFormat::Pls => Some(ListFormat::Pls),
+            PlaylistFormat::M3u => Some(ListFormat::M3u),
+            PlaylistFormat::Xspf => Some(ListFormat::Xspf),
+            PlaylistFormat::Asx => None,
+        }
+    }
+}
+
+/// What to do with the existing stations on import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportMode {
+    /// Add new stations after the existing ones.
+    #[default]
+    Merge,
+    /// Replace the list with the imported stations.
+    Replace,
+}
+
+#[derive(Error, Debug)]
+pub enum StationListError {
+    #[error("Failed to access file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Playlist(#[from] PlaylistError),
+    #[error("Failed to parse OPML: {0}")]
+    Opml(#[from] roxmltree::Error),
+    #[error("Unknown file format, expected M3U, PLS, XSPF or OPML")]
+    UnknownFormat,
+    #[error("File contains no stations")]
+    Empty,
+}
+
+/// A station read from a list file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedStation {
+    pub name: String,
+    pub url: String,
+}
+
+/// The outcome of an import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ImportSummary {
+    /// Stations in the file that weren't in the list yet.
+    pub added: usize,
+    /// Stations in the file that were already listed, or listed twice.
+    pub duplicates: usize,
+}
+
+/// Parse a station list. Entries without an absolute URL are skipped.
+pub fn parse(format: ListFormat, content: &str) -> Result<Vec<ImportedStation>, StationListError> {
+    let entries: Vec<(Option<String>, String)> = match format {
+        ListFormat::M3u | ListFormat::Pls | ListFormat::Xspf => {
+            let playlist_format = match format {
+                ListFormat::M3u => PlaylistFormat::M3u,
+                ListFormat::Pls => PlaylistFormat::Pls,
+                _ => PlaylistFormat::Xspf,
+            };
+            playlist::parse_entries(playlist_format, content)?
+                .into_iter()
+                .map(|entry| (entry.title, entry.location))
+                .collect()
+        }
+        ListFormat::Opml => parse_opml(content)?,
+    };
+
+    let stations: Vec<ImportedStation> = entries
+        .into_iter()
+        .filter_map(|(title, location)| {
+            let url = Url::parse(location.trim()).ok()?;
+            let name = title.unwrap_or_else(|| url.host_str().unwrap_or(url.as_str()).to_string());
+            Some(ImportedStation {
+                name,
+                url: location.trim().to_string(),
+            })
+        })
+        .collect();
+    if stations.is_empty() {
+        return Err(StationListError::Empty);
+    }
+    Ok(stations)
+}
+
+/// `<outline>` elements carrying a stream URL, as written by podcast and radio apps.
+fn parse_opml(content: &str) -> Result<Vec<(Option<String>, String)>, StationListError> {
+    let document = roxmltree::Document::parse(content)?;
+    Ok(document
+        .descendants()
+        .filter(|node| node.has_tag_name("outline"))
+        .filter_map(|node| {
+            let url = node.attribute("URL").or_else(|| node.attribute("url"))?;
+            let title = node
+                .attribute("text")
+                .or_else(|| node.attribute("title"))
+                .map(str::trim)
+                .filter(|title| !title.is_empty())
+                .map(str::to_string);
+            Some((title, url.to_string()))
+        })
+        .collect())
+}
+
+/// Read a station list file, detecting its format.
+pub fn read(path: &Path) -> Result<Vec<ImportedStation>, StationListError> {
+    let content = fs::read_to_string(path)?;
+    let format = ListFormat::detect(path, &content).ok_or(StationListError::UnknownFormat)?;
+    parse(format, &content)
+}
+
+/// URLs differing only in case of scheme or host are the same station.
+fn url_key(url: &str) -> String {
+    Url::parse(url.trim()).map(String::from).unwrap_or_else(|_| url.trim().to_string())
+}
+
+/// Combine the existing channels with imported stations, skipping URLs
+/// that are already listed.
+///
+/// When replacing, stations that were already listed keep their channel,
+/// so ids, favourites and details survive a round trip.
+pub fn import(
+    existing: &[Channel],
+    stations: Vec<ImportedStation>,
+    mode: ImportMode,
+) -> (Vec<Channel>, ImportSummary) {
+    let mut channels: Vec<Channel> = match mode {
+        ImportMode::Merge => existing.to_vec(),
+        ImportMode::Replace => Vec::new(),
+    };
+    let mut summary = ImportSummary::default();
+
+    for station in stations {
+        let key = url_key(&station.url);
+        let listed = |channel: &Channel| channel.streams.iter().any(|stream| url_key(&stream.uri) == key);
+        if channels.iter().any(&listed) {
+            summary.duplicates += 1;
+            continue;
+        }
+        if mode == ImportMode::Replace
+            && let Some(channel) = existing.iter().find(|channel| listed(channel))
+        {
+            channels.push(channel.clone());
+            summary.duplicates += 1;
+            continue;
+        }
+
+        let Some(id) = channels::unique_id(&station.name, &channels)
+            .or_else(|| channels::unique_id("station", &channels))
+        else {
+            continue;
+        };
+        channels.push(Channel {
+            id,
+            name: station.name,
+            streams: vec![StreamEndpoint::new(station.url)],
+            ..Default::default()
+        });
+        summary.added += 1;
+    }
+
+    (channels, summary)
+}
+
+/// Render channels as a station list, one entry per station with its primary stream.
+pub fn to_string(format: ListFormat, channels: &[Channel]) -> String {
+    let mut out = String::new();
+    match format {
+        ListFormat::M3u => {
+            out.push_str("#EXTM3U\n");
+            for channel in channels {
+                out.push_str(&format!("#EXTINF:-1,{}\n{}\n", one_line(&channel.name), channel.primary_uri()));
+            }
+        }
+        ListFormat::Pls => {
+            out.push_str("[playlist]\n");
+            for (i, channel) in channels.iter().enumerate() {
+                let n = i + 1;
+                out.push_str(&format!(
+                    "File{n}={}\nTitle{n}={}\nLength{n}=-1\n",
+                    channel.primary_uri(),
+                    one_line(&channel.name)
+                ));
+            }
+            out.push_str(&format!("NumberOfEntries={}\nVersion=2\n", channels.len()));
+        }
+        ListFormat::Xspf => {
+            out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+            out.push_str("<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n");
+            out.push_str("  <title>Radio Stations</title>\n  <trackList>\n");
+            for channel in channels {
+                out.push_str(&format!(
+                    "    <track>\n      <location>{}</location>\n      <title>{}</title>\n    </track>\n",
+                    escape_xml(channel.primary_uri()),
+                    escape_xml(&channel.name)
+                ));
+            }
+            out.push_str("  </trackList>\n</playlist>\n");
+        }
+        ListFormat::Opml => {
+            out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">\n");
+            out.push_str("  <head>\n    <title>Radio Stations</title>\n  </head>\n  <body>\n");
+            for channel in channels {
+                out.push_str(&format!(
+                    "    <outline type=\"audio\" text=\"{}\" URL=\"{}\"/>\n",
+                    escape_xml(&channel.name),
+                    escape_xml(channel.primary_uri())
+                ));
+            }
+            out.push_str("  </body>\n</opml>\n");
+        }
+    }
+    out
+}
+
+/// Write channels to a new file in `directory`, returning its path.
+pub fn export(channels: &[Channel], format: ListFormat, directory: &Path) -> Result<PathBuf, StationListError> {
+    fs::create_dir_all(directory)?;
+    let path = directory.join(format!(
+        "radio-stations-{}.{}",
+        Local::now().format("%Y-%m-%d-%H%M%S"),
+        format.extension()
+    ));
+    fs::write(&path, to_string(format, channels))?;
+    Ok(path)
+}
+
+/// Names end up on a single line in M3U and PLS files.
+fn one_line(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(id: &str, name: &str, uri: &str) -> Channel {
+        Channel {
+            id: id.to_string(),
+            name: name.to_string(),
+            streams: vec![StreamEndpoint::new(uri)],
+            ..Default::default()
+        }
+    }
+
+    fn stations() -> Vec<Channel> {
+        vec![
+            channel("fip", "FIP", "http://icecast.radiofrance.fr/fip-hifi.aac"),
+            channel("groove-salad", "Groove Salad & <Friends>", "https://ice2.somafm.com/groovesalad-256-mp3"),
+        ]
+    }
+
+    #[test]
+    fn test_round_trip_all_formats() {
+        let channels = stations();
+        for format in ListFormat::ALL {
+            let content = to_string(format, &channels);
+            let path = Path::new("stations").with_extension(format.extension());
+            assert_eq!(ListFormat::detect(&path, &content), Some(format));
+            assert_eq!(ListFormat::detect(Path::new("stations.txt"), &content), Some(format), "{}", content);
+
+            let imported = parse(format, &content).unwrap();
+            let expected: Vec<ImportedStation> = channels
+                .iter()
+                .map(|c| ImportedStation {
+                    name: c.name.clone(),
+                    url: c.primary_uri().to_string(),
+                })
+                .collect();
+            assert_eq!(imported, expected, "{}", format.name());
+        }
+    }
+
+    #[test]
+    fn test_parse_opml_and_fallback_names() {
+        let opml = r#"<opml version="1.0"><body>
+            <outline text="Jazz">
+                <outline type="audio" text="TSF Jazz" URL="http://tsfjazz.ice.infomaniak.ch/tsfjazz-high.mp3"/>
+                <outline type="link" title="No stream"/>
+            </outline>
+            <outline type="audio" url="https://stream.example.com/live"/>
+        </body></opml>"#;
+        let imported = parse(ListFormat::Opml, opml).unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].name, "TSF Jazz");
+        assert_eq!(imported[1].name, "stream.example.com");
+
+        // Relative entries can't be played from a local file
+        assert!(matches!(parse(ListFormat::M3u, "#EXTM3U\nlive.mp3\n"), Err(StationListError::Empty)));
+    }
+
+    #[test]
+    fn test_import_merge_and_replace() {
+        let existing = stations();
+        let imported = vec![
+            ImportedStation {
+                name: "FIP (copy)".to_string(),
+                url: "HTTP://ICECAST.RADIOFRANCE.FR/fip-hifi.aac".to_string(),
+            },
+            ImportedStation {
+                name: "FIP".to_string(),
+                url: "http://icecast.radiofrance.fr/fip-midfi.mp3".to_string(),
+            },
+            ImportedStation {
+                name: "FIP again".to_string(),
+                url: "http://icecast.radiofrance.fr/fip-midfi.mp3".to_string(),
+            },
+        ];
+
+        let (merged, summary) = import(&existing, imported.clone(), ImportMode::Merge);
+        assert_eq!(summary, ImportSummary { added: 1, duplicates: 2 });
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[2].id, "fip-2");
+        assert_eq!(merged[2].primary_uri(), "http://icecast.radiofrance.fr/fip-midfi.mp3");
+
+        let mut favourite = existing.clone();
+        favourite[0].favourite = true;
+        let (replaced, summary) = import(&favourite, imported, ImportMode::Replace);
+        assert_eq!(summary, ImportSummary { added: 1, duplicates: 2 });
+        let ids: Vec<&str> = replaced.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["fip", "fip-2"]);
+        assert!(replaced[0].favourite);
+    }
+}
@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! A minimal HTTP server on localhost for tests.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+
+/// A parsed HTTP request.
+#[derive(Debug, Clone, Default)]
+pub struct Request {
+    /// Path including the query string.
+    pub path: String,
+    /// Header names are lowercased.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Value of the first header called `name` (lowercase).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Serves every connection on a background thread until the test exits.
+pub struct TestServer {
+    addr: SocketAddr,
+}
+
+impl TestServer {
+    /// Start a server that calls `handler` for each request.
+    ///
+    /// The handler writes the raw response, so it can also cut the connection early.
+    pub fn spawn<F>(handler: F) -> Self
+    where
+        F: Fn(&Request, &mut TcpStream) + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind test server");
+        let addr = listener.local_addr().expect("Test server has no address");
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let request = read_request(&stream).unwrap_or_default();
+                handler(&request, &mut stream);
+                let _ = stream.flush();
+            }
+        });
+
+        Self { addr }
+    }
+
+    /// URL for `path` on this server.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+}
+
+/// Write a complete response and let the connection close.
+pub fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) {
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    let _ = stream.write_all(body);
+}
+
+/// Read the request line, headers and body.
+fn read_request(stream: &TcpStream) -> Option<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    let mut request = Request {
+        path: request_line.split_whitespace().nth(1)?.to_string(),
+        ..Default::default()
+    };
+
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            request
+                .headers
+                .push((key.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    let length = request
+        .header("content-length")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body).ok()?;
+
+    Some(request)
+}